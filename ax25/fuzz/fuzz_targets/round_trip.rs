@@ -0,0 +1,11 @@
+#![no_main]
+
+use ax25::frame::Ax25Frame;
+use libfuzzer_sys::fuzz_target;
+
+// Any frame that parses successfully must encode back to exactly the same bytes
+fuzz_target!(|data: &[u8]| {
+    if let Ok(frame) = Ax25Frame::from_bytes(data) {
+        assert_eq!(frame.to_bytes(), data, "{:?}", frame);
+    }
+});
@@ -0,0 +1,524 @@
+use crate::frame::{Ax25Frame, FrameContent, ProtocolIdentifier};
+use alloc::string::{String, ToString};
+
+/// The structured content of an APRS packet, as carried in the information field of a
+/// UI frame.
+///
+/// Only some of the APRS data types are recognised. Others are not parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AprsPacket {
+    /// A text message addressed to another station
+    Message(Message),
+    /// Acknowledgement that the message with this ID was received
+    Ack {
+        addressee: String,
+        id: String,
+    },
+    /// Notice that the message with this ID was received but cannot be handled
+    Reject {
+        addressee: String,
+        id: String,
+    },
+    Position(Position),
+    Status(Status),
+    /// A Mic-E position report. These can only be recognised from a whole frame, since
+    /// part of the report is carried in the destination address.
+    MicE(MicE),
+}
+
+/// An APRS message, `:ADDRESSEE:text{id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// The receiving station, with the padding spaces removed
+    pub addressee: String,
+    pub text: String,
+    /// Present if the sender would like an acknowledgement
+    pub id: Option<String>,
+}
+
+/// A position report, in either the uncompressed or compressed format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    /// Degrees north of the equator; negative for the southern hemisphere
+    pub latitude: f64,
+    /// Degrees east of Greenwich; negative for the western hemisphere
+    pub longitude: f64,
+    /// `/` for the primary symbol table, `\` for the alternate or an overlay character
+    pub symbol_table: char,
+    pub symbol_code: char,
+    /// Whether the station can receive APRS messages
+    pub messaging: bool,
+    /// The timestamp exactly as sent, such as `092345z`, if the report has one
+    pub timestamp: Option<String>,
+    /// Anything following the position, which may include course, speed or altitude
+    pub comment: String,
+}
+
+/// A status report, `>text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    /// The timestamp exactly as sent, such as `092345z`, if the report has one
+    pub timestamp: Option<String>,
+    pub text: String,
+}
+
+/// A Mic-E position report, which encodes the latitude and a message in the
+/// destination callsign and the rest of the position in the information field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MicE {
+    /// Degrees north of the equator; negative for the southern hemisphere
+    pub latitude: f64,
+    /// Degrees east of Greenwich; negative for the western hemisphere
+    pub longitude: f64,
+    /// Speed over ground in knots
+    pub speed: u16,
+    /// Degrees clockwise from north, from 1 to 360, or 0 if not known
+    pub course: u16,
+    /// `/` for the primary symbol table, `\` for the alternate or an overlay character
+    pub symbol_table: char,
+    pub symbol_code: char,
+    pub message: MicEMessage,
+    /// Metres above sea level, if given
+    pub altitude: Option<i32>,
+    /// Anything following the position with the altitude removed, which often begins
+    /// with a character identifying the type of radio
+    pub comment: String,
+}
+
+/// The status message chosen by a Mic-E station, from the three message bits in its
+/// destination callsign.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MicEMessage {
+    /// One of the standard messages M0 to M6, such as 0 for "Off Duty" or 1 for
+    /// "En Route"
+    Standard(u8),
+    /// One of the custom messages C0 to C6
+    Custom(u8),
+    Emergency,
+    /// The message bits mixed standard and custom encodings
+    Unknown,
+}
+
+impl MicE {
+    /// Decode a Mic-E report from the destination callsign and information field of
+    /// the frame that carried it. The information field includes the data type
+    /// character, normally `` ` `` or `'`.
+    pub fn parse(destination: &str, info: &[u8]) -> Option<MicE> {
+        let dest = destination.as_bytes();
+        if dest.len() != 6 || info.len() < 9 || !matches!(info[0], b'`' | b'\'' | 0x1c | 0x1d) {
+            return None;
+        }
+
+        // Each destination character is a latitude digit that also carries one bit
+        let mut digits = [0u8; 6];
+        let mut standard_bits = 0;
+        let mut custom_bits = 0;
+        for (i, &c) in dest.iter().enumerate() {
+            let (digit, standard, custom) = match c {
+                b'0'..=b'9' => (c - b'0', false, false),
+                b'A'..=b'J' => (c - b'A', false, true),
+                b'K' => (0, false, true),
+                b'L' => (0, false, false),
+                b'P'..=b'Y' => (c - b'P', true, false),
+                b'Z' => (0, true, false),
+                _ => return None,
+            };
+            digits[i] = digit;
+            if i < 3 {
+                standard_bits |= (standard as u8) << (2 - i);
+                custom_bits |= (custom as u8) << (2 - i);
+            }
+        }
+        let message = match (standard_bits, custom_bits) {
+            (0, 0) => MicEMessage::Emergency,
+            (bits, 0) => MicEMessage::Standard(7 - bits),
+            (0, bits) => MicEMessage::Custom(7 - bits),
+            _ => MicEMessage::Unknown,
+        };
+        let is_set = |c: u8| c >= b'P';
+        let latitude = (digits[0] * 10 + digits[1]) as f64
+            + ((digits[2] * 10 + digits[3]) as f64 + (digits[4] * 10 + digits[5]) as f64 / 100.0)
+                / 60.0;
+        let latitude = if is_set(dest[3]) { latitude } else { -latitude };
+
+        let field = |i: usize| info[i].checked_sub(28).map(u16::from);
+        let mut degrees = field(1)?;
+        if is_set(dest[4]) {
+            degrees += 100;
+        }
+        match degrees {
+            180..=189 => degrees -= 80,
+            190..=199 => degrees -= 190,
+            _ => {}
+        }
+        let mut minutes = field(2)?;
+        if minutes >= 60 {
+            minutes -= 60;
+        }
+        let longitude = degrees as f64 + (minutes as f64 + field(3)? as f64 / 100.0) / 60.0;
+        let longitude = if is_set(dest[5]) {
+            -longitude
+        } else {
+            longitude
+        };
+
+        let (sp, dc, se) = (field(4)?, field(5)?, field(6)?);
+        let mut speed = sp * 10 + dc / 10;
+        if speed >= 800 {
+            speed -= 800;
+        }
+        let mut course = (dc % 10) * 100 + se;
+        if course >= 400 {
+            course -= 400;
+        }
+
+        if !is_valid_position(latitude, longitude) {
+            return None;
+        }
+
+        let rest = String::from_utf8_lossy(&info[9..]);
+        let (altitude, comment) = parse_mic_e_altitude(&rest);
+        Some(MicE {
+            latitude,
+            longitude,
+            speed,
+            course,
+            symbol_table: info[8] as char,
+            symbol_code: info[7] as char,
+            message,
+            altitude,
+            comment,
+        })
+    }
+}
+
+/// The altitude is three base-91 digits followed by `}`, either at the start of the
+/// comment or after a single character identifying the radio.
+fn parse_mic_e_altitude(rest: &str) -> (Option<i32>, String) {
+    for start in [0, 1] {
+        let Some(digits) = rest.get(start..start + 3) else {
+            break;
+        };
+        if rest[start + 3..].starts_with('}') {
+            if let Some(value) = base91(digits.as_bytes()) {
+                let comment = [&rest[..start], &rest[start + 4..]].concat();
+                return (Some(value as i32 - 10000), comment);
+            }
+        }
+    }
+    (None, rest.to_string())
+}
+
+impl AprsPacket {
+    /// Interpret a frame as APRS if it is a UI frame with no layer 3 protocol and its
+    /// information field is in a recognised format.
+    ///
+    /// Unlike `parse`, this also recognises Mic-E reports.
+    pub fn from_frame(frame: &Ax25Frame) -> Option<AprsPacket> {
+        match &frame.content {
+            FrameContent::UnnumberedInformation(ui) if ui.pid == ProtocolIdentifier::None => {
+                if let Some(mic_e) = MicE::parse(frame.destination.callsign(), &ui.info) {
+                    return Some(AprsPacket::MicE(mic_e));
+                }
+                Self::parse(&frame.info_string_lossy()?)
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse the text of an APRS information field.
+    pub fn parse(info: &str) -> Option<AprsPacket> {
+        let mut chars = info.chars();
+        let data_type = chars.next()?;
+        let rest = chars.as_str();
+        match data_type {
+            ':' => parse_message(rest),
+            '!' => parse_position(rest, false, None),
+            '=' => parse_position(rest, true, None),
+            '/' | '@' => {
+                let timestamp = rest.get(..7)?;
+                parse_position(&rest[7..], data_type == '@', Some(timestamp.to_string()))
+            }
+            '>' => Some(AprsPacket::Status(parse_status(rest))),
+            _ => None,
+        }
+    }
+}
+
+fn parse_message(rest: &str) -> Option<AprsPacket> {
+    let addressee = rest.get(..9)?.trim_end().to_string();
+    let body = rest.get(9..)?.strip_prefix(':')?;
+    for (prefix, is_ack) in [("ack", true), ("rej", false)] {
+        if let Some(id) = body.strip_prefix(prefix) {
+            if !id.is_empty() && !id.contains(' ') {
+                let id = id.to_string();
+                return Some(match is_ack {
+                    true => AprsPacket::Ack { addressee, id },
+                    false => AprsPacket::Reject { addressee, id },
+                });
+            }
+        }
+    }
+    let (text, id) = match body.rsplit_once('{') {
+        Some((text, id)) => (text, Some(id.to_string())),
+        None => (body, None),
+    };
+    Some(AprsPacket::Message(Message {
+        addressee,
+        text: text.to_string(),
+        id,
+    }))
+}
+
+fn parse_position(rest: &str, messaging: bool, timestamp: Option<String>) -> Option<AprsPacket> {
+    let first = rest.chars().next()?;
+    let (latitude, longitude, symbol_table, symbol_code, comment) = if first.is_ascii_digit() {
+        parse_uncompressed(rest)?
+    } else {
+        parse_compressed(rest)?
+    };
+    if !is_valid_position(latitude, longitude) {
+        return None;
+    }
+    Some(AprsPacket::Position(Position {
+        latitude,
+        longitude,
+        symbol_table,
+        symbol_code,
+        messaging,
+        timestamp,
+        comment: comment.to_string(),
+    }))
+}
+
+/// `DDMM.mmN/DDDMM.mmW$`, where digits may be replaced with spaces to reduce precision.
+fn parse_uncompressed(s: &str) -> Option<(f64, f64, char, char, &str)> {
+    let bytes = s.as_bytes();
+    if bytes.len() < 19 || !bytes[..19].is_ascii() {
+        return None;
+    }
+    let latitude = parse_coordinate(&s[0..2], &s[2..7], bytes[7], b'N', b'S')?;
+    let longitude = parse_coordinate(&s[9..12], &s[12..17], bytes[17], b'E', b'W')?;
+    Some((
+        latitude,
+        longitude,
+        bytes[8] as char,
+        bytes[18] as char,
+        &s[19..],
+    ))
+}
+
+/// Whether the coordinates are within ±90° latitude and ±180° longitude.
+fn is_valid_position(latitude: f64, longitude: f64) -> bool {
+    (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude)
+}
+
+fn parse_coordinate(degrees: &str, minutes: &str, hemisphere: u8, pos: u8, neg: u8) -> Option<f64> {
+    // Ambiguous digits are treated as zero
+    let degrees: f64 = degrees.replace(' ', "0").parse().ok()?;
+    let minutes: f64 = minutes.replace(' ', "0").parse().ok()?;
+    let value = degrees + minutes / 60.0;
+    match hemisphere {
+        h if h == pos => Some(value),
+        h if h == neg => Some(-value),
+        _ => None,
+    }
+}
+
+/// Symbol table, four base-91 digits each of latitude and longitude, then the symbol
+/// code and three bytes of course/speed or altitude which are left in the comment.
+fn parse_compressed(s: &str) -> Option<(f64, f64, char, char, &str)> {
+    let bytes = s.as_bytes();
+    if bytes.len() < 13 || !bytes[..10].is_ascii() {
+        return None;
+    }
+    let lat = base91(&bytes[1..5])?;
+    let lon = base91(&bytes[5..9])?;
+    Some((
+        90.0 - lat as f64 / 380926.0,
+        -180.0 + lon as f64 / 190463.0,
+        bytes[0] as char,
+        bytes[9] as char,
+        &s[10..],
+    ))
+}
+
+fn base91(digits: &[u8]) -> Option<u32> {
+    digits.iter().try_fold(0u32, |acc, &d| match d {
+        33..=123 => Some(acc * 91 + (d - 33) as u32),
+        _ => None,
+    })
+}
+
+fn parse_status(rest: &str) -> Status {
+    // A timestamp is only allowed in the DDHHMMz format
+    let has_timestamp = rest.len() >= 7
+        && rest.as_bytes()[..6].iter().all(u8::is_ascii_digit)
+        && rest.as_bytes()[6] == b'z';
+    match has_timestamp {
+        true => Status {
+            timestamp: Some(rest[..7].to_string()),
+            text: rest[7..].to_string(),
+        },
+        false => Status {
+            timestamp: None,
+            text: rest.to_string(),
+        },
+    }
+}
+
+#[test]
+fn test_message() {
+    assert_eq!(
+        AprsPacket::parse(":VK7NTK-1 :Hello there{42"),
+        Some(AprsPacket::Message(Message {
+            addressee: "VK7NTK-1".to_string(),
+            text: "Hello there".to_string(),
+            id: Some("42".to_string()),
+        }))
+    );
+    assert_eq!(
+        AprsPacket::parse(":BLN1     :No reply wanted"),
+        Some(AprsPacket::Message(Message {
+            addressee: "BLN1".to_string(),
+            text: "No reply wanted".to_string(),
+            id: None,
+        }))
+    );
+    assert_eq!(
+        AprsPacket::parse(":VK7NTK   :ack42"),
+        Some(AprsPacket::Ack {
+            addressee: "VK7NTK".to_string(),
+            id: "42".to_string(),
+        })
+    );
+    assert_eq!(
+        AprsPacket::parse(":VK7NTK   :rej7"),
+        Some(AprsPacket::Reject {
+            addressee: "VK7NTK".to_string(),
+            id: "7".to_string(),
+        })
+    );
+    // The addressee must be padded to nine characters
+    assert_eq!(AprsPacket::parse(":VK7NTK:hi"), None);
+}
+
+#[test]
+fn test_position() {
+    let position = match AprsPacket::parse("=4252.45S/14719.36E-PHG2360 Hobart") {
+        Some(AprsPacket::Position(p)) => p,
+        p => panic!("not a position: {:?}", p),
+    };
+    assert!((position.latitude - -42.874167).abs() < 1e-6);
+    assert!((position.longitude - 147.322667).abs() < 1e-6);
+    assert_eq!(position.symbol_table, '/');
+    assert_eq!(position.symbol_code, '-');
+    assert!(position.messaging);
+    assert_eq!(position.timestamp, None);
+    assert_eq!(position.comment, "PHG2360 Hobart");
+
+    let position = match AprsPacket::parse("@092345z4903.5 N/07201.75W>") {
+        Some(AprsPacket::Position(p)) => p,
+        p => panic!("not a position: {:?}", p),
+    };
+    assert!((position.latitude - 49.058333).abs() < 1e-6);
+    assert!((position.longitude - -72.029167).abs() < 1e-6);
+    assert_eq!(position.timestamp.as_deref(), Some("092345z"));
+
+    // Example from the APRS 1.0.1 specification
+    let position = match AprsPacket::parse("!/5L!!<*e7>7P[") {
+        Some(AprsPacket::Position(p)) => p,
+        p => panic!("not a position: {:?}", p),
+    };
+    assert!((position.latitude - 49.5).abs() < 1e-4);
+    assert!((position.longitude - -72.75).abs() < 1e-4);
+    assert_eq!(position.symbol_code, '>');
+    assert!(!position.messaging);
+
+    assert_eq!(AprsPacket::parse("!4903.50X/07201.75W-"), None);
+    // Out of range
+    assert_eq!(AprsPacket::parse("!9959.99N/07201.75W-"), None);
+    assert_eq!(AprsPacket::parse("!4903.50N/18100.00W-"), None);
+}
+
+#[test]
+fn test_mic_e() {
+    // 33 25.64N 112 07.74W, 20 knots on a course of 251, at 61 m
+    let info = b"`(_fn\"O>/]\"4T}Hello";
+    let mic_e = MicE::parse("S32UVT", info).unwrap();
+    assert!((mic_e.latitude - 33.427333).abs() < 1e-6);
+    assert!((mic_e.longitude - -112.129).abs() < 1e-6);
+    assert_eq!(mic_e.speed, 20);
+    assert_eq!(mic_e.course, 251);
+    assert_eq!(mic_e.symbol_table, '/');
+    assert_eq!(mic_e.symbol_code, '>');
+    assert_eq!(mic_e.message, MicEMessage::Standard(3));
+    assert_eq!(mic_e.altitude, Some(61));
+    assert_eq!(mic_e.comment, "]Hello");
+
+    // 42 50.00S 147 15.00E, stationary
+    let mic_e = MicE::parse("425LPL", b"'K+\x1cl\x1c\x1c-/").unwrap();
+    assert!((mic_e.latitude - -42.833333).abs() < 1e-6);
+    assert!((mic_e.longitude - 147.25).abs() < 1e-6);
+    assert_eq!((mic_e.speed, mic_e.course), (0, 0));
+    assert_eq!(mic_e.message, MicEMessage::Emergency);
+    assert_eq!(mic_e.altitude, None);
+    assert_eq!(mic_e.comment, "");
+
+    assert_eq!(
+        MicE::parse("ABCLLL", b"`(_fn\"O>/").unwrap().message,
+        MicEMessage::Custom(0)
+    );
+    assert_eq!(
+        MicE::parse("AQ0LLL", b"`(_fn\"O>/").unwrap().message,
+        MicEMessage::Unknown
+    );
+    // Not a Mic-E destination
+    assert_eq!(MicE::parse("APRS", b"`(_fn\"O>/"), None);
+    assert_eq!(MicE::parse("S32UVT", b"!(_fn\"O>/"), None);
+
+    let frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-9".parse().unwrap(),
+        "S32UVT".parse().unwrap(),
+        info.to_vec(),
+    );
+    assert!(matches!(
+        AprsPacket::from_frame(&frame),
+        Some(AprsPacket::MicE(m)) if m.speed == 20
+    ));
+}
+
+#[test]
+fn test_status() {
+    assert_eq!(
+        AprsPacket::parse(">092345zNet tonight"),
+        Some(AprsPacket::Status(Status {
+            timestamp: Some("092345z".to_string()),
+            text: "Net tonight".to_string(),
+        }))
+    );
+    assert_eq!(
+        AprsPacket::parse(">QRV 146.500"),
+        Some(AprsPacket::Status(Status {
+            timestamp: None,
+            text: "QRV 146.500".to_string(),
+        }))
+    );
+}
+
+#[test]
+fn test_from_frame() {
+    let mut frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "APRS".parse().unwrap(),
+        b">On the air".to_vec(),
+    );
+    assert!(matches!(
+        AprsPacket::from_frame(&frame),
+        Some(AprsPacket::Status(_))
+    ));
+    if let FrameContent::UnnumberedInformation(ui) = &mut frame.content {
+        ui.pid = ProtocolIdentifier::NetRom;
+    }
+    assert_eq!(AprsPacket::from_frame(&frame), None);
+}
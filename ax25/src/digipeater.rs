@@ -0,0 +1,216 @@
+use crate::frame::{Address, Ax25Frame, RouteEntry, MAX_REPEATERS};
+use alloc::string::{String, ToString};
+use alloc::{vec, vec::Vec};
+
+/// Rules for deciding whether a digipeater should repeat a frame and how to update
+/// its route when it does.
+///
+/// A frame is repeated if the next unrepeated entry in its route is either the
+/// digipeater's own address or an n-N alias such as `WIDE2-2` whose base name is
+/// listed in `aliases`. For an alias, the SSID counts the hops remaining: it is
+/// decremented, the entry is marked as repeated once it reaches 0, and the
+/// digipeater's own address is inserted before it so the path records who repeated it.
+/// An alias claiming more hops remaining than its n, such as `WIDE1-7`, is not repeated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigipeaterRules {
+    /// Base names of n-N aliases to respond to, e.g. `WIDE` to handle `WIDE1-1`
+    /// through `WIDE7-7`.
+    pub aliases: Vec<String>,
+    /// Refuse to repeat aliases whose n, the total hops requested, is more than this.
+    /// For example a limit of 2 allows `WIDE2-1` but not `WIDE3-1`.
+    pub max_hops: u8,
+}
+
+impl Default for DigipeaterRules {
+    fn default() -> Self {
+        Self {
+            aliases: vec!["WIDE".to_string(), "TRACE".to_string()],
+            max_hops: 7,
+        }
+    }
+}
+
+impl DigipeaterRules {
+    /// Work out whether the station `mine` should repeat `frame`.
+    ///
+    /// Returns the frame to transmit with its route updated, or `None` if it should not
+    /// be repeated because the path is exhausted or the next hop is someone else.
+    pub fn process(&self, frame: &Ax25Frame, mine: &Address) -> Option<Ax25Frame> {
+        let idx = frame.route.iter().position(|entry| !entry.has_repeated)?;
+        let next = &frame.route[idx].repeater;
+        let mut out = frame.clone();
+
+        if next == mine {
+            out.route[idx].has_repeated = true;
+            return Some(out);
+        }
+
+        let total = self.alias_hops(next)?;
+        let hops = next.ssid();
+        if hops == 0 || hops > total || total > self.max_hops {
+            return None;
+        }
+        let remaining = hops - 1;
+        out.route[idx] = RouteEntry {
+            repeater: next.clone().with_ssid(remaining).ok()?,
+            has_repeated: remaining == 0,
+        };
+        let me = RouteEntry {
+            repeater: mine.clone(),
+            has_repeated: true,
+        };
+        if out.route.len() < MAX_REPEATERS {
+            out.route.insert(idx, me);
+        } else if remaining == 0 {
+            // No room to insert, so take the place of the exhausted alias
+            out.route[idx] = me;
+        }
+        Some(out)
+    }
+
+    /// The n of an n-N alias such as `WIDE2` that these rules respond to, or `None` if
+    /// `address` is not one.
+    fn alias_hops(&self, address: &Address) -> Option<u8> {
+        let callsign = address.callsign();
+        // A received address field may be all spaces
+        let (idx, n) = callsign.char_indices().last()?;
+        let n = n.to_digit(10).filter(|n| (1..=7).contains(n))?;
+        self.aliases
+            .iter()
+            .any(|alias| alias == &callsign[..idx])
+            .then_some(n as u8)
+    }
+}
+
+#[cfg(test)]
+fn frame_via(path: &[(&str, u8, bool)]) -> Ax25Frame {
+    let mut frame = Ax25Frame::new_simple_ui_frame(
+        Address::new("VK7NTK", 1).unwrap(),
+        Address::new("APRS", 0).unwrap(),
+        b"hello".to_vec(),
+    );
+    for &(callsign, ssid, has_repeated) in path {
+        frame.route.push(RouteEntry {
+            repeater: Address::new(callsign, ssid).unwrap(),
+            has_repeated,
+        });
+    }
+    frame
+}
+
+#[test]
+fn test_wide_n_n() {
+    let rules = DigipeaterRules::default();
+    let mine = Address::new("VK7RAA", 0).unwrap();
+
+    let out = rules.process(&frame_via(&[("WIDE1", 1, false)]), &mine);
+    assert_eq!(
+        out,
+        Some(frame_via(&[("VK7RAA", 0, true), ("WIDE1", 0, true)]))
+    );
+
+    let out = rules.process(&frame_via(&[("WIDE2", 2, false)]), &mine);
+    assert_eq!(
+        out,
+        Some(frame_via(&[("VK7RAA", 0, true), ("WIDE2", 1, false)]))
+    );
+
+    let out = rules.process(
+        &frame_via(&[("VK7RBB", 0, true), ("TRACE2", 1, false)]),
+        &mine,
+    );
+    assert_eq!(
+        out,
+        Some(frame_via(&[
+            ("VK7RBB", 0, true),
+            ("VK7RAA", 0, true),
+            ("TRACE2", 0, true)
+        ]))
+    );
+}
+
+#[test]
+fn test_hop_limits() {
+    let mine = Address::new("VK7RAA", 0).unwrap();
+    let rules = DigipeaterRules::default();
+    // More hops remaining than were requested
+    assert_eq!(
+        rules.process(&frame_via(&[("WIDE1", 7, false)]), &mine),
+        None
+    );
+    assert_eq!(
+        rules.process(&frame_via(&[("WIDE2", 5, false)]), &mine),
+        None
+    );
+
+    // The limit applies to the total hops, even once some have been used
+    let rules = DigipeaterRules {
+        max_hops: 2,
+        ..Default::default()
+    };
+    assert!(rules
+        .process(&frame_via(&[("WIDE2", 2, false)]), &mine)
+        .is_some());
+    assert_eq!(
+        rules.process(&frame_via(&[("WIDE3", 1, false)]), &mine),
+        None
+    );
+    assert_eq!(
+        rules.process(&frame_via(&[("WIDE7", 2, false)]), &mine),
+        None
+    );
+}
+
+#[test]
+fn test_blank_repeater() {
+    let mut bytes = frame_via(&[("WIDE1", 1, false)]).to_bytes();
+    for b in &mut bytes[14..20] {
+        *b = b' ' << 1;
+    }
+    let frame = Ax25Frame::from_bytes(&bytes).unwrap();
+    assert_eq!(frame.route[0].repeater.callsign(), "");
+    let mine = Address::new("VK7RAA", 0).unwrap();
+    assert_eq!(DigipeaterRules::default().process(&frame, &mine), None);
+}
+
+#[test]
+fn test_not_repeated() {
+    let rules = DigipeaterRules {
+        max_hops: 2,
+        ..Default::default()
+    };
+    let mine = Address::new("VK7RAA", 0).unwrap();
+
+    // No path, exhausted path, someone else's hop, unknown alias, too many hops
+    assert_eq!(rules.process(&frame_via(&[]), &mine), None);
+    assert_eq!(
+        rules.process(&frame_via(&[("WIDE1", 0, true)]), &mine),
+        None
+    );
+    assert_eq!(
+        rules.process(&frame_via(&[("VK7RBB", 0, false)]), &mine),
+        None
+    );
+    assert_eq!(
+        rules.process(&frame_via(&[("RELAY", 1, false)]), &mine),
+        None
+    );
+    assert_eq!(
+        rules.process(&frame_via(&[("WIDE7", 7, false)]), &mine),
+        None
+    );
+}
+
+#[test]
+fn test_own_callsign() {
+    let rules = DigipeaterRules::default();
+    let mine = Address::new("VK7RAA", 0).unwrap();
+    let out = rules.process(
+        &frame_via(&[("VK7RAA", 0, false), ("WIDE2", 1, false)]),
+        &mine,
+    );
+    assert_eq!(
+        out,
+        Some(frame_via(&[("VK7RAA", 0, true), ("WIDE2", 1, false)]))
+    );
+}
@@ -2,7 +2,10 @@ use alloc::borrow::ToOwned;
 use alloc::fmt;
 use alloc::str::FromStr;
 use alloc::string::{String, ToString};
-use alloc::{vec, vec::Vec};
+use alloc::{format, vec, vec::Vec};
+use core::ops::Range;
+
+use crate::hdlc::{check_fcs, FcsCheck};
 
 /// Errors when parsing a callsign-SSID into an `Address`
 #[derive(Debug)]
@@ -37,26 +40,74 @@ impl fmt::Display for AddressParseError {
     }
 }
 
+/// Errors when parsing a path such as `WIDE1-1,WIDE2-2` with `RouteEntry::parse_path`
+#[derive(Debug)]
+pub enum PathParseError {
+    /// One of the comma-separated repeaters is not a valid callsign-SSID
+    InvalidAddress {
+        address: String,
+        source: AddressParseError,
+    },
+    TooManyRepeaters {
+        count: usize,
+    },
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PathParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidAddress { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PathParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidAddress { address, source } => {
+                write!(f, "Invalid repeater {:?} in path: {}", address, source)
+            }
+            Self::TooManyRepeaters { count } => write!(
+                f,
+                "Path has {} repeaters but at most {} are allowed",
+                count, MAX_REPEATERS
+            ),
+        }
+    }
+}
+
 /// Errors when parsing a byte buffer into an `Ax25Frame`
 #[derive(Debug)]
 pub enum FrameParseError {
-    OnlyNullBytes,
+    LeadingNullByte,
     NoEndToAddressField,
+    /// The address field ends before a destination and source have been given
     AddressFieldTooShort {
         start: usize,
         end: usize,
     },
-    FrameTooShort {
+    /// The address field does not divide into seven byte addresses
+    MisalignedAddressField {
         len: usize,
     },
     AddressInvalidUtf8 {
         source: alloc::string::FromUtf8Error,
     },
+    /// The frame ends with the address field, so there is no control field
     ContentZeroLength,
+    /// The control field is for an I or UI frame but the frame ends before the PID.
+    /// An empty information field after the PID is allowed.
     MissingPidField,
     UnrecognisedSFieldType,
     UnrecognisedUFieldType,
     WrongSizeFrmrInfo,
+    /// The information field of an XID frame is not a single group of parameters
+    MalformedXidInfo,
+    TooManyRepeaters {
+        count: usize,
+    },
 }
 
 #[cfg(feature = "std")]
@@ -72,26 +123,250 @@ impl std::error::Error for FrameParseError {
 impl fmt::Display for FrameParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::OnlyNullBytes => write!(f, "Supplied frame only contains null bytes"),
+            Self::LeadingNullByte => write!(f, "Frame starts with a null byte"),
             Self::NoEndToAddressField => write!(f, "Unable to locate end of address field"),
             Self::AddressFieldTooShort { start, end } => {
                 write!(f, "Address field too short: start {} end {}", start, end)
             }
-            Self::FrameTooShort { len } => write!(f, "Frame is too short: len {}", len),
+            Self::MisalignedAddressField { len } => write!(
+                f,
+                "Address field is {} bytes long, which is not a multiple of 7",
+                len
+            ),
             Self::AddressInvalidUtf8 { .. } => write!(f, "Callsign is not valid UTF-8"),
             Self::ContentZeroLength => write!(f, "Content section of frame is empty"),
             Self::MissingPidField => write!(f, "Protocol ID field is missing"),
             Self::UnrecognisedUFieldType => write!(f, "Unrecognised U field type"),
             Self::UnrecognisedSFieldType => write!(f, "Unrecognised S field type"),
             Self::WrongSizeFrmrInfo => write!(f, "Wrong size for FRMR info"),
+            Self::MalformedXidInfo => write!(f, "Malformed XID parameters"),
+            Self::TooManyRepeaters { count } => write!(
+                f,
+                "Address field has {} repeaters but at most {} are allowed",
+                count, MAX_REPEATERS
+            ),
+        }
+    }
+}
+
+impl FrameParseError {
+    /// Attach the bytes that could not be parsed, so they can be logged or analysed.
+    pub fn with_bytes(self, bytes: &[u8]) -> UnparseableFrame {
+        UnparseableFrame {
+            bytes: bytes.to_vec(),
+            error: self,
+        }
+    }
+}
+
+/// A `FrameParseError` together with the bytes which caused it.
+#[derive(Debug)]
+pub struct UnparseableFrame {
+    /// The complete frame as it was given to the parser
+    pub bytes: Vec<u8>,
+    /// The reason the frame could not be parsed
+    pub error: FrameParseError,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnparseableFrame {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl fmt::Display for UnparseableFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in frame of {} bytes:", self.error, self.bytes.len())?;
+        for b in &self.bytes {
+            write!(f, " {:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Errors when encoding an `Ax25Frame` that cannot be represented as a valid frame
+#[derive(Debug)]
+pub enum EncodeError {
+    TooManyRepeaters {
+        count: usize,
+    },
+    InfoTooLong {
+        len: usize,
+        max: usize,
+    },
+    /// A send or receive sequence number does not fit in the modulo-8 range
+    SequenceOutOfRange {
+        value: u8,
+    },
+    /// An XID parameter value is longer than the 255 bytes its length field can hold
+    XidParameterTooLong {
+        identifier: u8,
+        len: usize,
+    },
+    /// The XID parameters encode to more than the 65535 bytes the group length can hold
+    XidGroupTooLong {
+        len: usize,
+    },
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyRepeaters { count } => write!(
+                f,
+                "Route has {} repeaters but at most {} are allowed",
+                count, MAX_REPEATERS
+            ),
+            Self::InfoTooLong { len, max } => write!(
+                f,
+                "Information field is {} bytes long but at most {} are allowed",
+                len, max
+            ),
+            Self::SequenceOutOfRange { value } => write!(
+                f,
+                "Sequence number {} is out of range, must be between 0 and 7 inclusive",
+                value
+            ),
+            Self::XidParameterTooLong { identifier, len } => write!(
+                f,
+                "XID parameter {} is {} bytes long but at most 255 are allowed",
+                identifier, len
+            ),
+            Self::XidGroupTooLong { len } => write!(
+                f,
+                "XID parameters are {} bytes long but at most 65535 are allowed",
+                len
+            ),
+        }
+    }
+}
+
+/// Errors when parsing a hex string into an `Ax25Frame` with `Ax25Frame::from_hex`
+#[derive(Debug)]
+pub enum HexParseError {
+    /// A character which is neither a hex digit nor a separator, at this byte offset
+    InvalidCharacter { position: usize, character: char },
+    /// A group of digits starting at this byte offset has an odd length, so it cannot
+    /// be split into bytes
+    UnpairedDigit { position: usize },
+    /// The bytes were read but do not form a valid frame
+    Frame { source: FrameParseError },
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HexParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Frame { source } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HexParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCharacter {
+                position,
+                character,
+            } => write!(
+                f,
+                "Character {:?} at position {} is not a hex digit",
+                character, position
+            ),
+            Self::UnpairedDigit { position } => write!(
+                f,
+                "Hex digits at position {} do not pair up into bytes",
+                position
+            ),
+            Self::Frame { source } => write!(f, "Hex does not form a valid frame: {}", source),
+        }
+    }
+}
+
+/// A problem found by `Ax25Frame::validate`
+#[derive(Debug)]
+pub enum ValidationError {
+    /// The source, destination or a repeater is not a valid callsign-SSID
+    InvalidAddress {
+        callsign: String,
+        ssid: u8,
+        source: AddressParseError,
+    },
+    TooManyRepeaters {
+        count: usize,
+    },
+    InfoTooLong {
+        len: usize,
+        max: usize,
+    },
+    /// A send or receive sequence number does not fit in the modulo-8 range
+    SequenceOutOfRange {
+        value: u8,
+    },
+    /// The frame's content has `trailing_bytes`, which its type does not allow
+    TrailingBytes {
+        len: usize,
+    },
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidAddress { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidAddress {
+                callsign,
+                ssid,
+                source,
+            } => write!(f, "Invalid address {}-{}: {}", callsign, ssid, source),
+            Self::TooManyRepeaters { count } => write!(
+                f,
+                "Route has {} repeaters but at most {} are allowed",
+                count, MAX_REPEATERS
+            ),
+            Self::InfoTooLong { len, max } => write!(
+                f,
+                "Information field is {} bytes long but at most {} are allowed",
+                len, max
+            ),
+            Self::SequenceOutOfRange { value } => write!(
+                f,
+                "Sequence number {} is out of range, must be between 0 and 7 inclusive",
+                value
+            ),
+            Self::TrailingBytes { len } => write!(
+                f,
+                "Frame has {} bytes after a control field which should end it",
+                len
+            ),
         }
     }
 }
 
+/// The maximum number of repeaters that can appear in a frame's route.
+pub const MAX_REPEATERS: usize = 8;
+
+/// The default maximum length of an information field (paclen) in AX.25 2.0.
+pub const DEFAULT_MAX_INFO_LEN: usize = 256;
+
 /// Human-readable protocol identifiers, mostly from the AX.25 2.2 spec.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ProtocolIdentifier {
-    Layer3Impl,
+    /// One of the range of values indicating that AX.25 layer 3 is implemented
+    Layer3Impl(Layer3Pid),
     X25Plp,
     CompressedTcpIp,
     UncompressedTcpIp,
@@ -110,11 +385,12 @@ pub enum ProtocolIdentifier {
 }
 
 impl ProtocolIdentifier {
-    fn from_byte(byte: u8) -> ProtocolIdentifier {
+    /// Interpret a raw PID byte.
+    pub fn from_byte(byte: u8) -> ProtocolIdentifier {
+        if let Some(pid) = Layer3Pid::new(byte) {
+            return ProtocolIdentifier::Layer3Impl(pid);
+        }
         match byte {
-            pid if pid & 0b0011_0000 == 0b0001_0000 || pid & 0b0011_0000 == 0b0010_0000 => {
-                ProtocolIdentifier::Layer3Impl
-            }
             0x01 => ProtocolIdentifier::X25Plp,
             0x06 => ProtocolIdentifier::CompressedTcpIp,
             0x07 => ProtocolIdentifier::UncompressedTcpIp,
@@ -133,9 +409,10 @@ impl ProtocolIdentifier {
         }
     }
 
-    fn to_byte(&self) -> u8 {
+    /// The raw PID byte for this protocol.
+    pub fn to_byte(&self) -> u8 {
         match *self {
-            ProtocolIdentifier::Layer3Impl => 0b0001_0000,
+            ProtocolIdentifier::Layer3Impl(pid) => pid.byte(),
             ProtocolIdentifier::X25Plp => 0x01,
             ProtocolIdentifier::CompressedTcpIp => 0x06,
             ProtocolIdentifier::UncompressedTcpIp => 0x07,
@@ -155,15 +432,70 @@ impl ProtocolIdentifier {
     }
 }
 
+/// A PID byte from the range that indicates AX.25 layer 3 is implemented: any byte
+/// whose bits 5 and 4 are `01` or `10`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Layer3Pid(u8);
+
+impl Layer3Pid {
+    /// The given PID byte, if it is in the layer 3 range.
+    pub fn new(byte: u8) -> Option<Self> {
+        match byte & 0b0011_0000 {
+            0b0001_0000 | 0b0010_0000 => Some(Layer3Pid(byte)),
+            _ => None,
+        }
+    }
+
+    /// The raw PID byte.
+    pub fn byte(&self) -> u8 {
+        self.0
+    }
+}
+
+impl From<u8> for ProtocolIdentifier {
+    fn from(byte: u8) -> Self {
+        ProtocolIdentifier::from_byte(byte)
+    }
+}
+
+impl From<&ProtocolIdentifier> for u8 {
+    fn from(pid: &ProtocolIdentifier) -> Self {
+        pid.to_byte()
+    }
+}
+
 /// Indicates whether a given frame is a Command or a Response.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum CommandResponse {
     Command,
     Response,
+    /// Sent by an AX.25 1.x station, which does not say whether a frame is a command
+    /// or a response and sets both c-bits to `c_bit`. This only has meaning in
+    /// `Ax25Frame::command_or_response`.
+    V1 {
+        c_bit: bool,
+    },
+}
+
+/// Whether the frame rejected by an FRMR was a command or a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectedCommandResponse {
+    Command,
+    Response,
+}
+
+/// The version of the AX.25 protocol that a frame appears to follow, judging by the
+/// command/response bits in its address field.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProtocolVersion {
+    /// Both c-bits are the same, as sent by AX.25 1.x stations.
+    V1,
+    /// The c-bits differ, indicating a command or response as in AX.25 2.0.
+    V2,
 }
 
 /// Information (I) frame
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Information {
     pub pid: ProtocolIdentifier,
     pub info: Vec<u8>,
@@ -173,52 +505,66 @@ pub struct Information {
 }
 
 /// RR Supervisory (S) frame
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ReceiveReady {
     pub receive_sequence: u8,
     pub poll_or_final: bool,
+    /// See `FrameContent::trailing_bytes`
+    pub trailing: Vec<u8>,
 }
 
 /// RNR Supervisory (S) frame
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ReceiveNotReady {
     pub receive_sequence: u8,
     pub poll_or_final: bool,
+    /// See `FrameContent::trailing_bytes`
+    pub trailing: Vec<u8>,
 }
 
 /// REJ Supervisory (S) frame
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Reject {
     pub receive_sequence: u8,
     pub poll_or_final: bool,
+    /// See `FrameContent::trailing_bytes`
+    pub trailing: Vec<u8>,
 }
 
 /// SABM Unnumbered (U) frame
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SetAsynchronousBalancedMode {
     pub poll: bool,
+    /// See `FrameContent::trailing_bytes`
+    pub trailing: Vec<u8>,
 }
 
 /// DISC Unnumbered (U) frame
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Disconnect {
     pub poll: bool,
+    /// See `FrameContent::trailing_bytes`
+    pub trailing: Vec<u8>,
 }
 
 /// DM Unnumbered (U) frame
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct DisconnectedMode {
     pub final_bit: bool, // 'final' is a rust keyword
+    /// See `FrameContent::trailing_bytes`
+    pub trailing: Vec<u8>,
 }
 
 /// UA Unnumbered (U) frame
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct UnnumberedAcknowledge {
     pub final_bit: bool,
+    /// See `FrameContent::trailing_bytes`
+    pub trailing: Vec<u8>,
 }
 
 /// FRMR Unnumbered (U) frame. Flags correspond to names in the AX.25 specification.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FrameReject {
     pub final_bit: bool,
     /// A raw copy of the control field in the frame that was rejected
@@ -233,11 +579,137 @@ pub struct FrameReject {
     pub w: bool,
     pub receive_sequence: u8,
     pub send_sequence: u8,
-    pub command_response: CommandResponse,
+    pub command_response: RejectedCommandResponse,
+    /// Bits of the information field which should be zero, kept so that encoding
+    /// reproduces the frame as it was received. The high four bits are those above
+    /// W X Y Z and the lowest bit is the one below the send sequence number.
+    pub reserved_bits: u8,
+}
+
+/// XID Unnumbered (U) frame, which AX.25 2.2 stations use to negotiate link parameters
+/// such as window size and maximum information field length.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExchangeIdentification {
+    pub poll_or_final: bool,
+    /// The parameters in the order they were sent. An XID frame with no information
+    /// field has none.
+    pub parameters: Vec<XidParameter>,
+}
+
+/// One parameter of an XID frame, such as `XID_WINDOW_SIZE_RX`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct XidParameter {
+    /// The parameter identifier (PI)
+    pub identifier: u8,
+    /// The parameter value (PV), most significant byte first. At most 255 bytes can be
+    /// encoded.
+    pub value: Vec<u8>,
+}
+
+/// XID parameter: the classes of procedures, such as half or full duplex
+pub const XID_CLASSES_OF_PROCEDURES: u8 = 2;
+/// XID parameter: the HDLC optional functions, such as modulo 8 or 128 and REJ or SREJ
+pub const XID_HDLC_OPTIONAL_FUNCTIONS: u8 = 3;
+/// XID parameter: the largest information field the sender will transmit, in bits
+pub const XID_I_FIELD_LENGTH_TX: u8 = 5;
+/// XID parameter: the largest information field the sender can receive, in bits
+pub const XID_I_FIELD_LENGTH_RX: u8 = 6;
+/// XID parameter: the number of outstanding I frames the sender will transmit
+pub const XID_WINDOW_SIZE_TX: u8 = 7;
+/// XID parameter: the number of outstanding I frames the sender can receive
+pub const XID_WINDOW_SIZE_RX: u8 = 8;
+/// XID parameter: the acknowledgement timer T1, in milliseconds
+pub const XID_ACK_TIMER: u8 = 9;
+/// XID parameter: the retry count N2
+pub const XID_RETRIES: u8 = 10;
+
+/// The format indicator which starts an XID information field
+const XID_FORMAT_INDICATOR: u8 = 0x82;
+/// The group identifier of the parameter negotiation group
+const XID_GROUP_IDENTIFIER: u8 = 0x80;
+
+impl ExchangeIdentification {
+    /// The value of the first parameter with this identifier as a number, if it is
+    /// present and no more than four bytes long.
+    pub fn parameter(&self, identifier: u8) -> Option<u32> {
+        let parameter = self
+            .parameters
+            .iter()
+            .find(|p| p.identifier == identifier)?;
+        if parameter.value.len() > 4 {
+            return None;
+        }
+        Some(
+            parameter
+                .value
+                .iter()
+                .fold(0, |acc, &b| (acc << 8) | b as u32),
+        )
+    }
+
+    /// Set a parameter to a numeric value, using as few bytes as possible, replacing
+    /// any parameter with the same identifier or otherwise adding it at the end.
+    pub fn set_parameter(&mut self, identifier: u8, value: u32) {
+        let bytes = value.to_be_bytes();
+        let skip = bytes.iter().take(3).take_while(|&&b| b == 0).count();
+        let parameter = XidParameter {
+            identifier,
+            value: bytes[skip..].to_vec(),
+        };
+        match self
+            .parameters
+            .iter_mut()
+            .find(|p| p.identifier == identifier)
+        {
+            Some(existing) => *existing = parameter,
+            None => self.parameters.push(parameter),
+        }
+    }
+
+    /// Check that the parameters fit in the length fields of an information field.
+    fn validate(&self) -> Result<(), EncodeError> {
+        if let Some(p) = self.parameters.iter().find(|p| p.value.len() > 255) {
+            return Err(EncodeError::XidParameterTooLong {
+                identifier: p.identifier,
+                len: p.value.len(),
+            });
+        }
+        let len: usize = self.parameters.iter().map(|p| 2 + p.value.len()).sum();
+        if len > u16::MAX as usize {
+            return Err(EncodeError::XidGroupTooLong { len });
+        }
+        Ok(())
+    }
+
+    /// Encode the parameters as an information field.
+    fn encode_info(&self) -> Vec<u8> {
+        if self.parameters.is_empty() {
+            return Vec::new();
+        }
+        let mut group = Vec::new();
+        for p in &self.parameters {
+            group.push(p.identifier);
+            group.push(p.value.len() as u8);
+            group.extend(&p.value);
+        }
+        let mut info = vec![XID_FORMAT_INDICATOR, XID_GROUP_IDENTIFIER];
+        info.extend((group.len() as u16).to_be_bytes());
+        info.extend(group);
+        info
+    }
+}
+
+/// TEST Unnumbered (U) frame, which a station answers by echoing the information field
+/// back in a TEST response. Whether it is a command or a response is carried in the
+/// address field, as `Ax25Frame::command_or_response`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Test {
+    pub poll_or_final: bool,
+    pub info: Vec<u8>,
 }
 
 /// UI Unnumbered Information frame
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct UnnumberedInformation {
     pub pid: ProtocolIdentifier,
     pub info: Vec<u8>,
@@ -245,13 +717,13 @@ pub struct UnnumberedInformation {
 }
 
 /// Placeholder for when the Address part was parseable but not the control field
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct UnknownContent {
     pub raw: Vec<u8>,
 }
 
 /// The body of the frame after the end of the address field
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum FrameContent {
     Information(Information),
     ReceiveReady(ReceiveReady),
@@ -263,11 +735,130 @@ pub enum FrameContent {
     UnnumberedAcknowledge(UnnumberedAcknowledge),
     FrameReject(FrameReject),
     UnnumberedInformation(UnnumberedInformation),
+    ExchangeIdentification(ExchangeIdentification),
+    Test(Test),
     UnknownContent(UnknownContent),
 }
 
+/// The kind of a `FrameContent`, without any of its fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameKind {
+    Information,
+    ReceiveReady,
+    ReceiveNotReady,
+    Reject,
+    SetAsynchronousBalancedMode,
+    Disconnect,
+    DisconnectedMode,
+    UnnumberedAcknowledge,
+    FrameReject,
+    UnnumberedInformation,
+    ExchangeIdentification,
+    Test,
+    UnknownContent,
+}
+
+impl FrameKind {
+    /// Whether this is an I frame.
+    pub fn is_information(self) -> bool {
+        self == FrameKind::Information
+    }
+
+    /// Whether this is an S frame: RR, RNR or REJ.
+    pub fn is_supervisory(self) -> bool {
+        matches!(
+            self,
+            FrameKind::ReceiveReady | FrameKind::ReceiveNotReady | FrameKind::Reject
+        )
+    }
+
+    /// Whether this is a U frame. `UnknownContent` is not counted as any type.
+    pub fn is_unnumbered(self) -> bool {
+        matches!(
+            self,
+            FrameKind::SetAsynchronousBalancedMode
+                | FrameKind::Disconnect
+                | FrameKind::DisconnectedMode
+                | FrameKind::UnnumberedAcknowledge
+                | FrameKind::FrameReject
+                | FrameKind::UnnumberedInformation
+                | FrameKind::ExchangeIdentification
+                | FrameKind::Test
+        )
+    }
+}
+
 impl FrameContent {
-    fn encode(&self) -> Vec<u8> {
+    /// The kind of this content, for branching on without matching every variant.
+    pub fn kind(&self) -> FrameKind {
+        match self {
+            FrameContent::Information(_) => FrameKind::Information,
+            FrameContent::ReceiveReady(_) => FrameKind::ReceiveReady,
+            FrameContent::ReceiveNotReady(_) => FrameKind::ReceiveNotReady,
+            FrameContent::Reject(_) => FrameKind::Reject,
+            FrameContent::SetAsynchronousBalancedMode(_) => FrameKind::SetAsynchronousBalancedMode,
+            FrameContent::Disconnect(_) => FrameKind::Disconnect,
+            FrameContent::DisconnectedMode(_) => FrameKind::DisconnectedMode,
+            FrameContent::UnnumberedAcknowledge(_) => FrameKind::UnnumberedAcknowledge,
+            FrameContent::FrameReject(_) => FrameKind::FrameReject,
+            FrameContent::UnnumberedInformation(_) => FrameKind::UnnumberedInformation,
+            FrameContent::ExchangeIdentification(_) => FrameKind::ExchangeIdentification,
+            FrameContent::Test(_) => FrameKind::Test,
+            FrameContent::UnknownContent(_) => FrameKind::UnknownContent,
+        }
+    }
+
+    /// Whether this is an I frame.
+    pub fn is_information(&self) -> bool {
+        self.kind().is_information()
+    }
+
+    /// Whether this is an S frame: RR, RNR or REJ.
+    pub fn is_supervisory(&self) -> bool {
+        self.kind().is_supervisory()
+    }
+
+    /// Whether this is a U frame. `UnknownContent` is not counted as any type.
+    pub fn is_unnumbered(&self) -> bool {
+        self.kind().is_unnumbered()
+    }
+
+    /// Bytes found after the control field of a supervisory or unnumbered frame that
+    /// should have nothing after it, such as RR or SABM. These are not allowed by the
+    /// specification, and a station would normally answer with an FRMR, but they are
+    /// kept so that encoding the frame reproduces the bytes it was parsed from.
+    pub fn trailing_bytes(&self) -> &[u8] {
+        match self {
+            FrameContent::ReceiveReady(rr) => &rr.trailing,
+            FrameContent::ReceiveNotReady(rnr) => &rnr.trailing,
+            FrameContent::Reject(rej) => &rej.trailing,
+            FrameContent::SetAsynchronousBalancedMode(sabm) => &sabm.trailing,
+            FrameContent::Disconnect(disc) => &disc.trailing,
+            FrameContent::DisconnectedMode(dm) => &dm.trailing,
+            FrameContent::UnnumberedAcknowledge(ua) => &ua.trailing,
+            _ => &[],
+        }
+    }
+
+    /// Encode the control field and any following fields, checking first that every
+    /// sequence number is between 0 and 7 and that any XID parameters fit their length
+    /// fields.
+    pub fn try_encode(&self) -> Result<Vec<u8>, EncodeError> {
+        if let Some(value) = self.sequence_numbers().into_iter().find(|&n| n > 7) {
+            return Err(EncodeError::SequenceOutOfRange { value });
+        }
+        if let FrameContent::ExchangeIdentification(xid) = self {
+            xid.validate()?;
+        }
+        Ok(self.encode())
+    }
+
+    /// Encode the control field and any following fields.
+    ///
+    /// Sequence numbers are truncated to their low three bits, so a value above 7 is
+    /// silently sent as a different number. Likewise, oversized XID parameters produce
+    /// length fields that do not match their contents. Use `try_encode` to catch these.
+    pub fn encode(&self) -> Vec<u8> {
         let mut encoded = Vec::new();
 
         match *self {
@@ -327,15 +918,17 @@ impl FrameContent {
                 frmr1 |= if fr.y { 1 << 2 } else { 0 };
                 frmr1 |= if fr.x { 1 << 1 } else { 0 };
                 frmr1 |= if fr.w { 1 } else { 0 };
+                frmr1 |= fr.reserved_bits & 0b1111_0000;
                 encoded.push(frmr1);
                 let mut frmr2: u8 = 0;
                 frmr2 |= (fr.receive_sequence & 0b0000_0111) << 5;
-                frmr2 |= if fr.command_response == CommandResponse::Response {
+                frmr2 |= if fr.command_response == RejectedCommandResponse::Response {
                     1 << 4
                 } else {
                     0
                 };
                 frmr2 |= (fr.send_sequence & 0b0000_0111) << 1;
+                frmr2 |= fr.reserved_bits & 0b0000_0001;
                 encoded.push(frmr2);
                 encoded.push(fr.rejected_control_field_raw);
             }
@@ -346,84 +939,249 @@ impl FrameContent {
                 encoded.push(ui.pid.to_byte());
                 encoded.extend(&ui.info);
             }
+            FrameContent::ExchangeIdentification(ref xid) => {
+                let mut c: u8 = 0b1010_1111;
+                c |= if xid.poll_or_final { 1 << 4 } else { 0 };
+                encoded.push(c);
+                encoded.extend(xid.encode_info());
+            }
+            FrameContent::Test(ref test) => {
+                let mut c: u8 = 0b1110_0011;
+                c |= if test.poll_or_final { 1 << 4 } else { 0 };
+                encoded.push(c);
+                encoded.extend(&test.info);
+            }
             FrameContent::UnknownContent(ref uc) => {
                 encoded.extend(&uc.raw);
             }
         }
+        encoded.extend(self.trailing_bytes());
 
         encoded
     }
+
+    /// The send and receive sequence numbers carried by this content, if any.
+    fn sequence_numbers(&self) -> Vec<u8> {
+        match self {
+            FrameContent::Information(i) => vec![i.receive_sequence, i.send_sequence],
+            FrameContent::ReceiveReady(rr) => vec![rr.receive_sequence],
+            FrameContent::ReceiveNotReady(rnr) => vec![rnr.receive_sequence],
+            FrameContent::Reject(rej) => vec![rej.receive_sequence],
+            FrameContent::FrameReject(fr) => vec![fr.receive_sequence, fr.send_sequence],
+            _ => vec![],
+        }
+    }
 }
 
 /// A source, destination or repeater in an AX.25 frame.
 ///
-/// An `Address` is a combination of a callsign and a numeric SSID.
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// An `Address` is a combination of a callsign and a numeric SSID. It also carries the
+/// two reserved bits of the SSID byte so that they survive a parse and re-encode, but
+/// these are not considered when comparing or hashing addresses.
+#[derive(Clone, Debug)]
 pub struct Address {
     callsign: String,
     ssid: u8,
+    reserved: u8,
 }
 
-impl Address {
-    /// Construct an `Address` from callsign and SSID, ensuring that both are valid.
-    pub fn from_parts(callsign: String, ssid: u8) -> Result<Self, AddressParseError> {
-        let callsign = callsign.to_uppercase();
-        if callsign.is_empty() {
-            return Err(AddressParseError::InvalidFormat);
-        }
-        if callsign.len() > 6 {
-            return Err(AddressParseError::CallsignTooLong);
-        }
-        for c in callsign.chars() {
-            if !c.is_alphanumeric() {
-                return Err(AddressParseError::InvalidFormat);
-            }
-        }
-        if ssid > 15 {
-            return Err(AddressParseError::SsidOutOfRange);
-        }
-        Ok(Address { callsign, ssid })
-    }
-
-    /// Callsign part of the address, e.g. `VK7NTK`
-    pub fn callsign(&self) -> &str {
-        &self.callsign
-    }
+/// Value of the reserved bits when they are not being used for anything
+const DEFAULT_RESERVED_BITS: u8 = 0b11;
 
-    /// SSID part of the address, e.g. `0`
-    pub fn ssid(&self) -> u8 {
-        self.ssid
+impl PartialEq for Address {
+    fn eq(&self, other: &Self) -> bool {
+        self.callsign == other.callsign && self.ssid == other.ssid
     }
+}
 
-    fn to_bytes(&self, high_bit: bool, final_in_address: bool) -> Vec<u8> {
-        let mut encoded = Vec::new();
-        // Shift by one bit as required for AX.25 address encoding
-        for b in self.callsign.as_bytes() {
-            encoded.push(b << 1);
-        }
-        // Pad with spaces up to length 6
-        while encoded.len() != 6 {
-            encoded.push(b' ' << 1);
-        }
-        // Now do the SSID byte
-        let high = if high_bit { 0b1000_0000 } else { 0 };
-        let low = if final_in_address { 0b0000_0001 } else { 0 };
-        let ssid_byte = (self.ssid << 1) | 0b0110_0000 | high | low;
-        encoded.push(ssid_byte);
+impl Eq for Address {}
 
-        encoded
+impl PartialOrd for Address {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
+impl Ord for Address {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        (&self.callsign, self.ssid).cmp(&(&other.callsign, other.ssid))
+    }
+}
+
+impl core::hash::Hash for Address {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.callsign.hash(state);
+        self.ssid.hash(state);
+    }
+}
+
+impl Address {
+    /// Construct an `Address` like `from_parts`, without needing an owned callsign.
+    ///
+    /// The callsign must be 1-6 ASCII letters or digits and is converted to upper case.
+    /// The SSID must be between 0 and 15 inclusive.
+    pub fn new(callsign: &str, ssid: u8) -> Result<Self, AddressParseError> {
+        Self::from_parts(callsign.to_owned(), ssid)
+    }
+
+    /// Construct an `Address` from callsign and SSID, ensuring that both are valid.
+    pub fn from_parts(callsign: String, ssid: u8) -> Result<Self, AddressParseError> {
+        // Only ASCII is changed, so anything else is still rejected by the same rules
+        // as `is_valid_callsign`
+        let callsign = callsign.to_ascii_uppercase();
+        check_address(&callsign, ssid)?;
+        Ok(Address {
+            callsign,
+            ssid,
+            reserved: DEFAULT_RESERVED_BITS,
+        })
+    }
+
+    /// Construct an `Address` from the seven bytes of an address field as they appear
+    /// on air, without any validation.
+    ///
+    /// The callsign is kept exactly as it was sent apart from trailing space padding,
+    /// so tactical aliases and other non-standard callsigns survive being re-encoded.
+    /// The high and low bits of the SSID byte, which belong to the frame rather than
+    /// the address, are ignored.
+    pub fn from_raw_bytes(bytes: &[u8; 7]) -> Self {
+        let callsign: String = bytes[0..6].iter().map(|&c| char::from(c >> 1)).collect();
+        Address {
+            callsign: callsign.trim_end_matches(' ').to_owned(),
+            ssid: (bytes[6] >> 1) & 0x0f,
+            reserved: (bytes[6] >> 5) & 0b11,
+        }
+    }
+
+    /// Whether `callsign` would be accepted by `Address::new` or `FromStr`: 1-6 ASCII
+    /// letters or digits, in either case. Nothing is allocated.
+    pub fn is_valid_callsign(callsign: &str) -> bool {
+        check_callsign(callsign).is_ok()
+    }
+
+    /// Whether `ssid` is in the range 0 to 15 that an `Address` can hold.
+    pub fn is_valid_ssid(ssid: u8) -> bool {
+        ssid <= 15
+    }
+
+    /// Callsign part of the address, e.g. `VK7NTK`
+    pub fn callsign(&self) -> &str {
+        &self.callsign
+    }
+
+    /// SSID part of the address, e.g. `0`
+    pub fn ssid(&self) -> u8 {
+        self.ssid
+    }
+
+    /// Whether both addresses have the same callsign, ignoring case and SSID.
+    ///
+    /// For example `VK7NTK-1` matches `VK7NTK-9`, since they are the same operator.
+    pub fn matches_callsign(&self, other: &Address) -> bool {
+        self.callsign.eq_ignore_ascii_case(&other.callsign)
+    }
+
+    /// Whether both addresses refer to the same station, with the same callsign
+    /// (ignoring case) and SSID.
+    pub fn same_station(&self, other: &Address) -> bool {
+        self.matches_callsign(other) && self.ssid == other.ssid
+    }
+
+    /// The same callsign with a different SSID, which must be between 0 and 15 inclusive.
+    pub fn with_ssid(self, ssid: u8) -> Result<Self, AddressParseError> {
+        if !Self::is_valid_ssid(ssid) {
+            return Err(AddressParseError::SsidOutOfRange);
+        }
+        Ok(Address { ssid, ..self })
+    }
+
+    /// The two reserved bits of the SSID byte, as a value from 0 to 3.
+    ///
+    /// These are normally both set, giving 3. Some networks use them for their own
+    /// purposes.
+    pub fn reserved_bits(&self) -> u8 {
+        self.reserved
+    }
+
+    /// The same address with different reserved bits. Only the lowest two bits of
+    /// `reserved` are used.
+    pub fn with_reserved_bits(self, reserved: u8) -> Self {
+        Address {
+            reserved: reserved & 0b11,
+            ..self
+        }
+    }
+
+    /// Encode the address as the seven bytes of an address field.
+    ///
+    /// `last` sets the low bit of the SSID byte, which marks the final address in the
+    /// field. `c_bit` sets the high bit, which is the command/response bit for the
+    /// destination and source or the has-been-repeated bit for a repeater.
+    pub fn to_field_bytes(&self, last: bool, c_bit: bool) -> [u8; 7] {
+        // Shift by one bit as required for AX.25 address encoding, padding with spaces
+        let mut encoded = [b' ' << 1; 7];
+        for (e, b) in encoded.iter_mut().zip(self.callsign.as_bytes()) {
+            *e = b << 1;
+        }
+        let high = if c_bit { 0b1000_0000 } else { 0 };
+        let low = if last { 0b0000_0001 } else { 0 };
+        encoded[6] = (self.ssid << 1) | (self.reserved << 5) | high | low;
+        encoded
+    }
+
+    /// Decode the seven bytes of an address field, checking that they hold a valid
+    /// callsign. The high and low bits of the SSID byte are ignored.
+    ///
+    /// Use `from_raw_bytes` to accept non-standard callsigns.
+    pub fn from_field_bytes(bytes: &[u8; 7]) -> Result<Self, AddressParseError> {
+        // Callsign characters are shifted left, so their lowest bit is always clear
+        if bytes[..6].iter().any(|b| b & 1 != 0) {
+            return Err(AddressParseError::InvalidFormat);
+        }
+        let address = Self::from_raw_bytes(bytes);
+        check_address(&address.callsign, address.ssid)?;
+        Ok(address)
+    }
+}
+
+/// Check that an upper case callsign and SSID may be used in an `Address`.
+fn check_address(callsign: &str, ssid: u8) -> Result<(), AddressParseError> {
+    check_callsign(callsign)?;
+    if callsign.bytes().any(|c| c.is_ascii_lowercase()) {
+        return Err(AddressParseError::InvalidFormat);
+    }
+    if !Address::is_valid_ssid(ssid) {
+        return Err(AddressParseError::SsidOutOfRange);
+    }
+    Ok(())
+}
+
+/// Check that a callsign is 1-6 ASCII letters or digits, in either case.
+fn check_callsign(callsign: &str) -> Result<(), AddressParseError> {
+    if callsign.is_empty() {
+        return Err(AddressParseError::InvalidFormat);
+    }
+    if callsign.len() > 6 {
+        return Err(AddressParseError::CallsignTooLong);
+    }
+    if !callsign.bytes().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(AddressParseError::InvalidFormat);
+    }
+    Ok(())
+}
+
 impl Default for Address {
     fn default() -> Address {
         Address {
             callsign: "NOCALL".to_string(),
             ssid: 0,
+            reserved: DEFAULT_RESERVED_BITS,
         }
     }
 }
 
+/// The SSID is omitted when it is 0, which `FromStr` accepts, so any address created
+/// with `new` or `FromStr` displays as a string that parses back to an equal `Address`.
 impl fmt::Display for Address {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let ssid_str = match self.ssid {
@@ -434,6 +1192,12 @@ impl fmt::Display for Address {
     }
 }
 
+/// Parses a callsign optionally followed by `-` and an SSID, as produced by `Display`.
+///
+/// A bare callsign such as `VK7NTK` has SSID 0, and `VK7NTK-0` is accepted as the same
+/// address. The SSID must be written as plain decimal digits, so an empty SSID
+/// (`VK7NTK-`), a leading zero (`VK7NTK-01`) or a sign (`VK7NTK-+1`) is rejected with
+/// `InvalidFormat`, as is anything after a second `-`.
 impl FromStr for Address {
     type Err = AddressParseError;
 
@@ -442,7 +1206,12 @@ impl FromStr for Address {
         if parts.len() == 1 {
             Self::from_parts(parts[0].to_owned(), 0)
         } else if parts.len() == 2 {
-            let ssid = parts[1]
+            let ssid = parts[1];
+            if ssid.is_empty() || ssid.starts_with('+') || (ssid.len() > 1 && ssid.starts_with('0'))
+            {
+                return Err(AddressParseError::InvalidFormat);
+            }
+            let ssid = ssid
                 .parse::<u8>()
                 .map_err(|e| AddressParseError::InvalidSsid { source: e })?;
             Self::from_parts(parts[0].to_owned(), ssid)
@@ -453,7 +1222,7 @@ impl FromStr for Address {
 }
 
 /// A single hop in the frame's route
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RouteEntry {
     /// Callsign-SSID of a repeater to use for source routing.
     pub repeater: Address,
@@ -461,8 +1230,72 @@ pub struct RouteEntry {
     pub has_repeated: bool,
 }
 
-/// A strongly-typed representation of a single AX.25 frame.
+impl RouteEntry {
+    /// Parse a comma-separated list of repeaters such as `WIDE1-1,WIDE2-2` into a route
+    /// for an outgoing frame, with none of them marked as repeated.
+    ///
+    /// A repeater followed by `*` is marked as repeated, so the output of
+    /// `Ax25Frame::path_string` can be parsed back. An empty string gives an empty route.
+    /// At most `MAX_REPEATERS` repeaters are allowed.
+    pub fn parse_path(path: &str) -> Result<Vec<RouteEntry>, PathParseError> {
+        if path.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let route = path
+            .split(',')
+            .map(|hop| {
+                let hop = hop.trim();
+                let (address, has_repeated) = match hop.strip_suffix('*') {
+                    Some(address) => (address, true),
+                    None => (hop, false),
+                };
+                let repeater = address
+                    .parse()
+                    .map_err(|e| PathParseError::InvalidAddress {
+                        address: address.to_owned(),
+                        source: e,
+                    })?;
+                Ok(RouteEntry {
+                    repeater,
+                    has_repeated,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if route.len() > MAX_REPEATERS {
+            return Err(PathParseError::TooManyRepeaters { count: route.len() });
+        }
+        Ok(route)
+    }
+}
+
+/// Where each field of a frame was found within the bytes it was parsed from.
+///
+/// Ranges are indexes into the slice given to `Ax25Frame::from_bytes_annotated`.
 #[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldRanges {
+    pub destination: Range<usize>,
+    pub source: Range<usize>,
+    /// One range for each entry in the frame's route
+    pub repeaters: Vec<Range<usize>>,
+    pub control: Range<usize>,
+    /// Present for I and UI frames
+    pub pid: Option<Range<usize>>,
+    /// Present for frames which carry information, even if it is empty. For FRMR frames
+    /// this covers the three bytes describing the rejection.
+    pub info: Option<Range<usize>>,
+    /// Present when the frame's content has `trailing_bytes`
+    pub trailing: Option<Range<usize>>,
+}
+
+/// A parsed frame together with the location of each of its fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotatedFrame {
+    pub frame: Ax25Frame,
+    pub ranges: FieldRanges,
+}
+
+/// A strongly-typed representation of a single AX.25 frame.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Ax25Frame {
     /// Sending station
     pub source: Address,
@@ -471,7 +1304,8 @@ pub struct Ax25Frame {
     /// The route the frame has taken/will take according to repeater entries in the address field
     pub route: Vec<RouteEntry>,
     /// AX.25 2.0-compliant stations will indicate in every frame whether it is a command
-    /// or a response, as part of the address field.
+    /// or a response, as part of the address field. Frames from 1.x stations are
+    /// `CommandResponse::V1`. If this is `None` the frame is encoded as a command.
     pub command_or_response: Option<CommandResponse>,
     /// Various content depending on the packet type
     pub content: FrameContent,
@@ -479,37 +1313,39 @@ pub struct Ax25Frame {
 
 impl Ax25Frame {
     /// Parse raw bytes into an Ax25Frame if possible.
+    ///
+    /// The bytes must start with the address field. A null byte can never begin a valid
+    /// address so a frame that starts with one, such as those read from a Linux `AF_PACKET`
+    /// socket, is rejected rather than guessed at.
     pub fn from_bytes(bytes: &[u8]) -> Result<Ax25Frame, FrameParseError> {
-        // Skip over leading null bytes
-        // Linux AF_PACKET has one of these - we will strip it out in the linux module
-        // but also keep the protection here
-        let addr_start = bytes
-            .iter()
-            .position(|&c| c != 0)
-            .ok_or(FrameParseError::OnlyNullBytes)?;
+        if bytes.first() == Some(&0) {
+            return Err(FrameParseError::LeadingNullByte);
+        }
         let addr_end = bytes
             .iter()
             .position(|&c| c & 0x01 == 0x01)
             .ok_or(FrameParseError::NoEndToAddressField)?;
         let control = addr_end + 1;
         // +1 because the "terminator" is actually within the last byte
-        if addr_end - addr_start + 1 < 14 {
+        if addr_end + 1 < 14 {
             return Err(FrameParseError::AddressFieldTooShort {
-                start: addr_start,
+                start: 0,
                 end: addr_end,
             });
         }
-        if control >= bytes.len() {
-            return Err(FrameParseError::FrameTooShort { len: bytes.len() });
+        if control % 7 != 0 {
+            return Err(FrameParseError::MisalignedAddressField { len: control });
         }
 
-        let dest = parse_address(&bytes[addr_start..addr_start + 7])?;
-        let src = parse_address(&bytes[addr_start + 7..addr_start + 14])?;
-        let rpt_count = (addr_end + 1 - addr_start - 14) / 7;
+        let dest = parse_address(&bytes[0..7])?;
+        let src = parse_address(&bytes[7..14])?;
+        let rpt_count = (control - 14) / 7;
+        if rpt_count > MAX_REPEATERS {
+            return Err(FrameParseError::TooManyRepeaters { count: rpt_count });
+        }
         let mut route: Vec<RouteEntry> = Vec::new();
         for i in 0..rpt_count {
-            let repeater =
-                parse_address(&bytes[addr_start + 14 + i * 7..addr_start + 14 + (i + 1) * 7])?;
+            let repeater = parse_address(&bytes[14 + i * 7..14 + (i + 1) * 7])?;
             let entry = RouteEntry {
                 has_repeated: repeater.high_bit,
                 repeater: repeater.address,
@@ -518,11 +1354,11 @@ impl Ax25Frame {
         }
 
         let content = parse_content(&bytes[control..])?;
-        let command_or_response = match (dest.high_bit, src.high_bit) {
-            (true, false) => Some(CommandResponse::Command),
-            (false, true) => Some(CommandResponse::Response),
-            _ => None,
-        };
+        let command_or_response = Some(match (dest.high_bit, src.high_bit) {
+            (true, false) => CommandResponse::Command,
+            (false, true) => CommandResponse::Response,
+            (c_bit, _) => CommandResponse::V1 { c_bit },
+        });
 
         Ok(Ax25Frame {
             source: src.address,
@@ -533,6 +1369,64 @@ impl Ax25Frame {
         })
     }
 
+    /// Parse a frame like `from_bytes`, also recording which bytes each field came from.
+    ///
+    /// This is useful for showing the structure of a captured frame.
+    pub fn from_bytes_annotated(bytes: &[u8]) -> Result<AnnotatedFrame, FrameParseError> {
+        let frame = Self::from_bytes(bytes)?;
+        // The frame parsed, so the address field is known to be well-formed
+        let field = |n: usize| n * 7..(n + 1) * 7;
+        let control = (frame.route.len() + 2) * 7;
+        let (pid, info) = match frame.content {
+            FrameContent::Information(_) | FrameContent::UnnumberedInformation(_) => (
+                Some(control + 1..control + 2),
+                Some(control + 2..bytes.len()),
+            ),
+            FrameContent::FrameReject(_)
+            | FrameContent::ExchangeIdentification(_)
+            | FrameContent::Test(_) => (None, Some(control + 1..bytes.len())),
+            _ => (None, None),
+        };
+        let trailing = match frame.content.trailing_bytes().len() {
+            0 => None,
+            len => Some(bytes.len() - len..bytes.len()),
+        };
+        let ranges = FieldRanges {
+            destination: field(0),
+            source: field(1),
+            repeaters: (0..frame.route.len()).map(|i| field(i + 2)).collect(),
+            control: control..control + 1,
+            pid,
+            info,
+            trailing,
+        };
+        Ok(AnnotatedFrame { frame, ranges })
+    }
+
+    /// Parse raw bytes which end with the frame's two byte FCS, as delivered by some
+    /// modems and captures, returning the frame along with the result of checking the
+    /// FCS.
+    ///
+    /// A frame whose FCS does not match is still returned if it can be parsed, so that
+    /// link quality can be measured with `hdlc::FcsStats`. Check `FcsCheck::is_valid`
+    /// before trusting its contents.
+    pub fn from_bytes_with_fcs(bytes: &[u8]) -> Result<(Ax25Frame, FcsCheck), FrameParseError> {
+        // Too short to hold any of the address field once the FCS is removed
+        let (contents, check) = check_fcs(bytes)
+            .map_err(|_| FrameParseError::AddressFieldTooShort { start: 0, end: 0 })?;
+        Ok((Self::from_bytes(contents)?, check))
+    }
+
+    /// Construct a frame with the given content, sent as a command with no route.
+    pub fn new(source: Address, destination: Address, content: FrameContent) -> Self {
+        Self {
+            source,
+            destination,
+            content,
+            ..Default::default()
+        }
+    }
+
     /// Construct a basic UnnumberedInformation (connectionless) frame with chosen data.
     pub fn new_simple_ui_frame(source: Address, destination: Address, info: Vec<u8>) -> Self {
         Self {
@@ -547,22 +1441,319 @@ impl Ax25Frame {
         }
     }
 
+    /// The same frame sent via the given repeaters, in order, replacing any existing
+    /// route. None of them are marked as repeated.
+    ///
+    /// At most `MAX_REPEATERS` repeaters are allowed. To give the path as a string such
+    /// as `WIDE1-1,WIDE2-2`, use `RouteEntry::parse_path` and set `route` directly.
+    pub fn with_path(self, path: &[Address]) -> Result<Self, EncodeError> {
+        if path.len() > MAX_REPEATERS {
+            return Err(EncodeError::TooManyRepeaters { count: path.len() });
+        }
+        let route = path
+            .iter()
+            .map(|repeater| RouteEntry {
+                repeater: repeater.clone(),
+                has_repeated: false,
+            })
+            .collect();
+        Ok(Ax25Frame { route, ..self })
+    }
+
+    /// Construct a UI frame which answers this one, addressed back to its source from
+    /// its destination.
+    ///
+    /// The route is the part of this frame's route which was actually repeated, in
+    /// reverse, so the reply retraces the path this frame took to reach us. None of its
+    /// entries are marked as repeated. The reply is a response if this frame was a
+    /// command and a command otherwise.
+    pub fn reply(&self, info: Vec<u8>, pid: ProtocolIdentifier) -> Ax25Frame {
+        let command_or_response = match self.command_or_response {
+            Some(CommandResponse::Command) => CommandResponse::Response,
+            _ => CommandResponse::Command,
+        };
+        Ax25Frame {
+            source: self.destination.clone(),
+            destination: self.source.clone(),
+            route: self.return_route(),
+            command_or_response: Some(command_or_response),
+            content: FrameContent::UnnumberedInformation(UnnumberedInformation {
+                pid,
+                info,
+                poll_or_final: false,
+            }),
+        }
+    }
+
+    /// If this frame is a TEST command, construct the TEST response which echoes its
+    /// information field back to the sender, with the final bit set to match the poll
+    /// bit. The response is addressed and routed as for `reply`.
+    ///
+    /// Returns `None` for any other frame, including a TEST response, so that two
+    /// stations never answer each other's answers.
+    pub fn test_response(&self) -> Option<Ax25Frame> {
+        match (&self.content, &self.command_or_response) {
+            (FrameContent::Test(test), &Some(CommandResponse::Command)) => Some(Ax25Frame {
+                source: self.destination.clone(),
+                destination: self.source.clone(),
+                route: self.return_route(),
+                command_or_response: Some(CommandResponse::Response),
+                content: FrameContent::Test(test.clone()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The repeated part of this frame's route in reverse, with nothing marked as
+    /// repeated yet.
+    fn return_route(&self) -> Vec<RouteEntry> {
+        self.route
+            .iter()
+            .rev()
+            .filter(|entry| entry.has_repeated)
+            .map(|entry| RouteEntry {
+                repeater: entry.repeater.clone(),
+                has_repeated: false,
+            })
+            .collect()
+    }
+
+    /// Encode an Ax25Frame struct as raw bytes for transmission, checking first that
+    /// the result will be a valid frame.
+    ///
+    /// The information field may be at most `DEFAULT_MAX_INFO_LEN` bytes.
+    pub fn try_to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        self.try_to_bytes_with_max_info(DEFAULT_MAX_INFO_LEN)
+    }
+
+    /// Encode as raw bytes like `try_to_bytes`, for a network which has agreed on a
+    /// different maximum information field length.
+    pub fn try_to_bytes_with_max_info(&self, max_info_len: usize) -> Result<Vec<u8>, EncodeError> {
+        if self.route.len() > MAX_REPEATERS {
+            return Err(EncodeError::TooManyRepeaters {
+                count: self.route.len(),
+            });
+        }
+        let info_len = match &self.content {
+            FrameContent::Information(i) => i.info.len(),
+            FrameContent::UnnumberedInformation(ui) => ui.info.len(),
+            FrameContent::Test(test) => test.info.len(),
+            _ => 0,
+        };
+        if info_len > max_info_len {
+            return Err(EncodeError::InfoTooLong {
+                len: info_len,
+                max: max_info_len,
+            });
+        }
+        self.content.try_encode()?;
+        Ok(self.to_bytes())
+    }
+
+    /// Every address in the frame: the destination, the source, then each repeater in
+    /// the order they appear in the route.
+    pub fn addresses(&self) -> impl Iterator<Item = &Address> {
+        [&self.destination, &self.source]
+            .into_iter()
+            .chain(self.route.iter().map(|entry| &entry.repeater))
+    }
+
+    /// The route as a comma-separated list of repeaters in the style of TNC2 monitor
+    /// logs, with a `*` after each one that has repeated the frame, such as
+    /// `WIDE1-1*,WIDE2-2`. A frame with no route gives an empty string.
+    pub fn path_string(&self) -> String {
+        let hops: Vec<String> = self
+            .route
+            .iter()
+            .map(|entry| {
+                if entry.has_repeated {
+                    format!("{}*", entry.repeater)
+                } else {
+                    entry.repeater.to_string()
+                }
+            })
+            .collect();
+        hops.join(",")
+    }
+
+    /// Whether the frame was heard directly from the source station, which is the case
+    /// when no repeater in the route has marked it as repeated.
+    pub fn heard_direct(&self) -> bool {
+        !self.route.iter().any(|entry| entry.has_repeated)
+    }
+
+    /// The repeater that most recently transmitted this frame, which is the last entry
+    /// in the route marked as repeated.
+    pub fn last_repeater(&self) -> Option<&Address> {
+        self.route
+            .iter()
+            .rev()
+            .find(|entry| entry.has_repeated)
+            .map(|entry| &entry.repeater)
+    }
+
+    /// Estimate how long this frame takes to transmit at `baud` bits per second.
+    ///
+    /// This counts the encoded frame, a two byte FCS and one opening and one closing
+    /// HDLC flag, plus the bits stuffed into the frame bytes after every five
+    /// consecutive ones. Stuffing within the FCS is not counted since it depends on the
+    /// checksum. TXDELAY, TXTAIL and any preamble the modem sends are not included.
+    ///
+    /// Panics if `baud` is zero.
+    pub fn air_time(&self, baud: u32) -> core::time::Duration {
+        const FLAG_BYTES: u64 = 2;
+        const FCS_BYTES: u64 = 2;
+        let bytes = self.to_bytes();
+        let bits = (bytes.len() as u64 + FCS_BYTES + FLAG_BYTES) * 8 + stuffed_bits(&bytes);
+        core::time::Duration::from_nanos(bits * 1_000_000_000 / baud as u64)
+    }
+
+    /// Check everything about this frame that `to_bytes` would silently tolerate,
+    /// returning every problem found rather than stopping at the first.
+    ///
+    /// The information field may be at most `DEFAULT_MAX_INFO_LEN` bytes.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        self.validate_with_max_info(DEFAULT_MAX_INFO_LEN)
+    }
+
+    /// Check the frame like `validate`, for a network which has agreed on a different
+    /// maximum information field length.
+    pub fn validate_with_max_info(&self, max_info_len: usize) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        for address in self.addresses() {
+            if let Err(e) = check_address(&address.callsign, address.ssid) {
+                errors.push(ValidationError::InvalidAddress {
+                    callsign: address.callsign.clone(),
+                    ssid: address.ssid,
+                    source: e,
+                });
+            }
+        }
+        if self.route.len() > MAX_REPEATERS {
+            errors.push(ValidationError::TooManyRepeaters {
+                count: self.route.len(),
+            });
+        }
+        let info_len = match &self.content {
+            FrameContent::Information(i) => i.info.len(),
+            FrameContent::UnnumberedInformation(ui) => ui.info.len(),
+            FrameContent::Test(test) => test.info.len(),
+            _ => 0,
+        };
+        if info_len > max_info_len {
+            errors.push(ValidationError::InfoTooLong {
+                len: info_len,
+                max: max_info_len,
+            });
+        }
+        for value in self.content.sequence_numbers() {
+            if value > 7 {
+                errors.push(ValidationError::SequenceOutOfRange { value });
+            }
+        }
+        if !self.content.trailing_bytes().is_empty() {
+            errors.push(ValidationError::TrailingBytes {
+                len: self.content.trailing_bytes().len(),
+            });
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Parse a frame written as hex, such as one copied from a log or a hex dump.
+    ///
+    /// Whitespace and colons separate groups of digits and are otherwise ignored. Each
+    /// group is either a single digit, as printed by `{:X}`, or pairs of digits, so
+    /// `"82A0AAEA"`, `"82 A0 AA EA"` and `"82:a0:aa:ea"` are all accepted.
+    pub fn from_hex(hex: &str) -> Result<Ax25Frame, HexParseError> {
+        let mut bytes = Vec::with_capacity(hex.len() / 2);
+        let mut group_start = 0;
+        let mut digits: Vec<u8> = Vec::new();
+        // A trailing separator closes the final group
+        for (position, character) in hex.char_indices().chain(Some((hex.len(), ' '))) {
+            if character.is_whitespace() || character == ':' {
+                match digits.len() {
+                    0 => {}
+                    1 => bytes.push(digits[0]),
+                    n if n % 2 == 0 => bytes.extend(digits.chunks(2).map(|p| p[0] << 4 | p[1])),
+                    _ => {
+                        return Err(HexParseError::UnpairedDigit {
+                            position: group_start,
+                        })
+                    }
+                }
+                digits.clear();
+                continue;
+            }
+            let digit = character
+                .to_digit(16)
+                .ok_or(HexParseError::InvalidCharacter {
+                    position,
+                    character,
+                })?;
+            if digits.is_empty() {
+                group_start = position;
+            }
+            digits.push(digit as u8);
+        }
+        Ax25Frame::from_bytes(&bytes).map_err(|e| HexParseError::Frame { source: e })
+    }
+
+    /// Encode the frame like `to_bytes` and write the bytes as upper case hex pairs
+    /// separated by spaces, which `from_hex` can read back.
+    pub fn to_hex(&self) -> String {
+        let mut hex = String::new();
+        for (i, byte) in self.to_bytes().iter().enumerate() {
+            if i > 0 {
+                hex.push(' ');
+            }
+            hex.push_str(&alloc::format!("{:02X}", byte));
+        }
+        hex
+    }
+
     /// Encode an Ax25Frame struct as raw bytes for transmission
+    ///
+    /// No validation is performed and sequence numbers above 7 are truncated. Use
+    /// `try_to_bytes` if the frame has not already been checked.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut frame = Vec::new();
+        // Assume Command
+        self.to_bytes_with_default_c_bits(true, false)
+    }
+
+    /// Encode the frame like `to_bytes`, but with the given destination and source
+    /// c-bits if `command_or_response` is `None`.
+    ///
+    /// `to_bytes` marks such frames as commands. Passing `false, false` instead produces
+    /// frames with both bits clear, as expected by some AX.25 1.x equipment.
+    pub fn to_bytes_with_default_c_bits(&self, dest_c_bit: bool, src_c_bit: bool) -> Vec<u8> {
         let (dest_c_bit, src_c_bit) = match self.command_or_response {
             Some(CommandResponse::Command) => (true, false),
             Some(CommandResponse::Response) => (false, true),
-            _ => (true, false), // assume Command
+            Some(CommandResponse::V1 { c_bit }) => (c_bit, c_bit),
+            None => (dest_c_bit, src_c_bit),
         };
-        frame.extend(self.destination.to_bytes(dest_c_bit, false));
-        frame.extend(self.source.to_bytes(src_c_bit, self.route.is_empty()));
+        self.to_bytes_with_c_bits(dest_c_bit, src_c_bit)
+    }
+
+    /// Encode the frame like `to_bytes` but with the destination and source c-bits given
+    /// explicitly, ignoring `command_or_response`.
+    ///
+    /// This can produce the AX.25 1.x patterns where both bits are set or both are clear,
+    /// which is useful for testing how other stations handle them.
+    pub fn to_bytes_with_c_bits(&self, dest_c_bit: bool, src_c_bit: bool) -> Vec<u8> {
+        let mut frame = Vec::new();
+        frame.extend(self.destination.to_field_bytes(false, dest_c_bit));
+        frame.extend(self.source.to_field_bytes(self.route.is_empty(), src_c_bit));
 
         for (i, entry) in self.route.iter().enumerate() {
             frame.extend(
                 entry
                     .repeater
-                    .to_bytes(entry.has_repeated, i + 1 == self.route.len()),
+                    .to_field_bytes(i + 1 == self.route.len(), entry.has_repeated),
             );
         }
 
@@ -570,18 +1761,121 @@ impl Ax25Frame {
         frame
     }
 
+    /// Whether this frame follows AX.25 1.x or 2.0 conventions for the c-bits.
+    ///
+    /// Frames from 1.x stations do not say whether they are a command or a response,
+    /// so `command_or_response` is `CommandResponse::V1` for these. A frame with no
+    /// `command_or_response` is V2, since `to_bytes` encodes it as a command.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        match self.command_or_response {
+            Some(CommandResponse::V1 { .. }) => ProtocolVersion::V1,
+            Some(_) | None => ProtocolVersion::V2,
+        }
+    }
+
+    /// A value identifying this frame for duplicate suppression, made up of the source,
+    /// destination and information field.
+    ///
+    /// The route is not included, so copies of a frame that arrive via different
+    /// digipeaters produce equal keys, as is conventional for APRS.
+    pub fn dedup_key(&self) -> (&Address, &Address, &[u8]) {
+        let info: &[u8] = match &self.content {
+            FrameContent::Information(i) => &i.info,
+            FrameContent::UnnumberedInformation(ui) => &ui.info,
+            _ => &[],
+        };
+        (&self.source, &self.destination, info)
+    }
+
+    /// The next repeater in the route which has not yet repeated this frame, if any.
+    pub fn next_repeater(&self) -> Option<&RouteEntry> {
+        self.route.iter().find(|entry| !entry.has_repeated)
+    }
+
+    /// Record that `repeater` has repeated this frame by setting the flag on its entry
+    /// in the route.
+    ///
+    /// Only the next unrepeated entry is considered, since a station should not repeat
+    /// a frame out of order. Returns `true` if that entry matched `repeater`.
+    pub fn mark_repeated(&mut self, repeater: &Address) -> bool {
+        match self.route.iter_mut().find(|entry| !entry.has_repeated) {
+            Some(entry) if entry.repeater == *repeater => {
+                entry.has_repeated = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if this frame is an I or UI frame carrying at least one byte of
+    /// information. Frames whose info field is present but zero-length return false.
+    pub fn has_info(&self) -> bool {
+        match self.content {
+            FrameContent::Information(ref i) => !i.info.is_empty(),
+            FrameContent::UnnumberedInformation(ref ui) => !ui.info.is_empty(),
+            _ => false,
+        }
+    }
+
     /// Returns a UTF-8 string that is a "best effort" at displaying the information
-    /// content of this frame. Returns None if there is no information field present.
+    /// content of this frame. Returns None if there is no information field present,
+    /// including I and UI frames whose info field is empty.
     /// Most applications will need to work with the Vec<u8> info directly.
     pub fn info_string_lossy(&self) -> Option<String> {
         match self.content {
-            FrameContent::Information(ref i) => Some(String::from_utf8_lossy(&i.info).into_owned()),
-            FrameContent::UnnumberedInformation(ref ui) => {
+            FrameContent::Information(ref i) if !i.info.is_empty() => {
+                Some(String::from_utf8_lossy(&i.info).into_owned())
+            }
+            FrameContent::UnnumberedInformation(ref ui) if !ui.info.is_empty() => {
                 Some(String::from_utf8_lossy(&ui.info).into_owned())
             }
             _ => None,
         }
     }
+
+    /// A summary of the control field in the style TNCs use when monitoring traffic,
+    /// such as `<I R2 S3 P>`, `<RR F R5>` or `<UI>`.
+    ///
+    /// A set P/F bit is shown as `P` on a command and `F` on a response. For frame
+    /// types that can be either, a frame without a command/response indication is
+    /// treated as a command.
+    pub fn control_summary(&self) -> String {
+        let pf = |set: bool| match (set, &self.command_or_response) {
+            (false, _) => "",
+            (true, Some(CommandResponse::Response)) => " F",
+            (true, _) => " P",
+        };
+        let p = |set: bool| if set { " P" } else { "" };
+        let f = |set: bool| if set { " F" } else { "" };
+        match &self.content {
+            FrameContent::Information(i) => format!(
+                "<I R{} S{}{}>",
+                i.receive_sequence,
+                i.send_sequence,
+                p(i.poll)
+            ),
+            FrameContent::ReceiveReady(rr) => {
+                format!("<RR{} R{}>", pf(rr.poll_or_final), rr.receive_sequence)
+            }
+            FrameContent::ReceiveNotReady(rnr) => {
+                format!("<RNR{} R{}>", pf(rnr.poll_or_final), rnr.receive_sequence)
+            }
+            FrameContent::Reject(rej) => {
+                format!("<REJ{} R{}>", pf(rej.poll_or_final), rej.receive_sequence)
+            }
+            FrameContent::SetAsynchronousBalancedMode(sabm) => format!("<SABM{}>", p(sabm.poll)),
+            FrameContent::Disconnect(disc) => format!("<DISC{}>", p(disc.poll)),
+            FrameContent::DisconnectedMode(dm) => format!("<DM{}>", f(dm.final_bit)),
+            FrameContent::UnnumberedAcknowledge(ua) => format!("<UA{}>", f(ua.final_bit)),
+            FrameContent::FrameReject(frmr) => format!("<FRMR{}>", f(frmr.final_bit)),
+            FrameContent::UnnumberedInformation(ui) => format!("<UI{}>", pf(ui.poll_or_final)),
+            FrameContent::ExchangeIdentification(xid) => {
+                format!("<XID{}>", pf(xid.poll_or_final))
+            }
+            FrameContent::Test(test) => format!("<TEST{}>", pf(test.poll_or_final)),
+            FrameContent::UnknownContent(_) => "<?>".to_string(),
+        }
+    }
 }
 
 impl Default for Ax25Frame {
@@ -602,16 +1896,15 @@ impl Default for Ax25Frame {
 
 impl fmt::Display for Ax25Frame {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let info_display = match self.info_string_lossy() {
-            Some(ref info) => info.clone(),
-            None => "-".to_string(),
-        };
         write!(
             f,
-            "Source\t\t{}\nDestination\t{}\n\
-             Data\t\t\"{}\"",
-            self.source, self.destination, info_display
-        )
+            "Source\t\t{}\nDestination\t{}\nData\t\t",
+            self.source, self.destination
+        )?;
+        match self.info_string_lossy() {
+            Some(info) => write!(f, "\"{}\"", info),
+            None => write!(f, "-"),
+        }
     }
 }
 
@@ -622,20 +1915,10 @@ struct ParsedAddress {
 }
 
 fn parse_address(bytes: &[u8]) -> Result<ParsedAddress, FrameParseError> {
-    let mut dest_utf8: Vec<u8> = bytes[0..6]
-        .iter()
-        .rev()
-        .map(|&c| c >> 1)
-        .skip_while(|&c| c == b' ')
-        .collect::<Vec<u8>>();
-    dest_utf8.reverse();
-    let address = Address {
-        callsign: String::from_utf8(dest_utf8)
-            .map_err(|e| FrameParseError::AddressInvalidUtf8 { source: e })?,
-        ssid: (bytes[6] >> 1) & 0x0f,
-    };
+    let mut field = [0u8; 7];
+    field.copy_from_slice(&bytes[0..7]);
     Ok(ParsedAddress {
-        address,
+        address: Address::from_raw_bytes(&field),
         high_bit: bytes[6] & 0b1000_0000 > 0,
     })
 }
@@ -660,19 +1943,23 @@ fn parse_s_frame(bytes: &[u8]) -> Result<FrameContent, FrameParseError> {
     let c = bytes[0];
     let n_r = (c & 0b1110_0000) >> 5;
     let poll_or_final = (c & 0b0001_0000) > 0;
+    let trailing = bytes[1..].to_vec();
 
     match c & 0b0000_1111 {
         0b0000_0001 => Ok(FrameContent::ReceiveReady(ReceiveReady {
             receive_sequence: n_r,
             poll_or_final,
+            trailing,
         })),
         0b0000_0101 => Ok(FrameContent::ReceiveNotReady(ReceiveNotReady {
             receive_sequence: n_r,
             poll_or_final,
+            trailing,
         })),
         0b0000_1001 => Ok(FrameContent::Reject(Reject {
             receive_sequence: n_r,
             poll_or_final,
+            trailing,
         })),
         _ => Err(FrameParseError::UnrecognisedSFieldType),
     }
@@ -685,24 +1972,35 @@ fn parse_u_frame(bytes: &[u8]) -> Result<FrameContent, FrameParseError> {
     // UI is followed by PID and variable length information field
     let c = bytes[0];
     let poll_or_final = c & 0b0001_0000 > 0;
+    // Only kept for the types which should end with the control field
+    let trailing = bytes[1..].to_vec();
 
     // Ignore the P/F bit for identifying the command or response
     match c & 0b1110_1111 {
         0b0010_1111 => Ok(FrameContent::SetAsynchronousBalancedMode(
             SetAsynchronousBalancedMode {
                 poll: poll_or_final,
+                trailing,
             },
         )),
         0b0100_0011 => Ok(FrameContent::Disconnect(Disconnect {
             poll: poll_or_final,
+            trailing,
         })),
         0b0000_1111 => Ok(FrameContent::DisconnectedMode(DisconnectedMode {
             final_bit: poll_or_final,
+            trailing,
         })),
         0b0110_0011 => Ok(FrameContent::UnnumberedAcknowledge(UnnumberedAcknowledge {
             final_bit: poll_or_final,
+            trailing,
         })),
         0b1000_0111 => parse_frmr_frame(bytes),
+        0b1010_1111 => parse_xid_frame(bytes),
+        0b1110_0011 => Ok(FrameContent::Test(Test {
+            poll_or_final,
+            info: bytes[1..].to_vec(),
+        })),
         0b0000_0011 => parse_ui_frame(bytes),
         _ => Err(FrameParseError::UnrecognisedUFieldType),
     }
@@ -734,14 +2032,49 @@ fn parse_frmr_frame(bytes: &[u8]) -> Result<FrameContent, FrameParseError> {
         w: bytes[1] & 0b0000_0001 > 0,
         receive_sequence: (bytes[2] & 0b1110_0000) >> 5,
         command_response: if bytes[2] & 0b0001_0000 > 0 {
-            CommandResponse::Response
+            RejectedCommandResponse::Response
         } else {
-            CommandResponse::Command
+            RejectedCommandResponse::Command
         },
         send_sequence: (bytes[2] & 0b0000_1110) >> 1,
+        reserved_bits: (bytes[1] & 0b1111_0000) | (bytes[2] & 0b0000_0001),
     }))
 }
 
+fn parse_xid_frame(bytes: &[u8]) -> Result<FrameContent, FrameParseError> {
+    let poll_or_final = bytes[0] & 0b0001_0000 > 0;
+    let info = &bytes[1..];
+    let mut parameters = Vec::new();
+    if !info.is_empty() {
+        // FI, GI and a two byte group length, then the whole group
+        if info.len() < 4 || info[0] != XID_FORMAT_INDICATOR || info[1] != XID_GROUP_IDENTIFIER {
+            return Err(FrameParseError::MalformedXidInfo);
+        }
+        let group_len = u16::from_be_bytes([info[2], info[3]]) as usize;
+        let mut group = &info[4..];
+        if group.len() != group_len {
+            return Err(FrameParseError::MalformedXidInfo);
+        }
+        while !group.is_empty() {
+            if group.len() < 2 || group.len() < 2 + group[1] as usize {
+                return Err(FrameParseError::MalformedXidInfo);
+            }
+            let end = 2 + group[1] as usize;
+            parameters.push(XidParameter {
+                identifier: group[0],
+                value: group[2..end].to_vec(),
+            });
+            group = &group[end..];
+        }
+    }
+    Ok(FrameContent::ExchangeIdentification(
+        ExchangeIdentification {
+            poll_or_final,
+            parameters,
+        },
+    ))
+}
+
 /// Parse the content of the frame starting from the control field
 fn parse_content(bytes: &[u8]) -> Result<FrameContent, FrameParseError> {
     if bytes.is_empty() {
@@ -757,6 +2090,27 @@ fn parse_content(bytes: &[u8]) -> Result<FrameContent, FrameParseError> {
     }
 }
 
+/// Count the zero bits HDLC would insert after each run of five ones, sending each
+/// byte least significant bit first.
+fn stuffed_bits(bytes: &[u8]) -> u64 {
+    let mut stuffed = 0;
+    let mut ones = 0;
+    for &byte in bytes {
+        for bit in 0..8 {
+            if byte & (1 << bit) == 0 {
+                ones = 0;
+                continue;
+            }
+            ones += 1;
+            if ones == 5 {
+                stuffed += 1;
+                ones = 0;
+            }
+        }
+    }
+    stuffed
+}
+
 #[test]
 fn pid_test() {
     assert_eq!(
@@ -777,16 +2131,22 @@ fn pid_test() {
     );
     assert_eq!(
         ProtocolIdentifier::from_byte(0x10),
-        ProtocolIdentifier::Layer3Impl
+        ProtocolIdentifier::Layer3Impl(Layer3Pid::new(0x10).unwrap())
     );
     assert_eq!(
         ProtocolIdentifier::from_byte(0x20),
-        ProtocolIdentifier::Layer3Impl
+        ProtocolIdentifier::Layer3Impl(Layer3Pid::new(0x20).unwrap())
     );
     assert_eq!(
         ProtocolIdentifier::from_byte(0xA5),
-        ProtocolIdentifier::Layer3Impl
+        ProtocolIdentifier::Layer3Impl(Layer3Pid::new(0xA5).unwrap())
     );
+    assert_eq!(Layer3Pid::new(0xF0), None);
+    assert_eq!(ProtocolIdentifier::from(0xF0), ProtocolIdentifier::None);
+    assert_eq!(u8::from(&ProtocolIdentifier::NetRom), 0xCF);
+    for byte in [0x01, 0x06, 0x10, 0xA5, 0xCA, 0xCF, 0xF0, 0xFF, 0x45] {
+        assert_eq!(u8::from(&ProtocolIdentifier::from(byte)), byte);
+    }
 }
 
 #[test]
@@ -797,6 +2157,7 @@ fn test_address_fromstr() {
         Address {
             callsign: "VK7NTK".to_string(),
             ssid: 1,
+            reserved: DEFAULT_RESERVED_BITS,
         }
     );
     assert_eq!(
@@ -804,6 +2165,7 @@ fn test_address_fromstr() {
         Address {
             callsign: "ID".to_string(),
             ssid: 15,
+            reserved: DEFAULT_RESERVED_BITS,
         }
     );
 
@@ -827,6 +2189,1148 @@ fn test_address_fromstr() {
     assert!(Address::from_str("vk7n--1").is_err());
 }
 
+#[test]
+fn test_is_valid_callsign() {
+    for good in ["VK7NTK", "vk7ntk", "N0CALL", "A"] {
+        assert!(Address::is_valid_callsign(good), "{}", good);
+        assert!(Address::new(good, 0).is_ok());
+    }
+    for bad in ["", "VK7NTK1", "VK7-NT", "VK7 N", "VK7ÑT"] {
+        assert!(!Address::is_valid_callsign(bad), "{}", bad);
+        assert!(Address::new(bad, 0).is_err());
+    }
+    assert!(Address::is_valid_ssid(0));
+    assert!(Address::is_valid_ssid(15));
+    assert!(!Address::is_valid_ssid(16));
+
+    // Some non-ASCII letters have ASCII upper case forms, but are still not allowed
+    for callsign in [
+        "VK7NTK",
+        "vk7ntk",
+        "ſ",
+        "vk7ıt",
+        "ß",
+        "ﬀ",
+        "ſſſſſſ",
+        "ÅB",
+        "",
+    ] {
+        let valid = Address::is_valid_callsign(callsign);
+        assert_eq!(Address::new(callsign, 0).is_ok(), valid, "{}", callsign);
+        assert_eq!(callsign.parse::<Address>().is_ok(), valid, "{}", callsign);
+    }
+}
+
+#[test]
+fn test_address_fromstr_strict_ssid() {
+    assert_eq!(
+        Address::from_str("VK7NTK-0").unwrap(),
+        Address::from_str("VK7NTK").unwrap()
+    );
+    assert_eq!(Address::from_str("VK7NTK-10").unwrap().ssid(), 10);
+    for bad in [
+        "VK7NTK-",
+        "VK7NTK-01",
+        "VK7NTK-00",
+        "VK7NTK-+1",
+        "VK7NTK-1-extra",
+    ] {
+        assert!(
+            matches!(
+                Address::from_str(bad),
+                Err(AddressParseError::InvalidFormat)
+            ),
+            "{}",
+            bad
+        );
+    }
+    assert!(matches!(
+        Address::from_str("VK7NTK-x"),
+        Err(AddressParseError::InvalidSsid { .. })
+    ));
+    assert!(matches!(
+        Address::from_str("VK7NTK-99"),
+        Err(AddressParseError::SsidOutOfRange)
+    ));
+}
+
+#[test]
+fn test_address_display_round_trip() {
+    for callsign in ["VK7NTK", "ID", "8", "N0CALL"] {
+        for ssid in 0..=15 {
+            let addr = Address::new(callsign, ssid).unwrap();
+            let displayed = addr.to_string();
+            assert_eq!(displayed.contains('-'), ssid != 0);
+            assert_eq!(displayed.parse::<Address>().unwrap(), addr);
+        }
+    }
+    // An explicit SSID of 0 is accepted too, even though it is never displayed
+    assert_eq!(Address::from_str("VK7NTK-0").unwrap().to_string(), "VK7NTK");
+}
+
+#[test]
+fn test_address_new() {
+    let addr = Address::new("vk7ntk", 2).unwrap();
+    assert_eq!(addr.callsign(), "VK7NTK");
+    assert_eq!(addr.ssid(), 2);
+    assert_eq!(addr, Address::from_str("VK7NTK-2").unwrap());
+
+    assert!(matches!(
+        Address::new("VK7NTKX", 0),
+        Err(AddressParseError::CallsignTooLong)
+    ));
+    assert!(matches!(
+        Address::new("", 0),
+        Err(AddressParseError::InvalidFormat)
+    ));
+    assert!(matches!(
+        Address::new("VK7NÉ", 0),
+        Err(AddressParseError::InvalidFormat)
+    ));
+    assert!(matches!(
+        Address::new("VK7NTK", 16),
+        Err(AddressParseError::SsidOutOfRange)
+    ));
+
+    let moved = addr.with_ssid(9).unwrap();
+    assert_eq!(moved, Address::from_str("VK7NTK-9").unwrap());
+    assert!(moved.with_ssid(16).is_err());
+}
+
+#[test]
+fn test_reserved_bits_round_trip() {
+    let frame = Ax25Frame::new_simple_ui_frame(
+        Address::new("VK7NTK", 1).unwrap().with_reserved_bits(0b01),
+        Address::new("IDENT", 0).unwrap().with_reserved_bits(0b00),
+        b"hello".to_vec(),
+    );
+    let bytes = frame.to_bytes();
+    assert_eq!(bytes[6] & 0b0110_0000, 0b0000_0000);
+    assert_eq!(bytes[13] & 0b0110_0000, 0b0010_0000);
+
+    let parsed = Ax25Frame::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed.source.reserved_bits(), 0b01);
+    assert_eq!(parsed.destination.reserved_bits(), 0b00);
+    assert_eq!(parsed.to_bytes(), bytes);
+
+    // Reserved bits are not part of an address's identity
+    assert_eq!(parsed.source, Address::new("VK7NTK", 1).unwrap());
+    assert_eq!(Address::default().reserved_bits(), 0b11);
+}
+
+#[test]
+fn test_repeater_limit() {
+    let mut frame = Ax25Frame::new_simple_ui_frame(
+        Address::new("VK7NTK", 1).unwrap(),
+        Address::new("IDENT", 0).unwrap(),
+        b"hello".to_vec(),
+    );
+    for i in 0..MAX_REPEATERS {
+        frame.route.push(RouteEntry {
+            repeater: Address::new("RPT", i as u8).unwrap(),
+            has_repeated: false,
+        });
+    }
+    let bytes = frame.try_to_bytes().unwrap();
+    assert_eq!(Ax25Frame::from_bytes(&bytes).unwrap(), frame);
+
+    frame.route.push(RouteEntry {
+        repeater: Address::new("RPT", 9).unwrap(),
+        has_repeated: false,
+    });
+    assert!(matches!(
+        frame.try_to_bytes(),
+        Err(EncodeError::TooManyRepeaters { count: 9 })
+    ));
+    assert!(matches!(
+        Ax25Frame::from_bytes(&frame.to_bytes()),
+        Err(FrameParseError::TooManyRepeaters { count: 9 })
+    ));
+}
+
+#[test]
+fn test_info_limit() {
+    let mut frame = Ax25Frame::new_simple_ui_frame(
+        Address::new("VK7NTK", 1).unwrap(),
+        Address::new("IDENT", 0).unwrap(),
+        vec![b'x'; DEFAULT_MAX_INFO_LEN],
+    );
+    assert!(frame.try_to_bytes().is_ok());
+
+    if let FrameContent::UnnumberedInformation(ui) = &mut frame.content {
+        ui.info.push(b'x');
+    }
+    assert!(matches!(
+        frame.try_to_bytes(),
+        Err(EncodeError::InfoTooLong { len: 257, max: 256 })
+    ));
+    assert!(frame.try_to_bytes_with_max_info(512).is_ok());
+    assert!(matches!(
+        frame.try_to_bytes_with_max_info(128),
+        Err(EncodeError::InfoTooLong { len: 257, max: 128 })
+    ));
+}
+
+#[test]
+fn test_sequence_range_checked() {
+    let mut content = FrameContent::Information(Information {
+        pid: ProtocolIdentifier::None,
+        info: b"hi".to_vec(),
+        receive_sequence: 7,
+        send_sequence: 3,
+        poll: false,
+    });
+    assert_eq!(content.try_encode().unwrap(), content.encode());
+
+    if let FrameContent::Information(i) = &mut content {
+        i.send_sequence = 11;
+    }
+    assert!(matches!(
+        content.try_encode(),
+        Err(EncodeError::SequenceOutOfRange { value: 11 })
+    ));
+    // The infallible encode truncates to 3
+    assert_eq!(content.encode()[0], 0b1110_0110);
+
+    let frame = Ax25Frame {
+        source: Address::new("VK7NTK", 1).unwrap(),
+        destination: Address::new("VK7NTK", 2).unwrap(),
+        content: FrameContent::ReceiveReady(ReceiveReady {
+            receive_sequence: 8,
+            poll_or_final: false,
+            trailing: vec![],
+        }),
+        ..Default::default()
+    };
+    assert!(matches!(
+        frame.try_to_bytes(),
+        Err(EncodeError::SequenceOutOfRange { value: 8 })
+    ));
+}
+
+#[test]
+fn test_frame_kind() {
+    let frame = Ax25Frame::new_simple_ui_frame(
+        Address::new("VK7NTK", 1).unwrap(),
+        Address::new("IDENT", 0).unwrap(),
+        b"hi".to_vec(),
+    );
+    assert_eq!(frame.content.kind(), FrameKind::UnnumberedInformation);
+    assert!(frame.content.is_unnumbered());
+    assert!(!frame.content.is_supervisory());
+
+    let rej = FrameContent::Reject(Reject {
+        receive_sequence: 1,
+        poll_or_final: false,
+        trailing: vec![],
+    });
+    assert_eq!(rej.kind(), FrameKind::Reject);
+    assert!(rej.is_supervisory());
+    assert!(!rej.is_information());
+
+    let unknown = FrameContent::UnknownContent(UnknownContent { raw: vec![0x00] });
+    assert!(!unknown.is_information() && !unknown.is_supervisory() && !unknown.is_unnumbered());
+}
+
+#[test]
+fn test_xid_round_trip() {
+    let mut xid = ExchangeIdentification {
+        poll_or_final: true,
+        parameters: vec![],
+    };
+    xid.set_parameter(XID_I_FIELD_LENGTH_RX, 256 * 8);
+    xid.set_parameter(XID_WINDOW_SIZE_RX, 7);
+    xid.set_parameter(XID_ACK_TIMER, 3000);
+    xid.set_parameter(XID_WINDOW_SIZE_RX, 4);
+    let content = FrameContent::ExchangeIdentification(xid.clone());
+    let encoded = content.encode();
+    assert_eq!(
+        encoded,
+        vec![
+            0xBF, 0x82, 0x80, 0x00, 0x0B, 0x06, 0x02, 0x08, 0x00, 0x08, 0x01, 0x04, 0x09, 0x02,
+            0x0B, 0xB8
+        ]
+    );
+    assert_eq!(parse_content(&encoded).unwrap(), content);
+    assert_eq!(xid.parameter(XID_WINDOW_SIZE_RX), Some(4));
+    assert_eq!(xid.parameter(XID_I_FIELD_LENGTH_RX), Some(2048));
+    assert_eq!(xid.parameter(XID_RETRIES), None);
+    assert!(content.is_unnumbered());
+
+    let frame = Ax25Frame {
+        content,
+        ..Default::default()
+    };
+    let bytes = frame.to_bytes();
+    let annotated = Ax25Frame::from_bytes_annotated(&bytes).unwrap();
+    assert_eq!(annotated.frame, frame);
+    assert_eq!(annotated.ranges.info, Some(15..bytes.len()));
+
+    // An XID without parameters has no information field
+    let empty = FrameContent::ExchangeIdentification(ExchangeIdentification {
+        poll_or_final: false,
+        parameters: vec![],
+    });
+    assert_eq!(empty.encode(), vec![0xAF]);
+    assert_eq!(parse_content(&[0xAF]).unwrap(), empty);
+
+    for malformed in [
+        &[0xAF, 0x82][..],
+        &[0xAF, 0x81, 0x80, 0x00, 0x00],
+        &[0xAF, 0x82, 0x80, 0x00, 0x03, 0x08, 0x01],
+        &[0xAF, 0x82, 0x80, 0x00, 0x02, 0x08, 0x02],
+    ] {
+        assert!(matches!(
+            parse_content(malformed),
+            Err(FrameParseError::MalformedXidInfo)
+        ));
+    }
+}
+
+#[test]
+fn test_frmr_round_trip() {
+    // Rejecting a response I frame with N(R) 2 and N(S) 5
+    let bytes = [0x97, 0x01, 0x5a, 0x00];
+    let content = parse_content(&bytes).unwrap();
+    match &content {
+        FrameContent::FrameReject(fr) => {
+            assert!(fr.final_bit);
+            assert!(fr.w);
+            assert_eq!(fr.receive_sequence, 2);
+            assert_eq!(fr.send_sequence, 5);
+            assert_eq!(fr.command_response, RejectedCommandResponse::Response);
+        }
+        other => panic!("expected FRMR, got {:?}", other),
+    }
+    assert_eq!(content.encode(), bytes);
+}
+
+#[test]
+fn test_xid_length_checked() {
+    let mut xid = ExchangeIdentification {
+        poll_or_final: false,
+        parameters: vec![XidParameter {
+            identifier: XID_ACK_TIMER,
+            value: vec![0; 256],
+        }],
+    };
+    assert!(matches!(
+        FrameContent::ExchangeIdentification(xid.clone()).try_encode(),
+        Err(EncodeError::XidParameterTooLong {
+            identifier: XID_ACK_TIMER,
+            len: 256
+        })
+    ));
+
+    xid.parameters = vec![
+        XidParameter {
+            identifier: XID_ACK_TIMER,
+            value: vec![0; 255],
+        };
+        257
+    ];
+    assert!(matches!(
+        FrameContent::ExchangeIdentification(xid).try_encode(),
+        Err(EncodeError::XidGroupTooLong { len: 66049 })
+    ));
+}
+
+#[test]
+fn test_test_frame() {
+    let content = FrameContent::Test(Test {
+        poll_or_final: true,
+        info: b"ping".to_vec(),
+    });
+    let encoded = content.encode();
+    assert_eq!(encoded, vec![0xF3, b'p', b'i', b'n', b'g']);
+    assert_eq!(parse_content(&encoded).unwrap(), content);
+    assert_eq!(
+        parse_content(&[0xE3]).unwrap(),
+        FrameContent::Test(Test {
+            poll_or_final: false,
+            info: vec![],
+        })
+    );
+    assert!(content.is_unnumbered());
+
+    let mut command = Ax25Frame {
+        source: Address::from_str("VK7NTK-1").unwrap(),
+        destination: Address::from_str("VK7NTK-2").unwrap(),
+        route: vec![RouteEntry {
+            repeater: Address::from_str("RELAY").unwrap(),
+            has_repeated: true,
+        }],
+        command_or_response: Some(CommandResponse::Command),
+        content,
+    };
+    let bytes = command.to_bytes();
+    let annotated = Ax25Frame::from_bytes_annotated(&bytes).unwrap();
+    assert_eq!(annotated.frame, command);
+    assert_eq!(annotated.ranges.pid, None);
+    assert_eq!(annotated.ranges.info, Some(22..bytes.len()));
+
+    let response = command.test_response().unwrap();
+    assert_eq!(response.source, command.destination);
+    assert_eq!(response.destination, command.source);
+    assert_eq!(response.route[0].repeater, command.route[0].repeater);
+    assert!(!response.route[0].has_repeated);
+    assert_eq!(
+        response.command_or_response,
+        Some(CommandResponse::Response)
+    );
+    assert_eq!(response.content, command.content);
+    assert_eq!(response.test_response(), None);
+
+    command.command_or_response = None;
+    assert_eq!(command.test_response(), None);
+}
+
+#[test]
+fn test_repeater_hops() {
+    let mut frame = Ax25Frame::new_simple_ui_frame(
+        Address::new("VK7NTK", 1).unwrap(),
+        Address::new("IDENT", 0).unwrap(),
+        b"hello".to_vec(),
+    );
+    assert!(frame.next_repeater().is_none());
+
+    let first = Address::new("VK7RAA", 0).unwrap();
+    let second = Address::new("VK7RBB", 0).unwrap();
+    for repeater in [&first, &second] {
+        frame.route.push(RouteEntry {
+            repeater: repeater.clone(),
+            has_repeated: false,
+        });
+    }
+    assert_eq!(frame.next_repeater().unwrap().repeater, first);
+
+    // Out of order
+    assert!(!frame.mark_repeated(&second));
+    assert!(frame.mark_repeated(&first));
+    assert_eq!(frame.next_repeater().unwrap().repeater, second);
+    assert!(!frame.mark_repeated(&first));
+    assert!(frame.mark_repeated(&second));
+    assert!(frame.next_repeater().is_none());
+}
+
+#[test]
+fn test_frame_equality() {
+    let frame = Ax25Frame::new_simple_ui_frame(
+        Address::new("VK7NTK", 1).unwrap(),
+        Address::new("IDENT", 0).unwrap(),
+        b"hello".to_vec(),
+    );
+    let mut decoded = Ax25Frame::from_bytes(&frame.to_bytes()).unwrap();
+    assert_eq!(decoded, frame);
+
+    decoded.route.push(RouteEntry {
+        repeater: Address::new("VK7RAA", 0).unwrap(),
+        has_repeated: false,
+    });
+    assert_ne!(decoded, frame);
+    let mut repeated = decoded.clone();
+    repeated.route[0].has_repeated = true;
+    assert_ne!(repeated.route[0], decoded.route[0]);
+}
+
+#[test]
+fn test_dedup_key() {
+    use std::collections::HashSet;
+
+    let frame = Ax25Frame::new_simple_ui_frame(
+        Address::new("VK7NTK", 1).unwrap(),
+        Address::new("APRS", 0).unwrap(),
+        b"hello".to_vec(),
+    );
+    let mut digipeated = frame.clone();
+    digipeated.route.push(RouteEntry {
+        repeater: Address::new("VK7RAA", 0).unwrap(),
+        has_repeated: true,
+    });
+
+    let frames: HashSet<&Ax25Frame> = [&frame, &digipeated, &frame].into_iter().collect();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frame.dedup_key(), digipeated.dedup_key());
+    let keys: HashSet<_> = [frame.dedup_key(), digipeated.dedup_key()].into();
+    assert_eq!(keys.len(), 1);
+}
+
+#[test]
+fn test_address_comparison() {
+    let a = Address::from_str("VK7NTK-1").unwrap();
+    let b = Address::from_str("vk7ntk-9").unwrap();
+    let c = Address::from_str("VK7NTK-1").unwrap();
+    let d = Address::from_str("VK7XT-1").unwrap();
+    assert!(a.matches_callsign(&b));
+    assert!(!a.same_station(&b));
+    assert!(a.same_station(&c));
+    assert!(!a.matches_callsign(&d));
+    assert!(!a.same_station(&d));
+}
+
+#[test]
+fn test_short_frame_boundaries() {
+    let address_only = Ax25Frame::new_simple_ui_frame(
+        Address::new("VK7NTK", 1).unwrap(),
+        Address::new("IDENT", 0).unwrap(),
+        Vec::new(),
+    )
+    .to_bytes()[..14]
+        .to_vec();
+    let with = |tail: &[u8]| {
+        let mut bytes = address_only.clone();
+        bytes.extend(tail);
+        Ax25Frame::from_bytes(&bytes)
+    };
+
+    let mut destination_only = address_only[..7].to_vec();
+    destination_only[6] |= 1;
+    assert!(matches!(
+        Ax25Frame::from_bytes(&destination_only),
+        Err(FrameParseError::AddressFieldTooShort { start: 0, end: 6 })
+    ));
+    assert!(matches!(with(&[]), Err(FrameParseError::ContentZeroLength)));
+    // UI and I frames need a PID
+    assert!(matches!(
+        with(&[0x03]),
+        Err(FrameParseError::MissingPidField)
+    ));
+    assert!(matches!(
+        with(&[0x00]),
+        Err(FrameParseError::MissingPidField)
+    ));
+    // Supervisory and most unnumbered frames are complete with just the control field
+    assert!(matches!(
+        with(&[0x01]).unwrap().content,
+        FrameContent::ReceiveReady(_)
+    ));
+    assert!(matches!(
+        with(&[0x3f]).unwrap().content,
+        FrameContent::SetAsynchronousBalancedMode(_)
+    ));
+    // A PID with no information is an empty frame, not a truncated one
+    match with(&[0x03, 0xf0]).unwrap().content {
+        FrameContent::UnnumberedInformation(ui) => assert!(ui.info.is_empty()),
+        c => panic!("expected UI frame, got {:?}", c),
+    }
+
+    // An address field with a partial repeater
+    let mut misaligned = address_only.clone();
+    misaligned[13] &= !1;
+    misaligned.extend([b'A' << 1, b'B' << 1 | 1, 0x03, 0xf0]);
+    assert!(matches!(
+        Ax25Frame::from_bytes(&misaligned),
+        Err(FrameParseError::MisalignedAddressField { len: 16 })
+    ));
+}
+
+#[test]
+fn test_parse_error_with_bytes() {
+    let bytes = [0x82, 0xa0, 0x01];
+    let err = Ax25Frame::from_bytes(&bytes)
+        .map_err(|e| e.with_bytes(&bytes))
+        .unwrap_err();
+    assert_eq!(err.bytes, bytes);
+    assert!(matches!(
+        err.error,
+        FrameParseError::AddressFieldTooShort { .. }
+    ));
+    assert!(err.to_string().ends_with("frame of 3 bytes: 82 a0 01"));
+}
+
+#[test]
+fn test_c_bit_override() {
+    let frame = Ax25Frame::new_simple_ui_frame(
+        Address::new("VK7NTK", 1).unwrap(),
+        Address::new("IDENT", 0).unwrap(),
+        b"hello".to_vec(),
+    );
+    for (dest, src, expected) in [
+        (true, false, Some(CommandResponse::Command)),
+        (false, true, Some(CommandResponse::Response)),
+        (true, true, Some(CommandResponse::V1 { c_bit: true })),
+        (false, false, Some(CommandResponse::V1 { c_bit: false })),
+    ] {
+        let bytes = frame.to_bytes_with_c_bits(dest, src);
+        assert_eq!(bytes[6] & 0b1000_0000 != 0, dest);
+        assert_eq!(bytes[13] & 0b1000_0000 != 0, src);
+        let parsed = Ax25Frame::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.command_or_response, expected);
+        assert_eq!(parsed.content, frame.content);
+    }
+}
+
+#[test]
+fn test_default_c_bits() {
+    let mut frame = Ax25Frame::new_simple_ui_frame(
+        Address::new("VK7NTK", 1).unwrap(),
+        Address::new("IDENT", 0).unwrap(),
+        b"hello".to_vec(),
+    );
+    frame.command_or_response = None;
+    assert_eq!(frame.to_bytes(), frame.to_bytes_with_c_bits(true, false));
+    let bytes = frame.to_bytes_with_default_c_bits(false, false);
+    assert_eq!(bytes, frame.to_bytes_with_c_bits(false, false));
+    assert_eq!(
+        Ax25Frame::from_bytes(&bytes).unwrap().protocol_version(),
+        ProtocolVersion::V1
+    );
+
+    // The default does not override an explicit command or response
+    frame.command_or_response = Some(CommandResponse::Response);
+    assert_eq!(
+        frame.to_bytes_with_default_c_bits(false, false),
+        frame.to_bytes_with_c_bits(false, true)
+    );
+}
+
+#[test]
+fn test_protocol_version() {
+    let frame = Ax25Frame::new_simple_ui_frame(
+        Address::new("VK7NTK", 1).unwrap(),
+        Address::new("IDENT", 0).unwrap(),
+        b"hello".to_vec(),
+    );
+    let mut bytes = frame.to_bytes();
+    let parsed = Ax25Frame::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed.protocol_version(), ProtocolVersion::V2);
+
+    // Set the source c-bit so both are set
+    bytes[13] |= 0b1000_0000;
+    let parsed = Ax25Frame::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        parsed.command_or_response,
+        Some(CommandResponse::V1 { c_bit: true })
+    );
+    assert_eq!(parsed.protocol_version(), ProtocolVersion::V1);
+
+    // Clear both
+    bytes[6] &= 0b0111_1111;
+    bytes[13] &= 0b0111_1111;
+    let parsed = Ax25Frame::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed.protocol_version(), ProtocolVersion::V1);
+
+    // Agrees with how a frame with no command or response is encoded
+    let mut frame = frame;
+    frame.command_or_response = None;
+    assert_eq!(frame.protocol_version(), ProtocolVersion::V2);
+    let parsed = Ax25Frame::from_bytes(&frame.to_bytes()).unwrap();
+    assert_eq!(parsed.protocol_version(), ProtocolVersion::V2);
+}
+
+#[test]
+fn test_empty_info() {
+    let mut frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        Vec::new(),
+    );
+    assert!(!frame.has_info());
+    assert_eq!(frame.info_string_lossy(), None);
+    assert!(frame.to_string().ends_with("Data\t\t-"));
+
+    frame = Ax25Frame::new_simple_ui_frame(frame.source, frame.destination, b"hi".to_vec());
+    assert!(frame.has_info());
+    assert_eq!(frame.info_string_lossy(), Some("hi".to_string()));
+    assert!(frame.to_string().ends_with("Data\t\t\"hi\""));
+}
+
+#[test]
+fn test_validate() {
+    let mut frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        b"hello".to_vec(),
+    );
+    assert!(frame.validate().is_ok());
+
+    frame.source.callsign = "vk7ntk".to_string();
+    frame.destination.ssid = 16;
+    frame.route = vec![
+        RouteEntry {
+            repeater: "WIDE1-1".parse().unwrap(),
+            has_repeated: false,
+        };
+        9
+    ];
+    frame.content = FrameContent::Information(Information {
+        pid: ProtocolIdentifier::None,
+        info: vec![0; 300],
+        receive_sequence: 8,
+        send_sequence: 3,
+        poll: false,
+    });
+    let errors = frame.validate().unwrap_err();
+    assert_eq!(errors.len(), 5);
+    assert!(matches!(
+        errors[0],
+        ValidationError::InvalidAddress {
+            ssid: 16,
+            source: AddressParseError::SsidOutOfRange,
+            ..
+        }
+    ));
+    assert!(matches!(
+        errors[1],
+        ValidationError::InvalidAddress {
+            source: AddressParseError::InvalidFormat,
+            ..
+        }
+    ));
+    assert!(matches!(
+        errors[2],
+        ValidationError::TooManyRepeaters { count: 9 }
+    ));
+    assert!(matches!(
+        errors[3],
+        ValidationError::InfoTooLong { len: 300, max: 256 }
+    ));
+    assert!(matches!(
+        errors[4],
+        ValidationError::SequenceOutOfRange { value: 8 }
+    ));
+    assert!(frame.validate_with_max_info(300).unwrap_err().len() == 4);
+}
+
+#[test]
+fn test_from_bytes_annotated() {
+    let mut frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        b"hi".to_vec(),
+    );
+    frame.route.push(RouteEntry {
+        repeater: "WIDE1-1".parse().unwrap(),
+        has_repeated: false,
+    });
+    let bytes = frame.to_bytes();
+    let annotated = Ax25Frame::from_bytes_annotated(&bytes).unwrap();
+    assert_eq!(annotated.frame, frame);
+    let ranges = annotated.ranges;
+    assert_eq!(ranges.destination, 0..7);
+    assert_eq!(ranges.source, 7..14);
+    assert_eq!(ranges.repeaters, vec![14..21]);
+    assert_eq!(ranges.control, 21..22);
+    assert_eq!(ranges.pid, Some(22..23));
+    assert_eq!(&bytes[ranges.info.unwrap()], b"hi");
+
+    frame.content = FrameContent::ReceiveReady(ReceiveReady {
+        receive_sequence: 1,
+        poll_or_final: false,
+        trailing: vec![],
+    });
+    let ranges = Ax25Frame::from_bytes_annotated(&frame.to_bytes())
+        .unwrap()
+        .ranges;
+    assert_eq!(ranges.control, 21..22);
+    assert_eq!(ranges.pid, None);
+    assert_eq!(ranges.info, None);
+}
+
+#[test]
+fn test_address_from_raw_bytes() {
+    let mut field = [0u8; 7];
+    for (b, c) in field.iter_mut().zip(b"Rx-1  ") {
+        *b = c << 1;
+    }
+    field[6] = 0b1110_0101;
+    let address = Address::from_raw_bytes(&field);
+    assert_eq!(address.callsign(), "Rx-1");
+    assert_eq!(address.ssid(), 2);
+    assert_eq!(address.reserved_bits(), 0b11);
+    // Re-encoding gives back the same field, other than the frame's own bits
+    assert_eq!(address.to_field_bytes(true, true), field);
+}
+
+#[test]
+fn test_address_field_bytes_round_trip() {
+    for ssid in 0..=15 {
+        let address = Address::new("VK7NTK", ssid).unwrap();
+        for (last, c_bit) in [(false, false), (false, true), (true, false), (true, true)] {
+            let field = address.to_field_bytes(last, c_bit);
+            assert_eq!(field[6] & 1 != 0, last);
+            assert_eq!(field[6] & 0x80 != 0, c_bit);
+            let parsed = Address::from_field_bytes(&field).unwrap();
+            assert_eq!(parsed, address);
+            assert_eq!(parsed.reserved_bits(), address.reserved_bits());
+        }
+    }
+    // Short callsigns are padded with spaces
+    let field = Address::new("ID", 0).unwrap().to_field_bytes(true, false);
+    assert_eq!(&field[2..6], &[b' ' << 1; 4]);
+
+    let mut invalid = field;
+    invalid[0] = b'r' << 1;
+    assert!(matches!(
+        Address::from_field_bytes(&invalid),
+        Err(AddressParseError::InvalidFormat)
+    ));
+    invalid[0] = (b'R' << 1) | 1;
+    assert!(matches!(
+        Address::from_field_bytes(&invalid),
+        Err(AddressParseError::InvalidFormat)
+    ));
+}
+
+#[test]
+fn test_heard_direct() {
+    let mut frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "APRS".parse().unwrap(),
+        Vec::new(),
+    );
+    assert!(frame.heard_direct());
+    assert_eq!(frame.last_repeater(), None);
+
+    for (repeater, has_repeated) in [("VK7RHT", true), ("WIDE1", true), ("WIDE2-1", false)] {
+        frame.route.push(RouteEntry {
+            repeater: repeater.parse().unwrap(),
+            has_repeated,
+        });
+    }
+    assert!(!frame.heard_direct());
+    assert_eq!(frame.last_repeater(), Some(&"WIDE1".parse().unwrap()));
+
+    for entry in frame.route.iter_mut() {
+        entry.has_repeated = false;
+    }
+    assert!(frame.heard_direct());
+    assert_eq!(frame.last_repeater(), None);
+}
+
+#[test]
+fn test_hex_round_trip() {
+    let frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        b"hi".to_vec(),
+    );
+    let hex = frame.to_hex();
+    assert!(hex.starts_with("92 88 8A 9C A8 40 E0 AC"));
+    assert_eq!(Ax25Frame::from_hex(&hex).unwrap(), frame);
+
+    let packed: String = hex.split(' ').collect();
+    assert_eq!(Ax25Frame::from_hex(&packed).unwrap(), frame);
+    let colons = hex.replace(' ', ":").to_lowercase();
+    assert_eq!(Ax25Frame::from_hex(&colons).unwrap(), frame);
+    // Unpadded digits as printed by `{:X}`, with stray whitespace
+    let unpadded: Vec<String> = frame
+        .to_bytes()
+        .iter()
+        .map(|b| alloc::format!("{:X}", b))
+        .collect();
+    let unpadded = alloc::format!("\n  {} \n", unpadded.join(" "));
+    assert!(unpadded.contains(" 3 F0 "));
+    assert_eq!(Ax25Frame::from_hex(&unpadded).unwrap(), frame);
+}
+
+#[test]
+fn test_hex_errors() {
+    assert!(matches!(
+        Ax25Frame::from_hex("82 A0 xx"),
+        Err(HexParseError::InvalidCharacter {
+            position: 6,
+            character: 'x'
+        })
+    ));
+    assert!(matches!(
+        Ax25Frame::from_hex("82 A0A"),
+        Err(HexParseError::UnpairedDigit { position: 3 })
+    ));
+    assert!(matches!(
+        Ax25Frame::from_hex(""),
+        Err(HexParseError::Frame {
+            source: FrameParseError::NoEndToAddressField
+        })
+    ));
+}
+
+#[test]
+fn test_reply() {
+    let repeater = |call: &str, has_repeated| RouteEntry {
+        repeater: call.parse().unwrap(),
+        has_repeated,
+    };
+    let mut received = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "TIME".parse().unwrap(),
+        b"what is the time?".to_vec(),
+    );
+    received.route = vec![
+        repeater("DIGI1", true),
+        repeater("DIGI2", true),
+        repeater("DIGI3", false),
+    ];
+    received.command_or_response = Some(CommandResponse::Command);
+
+    let reply = received.reply(b"noon".to_vec(), ProtocolIdentifier::None);
+    assert_eq!(reply.source, received.destination);
+    assert_eq!(reply.destination, received.source);
+    // DIGI3 never carried the frame so it plays no part in the way back
+    assert_eq!(
+        reply.route,
+        vec![repeater("DIGI2", false), repeater("DIGI1", false)]
+    );
+    assert_eq!(reply.command_or_response, Some(CommandResponse::Response));
+    assert_eq!(reply.info_string_lossy().unwrap(), "noon");
+
+    received.command_or_response = None;
+    let reply = received.reply(Vec::new(), ProtocolIdentifier::None);
+    assert_eq!(reply.command_or_response, Some(CommandResponse::Command));
+}
+
+#[test]
+fn test_air_time() {
+    assert_eq!(stuffed_bits(&[0x00]), 0);
+    assert_eq!(stuffed_bits(&[0x1f]), 1);
+    assert_eq!(stuffed_bits(&[0xff, 0xff]), 3);
+    // Runs continue across byte boundaries
+    assert_eq!(stuffed_bits(&[0xc0, 0x07]), 1);
+
+    let frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        vec![0; 100],
+    );
+    let bytes = frame.to_bytes();
+    let bits = (bytes.len() as u64 + 4) * 8 + stuffed_bits(&bytes);
+    assert_eq!(
+        frame.air_time(1200),
+        core::time::Duration::from_nanos(bits * 1_000_000_000 / 1200)
+    );
+    assert!(frame.air_time(9600) < frame.air_time(1200));
+
+    // The same length of all ones needs a stuffed bit every five bits
+    let ones = Ax25Frame::new_simple_ui_frame(
+        frame.source.clone(),
+        frame.destination.clone(),
+        vec![0xff; 100],
+    );
+    assert!(ones.air_time(1200) > frame.air_time(1200));
+}
+
+#[test]
+fn test_addresses() {
+    let mut frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        Vec::new(),
+    );
+    for repeater in ["WIDE1-1", "WIDE2-2"] {
+        frame.route.push(RouteEntry {
+            repeater: repeater.parse().unwrap(),
+            has_repeated: false,
+        });
+    }
+    let addresses: Vec<String> = frame.addresses().map(|a| a.to_string()).collect();
+    assert_eq!(addresses, ["IDENT", "VK7NTK-1", "WIDE1-1", "WIDE2-2"]);
+}
+
+#[test]
+fn test_path_string() {
+    let mut frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        Vec::new(),
+    );
+    assert_eq!(frame.path_string(), "");
+    for (repeater, has_repeated) in [("VK7RHT", true), ("WIDE1-1", true), ("WIDE2-2", false)] {
+        frame.route.push(RouteEntry {
+            repeater: repeater.parse().unwrap(),
+            has_repeated,
+        });
+    }
+    assert_eq!(frame.path_string(), "VK7RHT*,WIDE1-1*,WIDE2-2");
+}
+
+#[test]
+fn test_path() {
+    let frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        Vec::new(),
+    );
+    let path: Vec<Address> = ["WIDE1-1", "WIDE2-2"]
+        .iter()
+        .map(|a| a.parse().unwrap())
+        .collect();
+    let frame = frame.with_path(&path).unwrap();
+    assert_eq!(frame.path_string(), "WIDE1-1,WIDE2-2");
+    assert_eq!(
+        RouteEntry::parse_path("WIDE1-1, WIDE2-2").unwrap(),
+        frame.route
+    );
+    assert!(matches!(
+        frame.clone().with_path(&vec![path[0].clone(); 9]),
+        Err(EncodeError::TooManyRepeaters { count: 9 })
+    ));
+
+    // The output of path_string parses back to the same route
+    let route = RouteEntry::parse_path("VK7RHT*,WIDE1-1*,WIDE2-2").unwrap();
+    assert_eq!(
+        route.iter().map(|e| e.has_repeated).collect::<Vec<_>>(),
+        vec![true, true, false]
+    );
+    assert_eq!(
+        Ax25Frame { route, ..frame }.path_string(),
+        "VK7RHT*,WIDE1-1*,WIDE2-2"
+    );
+
+    assert!(RouteEntry::parse_path("").unwrap().is_empty());
+    assert!(matches!(
+        RouteEntry::parse_path("WIDE1-1,,WIDE2-2"),
+        Err(PathParseError::InvalidAddress { address, .. }) if address.is_empty()
+    ));
+    assert!(matches!(
+        RouteEntry::parse_path("WIDE1-1,TOOLONGCALL"),
+        Err(PathParseError::InvalidAddress { address, .. }) if address == "TOOLONGCALL"
+    ));
+    assert!(matches!(
+        RouteEntry::parse_path("A,B,C,D,E,F,G,H,I"),
+        Err(PathParseError::TooManyRepeaters { count: 9 })
+    ));
+}
+
+#[test]
+fn test_trailing_bytes() {
+    // A captured DISC with a PID and info byte appended by hand, standing in for the
+    // output of a faulty station
+    let bytes = std::fs::read("testdata/linux-ax0/vk7ntk-2-disc-trailing.dat").unwrap();
+    let bytes = &bytes[1..];
+    let annotated = Ax25Frame::from_bytes_annotated(bytes).unwrap();
+    let frame = annotated.frame;
+    assert!(matches!(frame.content, FrameContent::Disconnect(_)));
+    assert_eq!(frame.content.trailing_bytes(), [0xf0, 0x21]);
+    assert_eq!(annotated.ranges.trailing, Some(15..17));
+    assert_eq!(frame.to_bytes(), bytes);
+    assert!(matches!(
+        frame.validate().unwrap_err()[..],
+        [ValidationError::TrailingBytes { len: 2 }]
+    ));
+
+    // Frames with an information field never have trailing bytes
+    let ui = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        b"hi".to_vec(),
+    );
+    let parsed = Ax25Frame::from_bytes(&ui.to_bytes()).unwrap();
+    assert!(parsed.content.trailing_bytes().is_empty());
+    assert_eq!(parsed, ui);
+}
+
+#[test]
+fn test_from_bytes_with_fcs() {
+    let frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        b"hello".to_vec(),
+    );
+    let mut bytes = frame.to_bytes();
+    bytes.extend(crate::hdlc::fcs(&frame.to_bytes()).to_le_bytes());
+    let (parsed, check) = Ax25Frame::from_bytes_with_fcs(&bytes).unwrap();
+    assert_eq!(parsed, frame);
+    assert!(check.is_valid());
+
+    // A damaged information field still parses, with the mismatch reported
+    bytes[17] ^= 0x01;
+    let (parsed, check) = Ax25Frame::from_bytes_with_fcs(&bytes).unwrap();
+    assert_eq!(parsed.info_string_lossy().as_deref(), Some("hdllo"));
+    assert!(!check.is_valid());
+    assert_eq!(check.expected, crate::hdlc::fcs(&frame.to_bytes()));
+
+    for len in 0..3 {
+        assert!(matches!(
+            Ax25Frame::from_bytes_with_fcs(&bytes[..len]),
+            Err(FrameParseError::AddressFieldTooShort { start: 0, end: 0 })
+        ));
+    }
+}
+
+#[test]
+fn test_control_summary() {
+    let summary = |content, command_or_response| {
+        Ax25Frame {
+            content,
+            command_or_response,
+            ..Default::default()
+        }
+        .control_summary()
+    };
+    const COMMAND: Option<CommandResponse> = Some(CommandResponse::Command);
+    const RESPONSE: Option<CommandResponse> = Some(CommandResponse::Response);
+    assert_eq!(
+        summary(
+            FrameContent::Information(Information {
+                pid: ProtocolIdentifier::None,
+                info: vec![],
+                receive_sequence: 2,
+                send_sequence: 3,
+                poll: true,
+            }),
+            COMMAND
+        ),
+        "<I R2 S3 P>"
+    );
+    let rr = FrameContent::ReceiveReady(ReceiveReady {
+        receive_sequence: 5,
+        poll_or_final: true,
+        trailing: vec![],
+    });
+    assert_eq!(summary(rr.clone(), RESPONSE), "<RR F R5>");
+    assert_eq!(summary(rr.clone(), COMMAND), "<RR P R5>");
+    assert_eq!(summary(rr, None), "<RR P R5>");
+    assert_eq!(
+        summary(
+            FrameContent::Reject(Reject {
+                receive_sequence: 0,
+                poll_or_final: false,
+                trailing: vec![],
+            }),
+            RESPONSE
+        ),
+        "<REJ R0>"
+    );
+    assert_eq!(
+        summary(
+            FrameContent::SetAsynchronousBalancedMode(SetAsynchronousBalancedMode {
+                poll: true,
+                trailing: vec![]
+            }),
+            COMMAND
+        ),
+        "<SABM P>"
+    );
+    assert_eq!(
+        summary(
+            FrameContent::UnnumberedAcknowledge(UnnumberedAcknowledge {
+                final_bit: true,
+                trailing: vec![]
+            }),
+            RESPONSE
+        ),
+        "<UA F>"
+    );
+    assert_eq!(
+        summary(
+            FrameContent::UnnumberedInformation(UnnumberedInformation {
+                pid: ProtocolIdentifier::None,
+                info: b"hi".to_vec(),
+                poll_or_final: false,
+            }),
+            COMMAND
+        ),
+        "<UI>"
+    );
+    assert_eq!(
+        summary(
+            FrameContent::UnknownContent(UnknownContent { raw: vec![0xFF] }),
+            None
+        ),
+        "<?>"
+    );
+}
+
 #[test]
 fn test_round_trips() {
     use std::fs::{read_dir, File};
@@ -845,6 +3349,10 @@ fn test_round_trips() {
         let mut frame_data: Vec<u8> = Vec::new();
         let _ = file.read_to_end(&mut frame_data);
         // Skip the leading null byte. A quirk as they came from Linux AF_PACKET.
+        assert!(matches!(
+            Ax25Frame::from_bytes(&frame_data),
+            Err(FrameParseError::LeadingNullByte)
+        ));
         let frame_data_fixed = &frame_data[1..];
 
         match Ax25Frame::from_bytes(frame_data_fixed) {
@@ -856,3 +3364,39 @@ fn test_round_trips() {
         };
     }
 }
+
+#[test]
+fn test_random_round_trips() {
+    // A small xorshift generator so that failures are reproducible
+    let mut state: u32 = 0x2545_f491;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    };
+    const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 ";
+
+    let mut parsed_count = 0;
+    for _ in 0..100_000 {
+        let mut bytes = Vec::new();
+        let n_addresses = 2 + next() as usize % 3;
+        for i in 0..n_addresses {
+            for _ in 0..6 {
+                bytes.push(CHARS[next() as usize % CHARS.len()] << 1);
+            }
+            let end_bit = if i == n_addresses - 1 { 1 } else { 0 };
+            bytes.push((next() as u8 & 0b1111_1110) | end_bit);
+        }
+        let n_extra = next() as usize % 8;
+        for _ in 0..n_extra {
+            bytes.push(next() as u8);
+        }
+
+        if let Ok(frame) = Ax25Frame::from_bytes(&bytes) {
+            parsed_count += 1;
+            assert_eq!(frame.to_bytes(), bytes, "{:?}", frame);
+        }
+    }
+    assert!(parsed_count > 10_000);
+}
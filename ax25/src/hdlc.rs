@@ -0,0 +1,329 @@
+use alloc::fmt;
+use alloc::vec::Vec;
+
+/// The HDLC flag which opens and closes every frame
+const FLAG: u8 = 0x7E;
+
+/// Errors when decoding a bit-level HDLC frame
+#[derive(Debug, PartialEq, Eq)]
+pub enum HdlcError {
+    /// The bits do not begin with a flag
+    MissingOpeningFlag,
+    /// No flag was found after the frame's data
+    MissingClosingFlag,
+    /// Seven or more consecutive ones, which a transmitter sends to abandon a frame
+    Aborted,
+    /// The data between the flags is not a whole number of bytes
+    NotByteAligned {
+        bits: usize,
+    },
+    /// There is not enough data for an FCS
+    FrameTooShort {
+        len: usize,
+    },
+    FcsMismatch {
+        expected: u16,
+        actual: u16,
+    },
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HdlcError {}
+
+impl fmt::Display for HdlcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingOpeningFlag => write!(f, "HDLC frame does not start with a flag"),
+            Self::MissingClosingFlag => write!(f, "HDLC frame does not end with a flag"),
+            Self::Aborted => write!(f, "HDLC frame was aborted"),
+            Self::NotByteAligned { bits } => {
+                write!(
+                    f,
+                    "HDLC frame has {} bits, not a whole number of bytes",
+                    bits
+                )
+            }
+            Self::FrameTooShort { len } => write!(f, "HDLC frame is too short: len {}", len),
+            Self::FcsMismatch { expected, actual } => write!(
+                f,
+                "FCS mismatch: frame has {:04x} but contents give {:04x}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+/// The frame check sequence of AX.25 and HDLC: CRC-16-CCITT, reflected, with an initial
+/// value and final XOR of 0xFFFF.
+pub fn fcs(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &b in bytes {
+        crc ^= b as u16;
+        for _ in 0..8 {
+            if crc & 0x0001 != 0 {
+                crc = (crc >> 1) ^ 0x8408;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    !crc
+}
+
+/// The FCS received at the end of a frame alongside the FCS calculated from its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FcsCheck {
+    /// The FCS carried by the frame
+    pub expected: u16,
+    /// The FCS of the frame's contents as received
+    pub actual: u16,
+}
+
+impl FcsCheck {
+    /// Whether the frame arrived intact, as far as the FCS can tell.
+    pub fn is_valid(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+/// Split frame bytes which end with their two byte FCS, least significant byte first,
+/// into the frame contents and the result of checking the FCS.
+///
+/// A mismatch is not an error, so that callers can count damaged frames.
+pub fn check_fcs(bytes: &[u8]) -> Result<(&[u8], FcsCheck), HdlcError> {
+    if bytes.len() < 3 {
+        return Err(HdlcError::FrameTooShort { len: bytes.len() });
+    }
+    let (contents, fcs_bytes) = bytes.split_at(bytes.len() - 2);
+    let check = FcsCheck {
+        expected: u16::from_le_bytes([fcs_bytes[0], fcs_bytes[1]]),
+        actual: fcs(contents),
+    };
+    Ok((contents, check))
+}
+
+/// Running totals of frames which passed or failed their FCS check, for monitoring the
+/// quality of a link.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FcsStats {
+    pub passed: u64,
+    pub failed: u64,
+}
+
+impl FcsStats {
+    /// Count the result of checking one frame.
+    pub fn record(&mut self, check: &FcsCheck) {
+        if check.is_valid() {
+            self.passed += 1;
+        } else {
+            self.failed += 1;
+        }
+    }
+
+    /// The number of frames recorded.
+    pub fn total(&self) -> u64 {
+        self.passed + self.failed
+    }
+
+    /// The fraction of recorded frames which failed their FCS check, or `None` if
+    /// nothing has been recorded.
+    ///
+    /// When bit errors are rare and independent, dividing this by the number of bits in
+    /// a typical frame gives an estimate of the channel's bit error rate.
+    pub fn frame_error_rate(&self) -> Option<f64> {
+        match self.total() {
+            0 => None,
+            total => Some(self.failed as f64 / total as f64),
+        }
+    }
+}
+
+/// Encode frame bytes, such as from `Ax25Frame::to_bytes`, as the bits of an HDLC
+/// frame in the order they are sent.
+///
+/// The FCS is appended and a zero is inserted after every five consecutive ones, then
+/// the result is wrapped in opening and closing flags. Each byte is sent least
+/// significant bit first. Modems normally NRZI-encode these bits before modulation,
+/// which is left to the caller.
+pub fn hdlc_encode(frame: &[u8]) -> Vec<bool> {
+    let mut bits = Vec::with_capacity((frame.len() + 4) * 9);
+    push_byte(&mut bits, FLAG);
+    let mut ones = 0;
+    let fcs = fcs(frame).to_le_bytes();
+    for &byte in frame.iter().chain(fcs.iter()) {
+        for i in 0..8 {
+            let bit = byte & (1 << i) != 0;
+            bits.push(bit);
+            ones = if bit { ones + 1 } else { 0 };
+            if ones == 5 {
+                bits.push(false);
+                ones = 0;
+            }
+        }
+    }
+    push_byte(&mut bits, FLAG);
+    bits
+}
+
+/// Decode one HDLC frame from its bits, as produced by `hdlc_encode`, returning the
+/// frame bytes without the FCS.
+///
+/// The bits must start with one or more flags. Stuffed zeros are removed and the FCS
+/// is verified. Anything after the closing flag is ignored.
+pub fn hdlc_decode(bits: &[bool]) -> Result<Vec<u8>, HdlcError> {
+    let mut start = 0;
+    while bits.len() >= start + 8 && byte_at(&bits[start..start + 8]) == FLAG {
+        start += 8;
+    }
+    if start == 0 {
+        return Err(HdlcError::MissingOpeningFlag);
+    }
+
+    let mut data = Vec::new();
+    let mut ones = 0;
+    let mut closed = false;
+    let mut iter = bits[start..].iter();
+    while let Some(&bit) = iter.next() {
+        if !bit {
+            if ones == 5 {
+                // Stuffed zero
+                ones = 0;
+                continue;
+            }
+            ones = 0;
+            data.push(false);
+            continue;
+        }
+        ones += 1;
+        data.push(true);
+        if ones == 6 {
+            match iter.next() {
+                Some(false) => {
+                    // Remove the flag's leading zero and six ones
+                    data.truncate(data.len().saturating_sub(7));
+                    closed = true;
+                    break;
+                }
+                Some(true) => return Err(HdlcError::Aborted),
+                None => break,
+            }
+        }
+    }
+    if !closed {
+        return Err(HdlcError::MissingClosingFlag);
+    }
+    if data.len() % 8 != 0 {
+        return Err(HdlcError::NotByteAligned { bits: data.len() });
+    }
+
+    let bytes: Vec<u8> = data.chunks(8).map(byte_at).collect();
+    let (contents, check) = check_fcs(&bytes)?;
+    if !check.is_valid() {
+        return Err(HdlcError::FcsMismatch {
+            expected: check.expected,
+            actual: check.actual,
+        });
+    }
+    Ok(contents.to_vec())
+}
+
+fn push_byte(bits: &mut Vec<bool>, byte: u8) {
+    bits.extend((0..8).map(|i| byte & (1 << i) != 0));
+}
+
+/// Assemble up to eight bits, least significant first.
+fn byte_at(bits: &[bool]) -> u8 {
+    bits.iter()
+        .enumerate()
+        .fold(0, |acc, (i, &bit)| acc | ((bit as u8) << i))
+}
+
+#[test]
+fn test_fcs() {
+    // Check value for CRC-16/X-25
+    assert_eq!(fcs(b"123456789"), 0x906E);
+}
+
+#[test]
+fn test_round_trip() {
+    use alloc::vec;
+
+    for frame in [
+        vec![0x01],
+        vec![0xFF; 20],
+        vec![FLAG, 0x00, 0x7C, 0x3E, 0xFF],
+    ] {
+        let bits = hdlc_encode(&frame);
+        // No flag can appear within the stuffed data
+        let inner = &bits[8..bits.len() - 8];
+        assert!(!inner.windows(6).any(|w| w.iter().all(|&b| b)));
+        assert_eq!(hdlc_decode(&bits), Ok(frame));
+    }
+
+    // Repeated opening flags and trailing bits are accepted
+    let mut bits = Vec::new();
+    push_byte(&mut bits, FLAG);
+    bits.extend(hdlc_encode(b"hello"));
+    bits.extend([true, false, true]);
+    assert_eq!(hdlc_decode(&bits), Ok(b"hello".to_vec()));
+}
+
+#[test]
+fn test_decode_errors() {
+    let bits = hdlc_encode(b"hello");
+    assert_eq!(hdlc_decode(&bits[1..]), Err(HdlcError::MissingOpeningFlag));
+    assert_eq!(
+        hdlc_decode(&bits[..bits.len() - 8]),
+        Err(HdlcError::MissingClosingFlag)
+    );
+
+    let mut aborted = bits[..24].to_vec();
+    aborted.extend([true; 7]);
+    assert_eq!(hdlc_decode(&aborted), Err(HdlcError::Aborted));
+
+    let mut corrupted = bits.clone();
+    corrupted[10] = !corrupted[10];
+    assert!(matches!(
+        hdlc_decode(&corrupted),
+        Err(HdlcError::FcsMismatch { .. })
+    ));
+
+    let mut unaligned = bits[..12].to_vec();
+    push_byte(&mut unaligned, FLAG);
+    assert_eq!(
+        hdlc_decode(&unaligned),
+        Err(HdlcError::NotByteAligned { bits: 4 })
+    );
+}
+
+#[test]
+fn test_fcs_stats() {
+    let mut bytes = b"hello".to_vec();
+    bytes.extend(fcs(b"hello").to_le_bytes());
+    let (contents, check) = check_fcs(&bytes).unwrap();
+    assert_eq!(contents, b"hello");
+    assert!(check.is_valid());
+
+    let mut stats = FcsStats::default();
+    assert_eq!(stats.frame_error_rate(), None);
+    stats.record(&check);
+
+    bytes[1] ^= 0x04;
+    let (_, check) = check_fcs(&bytes).unwrap();
+    assert!(!check.is_valid());
+    assert_eq!(check.expected, fcs(b"hello"));
+    assert_eq!(check.actual, fcs(b"hallo"));
+    stats.record(&check);
+    stats.record(&check);
+    stats.record(&check);
+
+    assert_eq!(stats.passed, 1);
+    assert_eq!(stats.failed, 3);
+    assert_eq!(stats.total(), 4);
+    assert_eq!(stats.frame_error_rate(), Some(0.75));
+
+    assert_eq!(
+        check_fcs(&[0x01, 0x02]),
+        Err(HdlcError::FrameTooShort { len: 2 })
+    );
+}
@@ -6,3 +6,17 @@ extern crate alloc;
 
 /// Encoding and decoding AX.25 v2.0 frames between raw bytes and strongly typed structures.
 pub mod frame;
+
+/// Bit-level HDLC framing with flags, zero-bit insertion and FCS, for software modems.
+pub mod hdlc;
+
+/// Path processing for digipeaters, including WIDEn-N aliases.
+pub mod digipeater;
+
+/// Reading and writing packet captures in the pcap format used by Wireshark.
+#[cfg(feature = "pcap")]
+pub mod pcap;
+
+/// Parsing APRS messages, position reports including Mic-E and status reports from UI frames.
+#[cfg(feature = "aprs")]
+pub mod aprs;
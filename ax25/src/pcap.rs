@@ -0,0 +1,371 @@
+use crate::frame::{Ax25Frame, FrameParseError};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+/// Packets are AX.25 frames without a trailing FCS
+const LINKTYPE_AX25: u32 = 3;
+/// Packets are AX.25 frames preceded by a KISS command byte
+const LINKTYPE_AX25_KISS: u32 = 202;
+const SNAPLEN: u32 = 65535;
+/// Largest packet accepted when reading, whatever the capture's snaplen says. This is
+/// the largest snaplen used by tcpdump.
+const MAX_RECORD_LEN: u32 = 262_144;
+
+/// Errors when reading frames from a pcap file
+#[derive(Debug)]
+pub enum PcapError {
+    Io { source: io::Error },
+    BadMagic { magic: u32 },
+    UnsupportedLinkType { linktype: u32 },
+    Parse { source: FrameParseError },
+}
+
+impl std::error::Error for PcapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source } => Some(source),
+            Self::Parse { source } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PcapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { source } => write!(f, "Unable to read capture: {}", source),
+            Self::BadMagic { magic } => write!(f, "Not a pcap file: magic number {:08x}", magic),
+            Self::UnsupportedLinkType { linktype } => {
+                write!(
+                    f,
+                    "Capture does not contain AX.25 frames: link type {}",
+                    linktype
+                )
+            }
+            Self::Parse { source } => write!(f, "Unable to parse captured frame: {}", source),
+        }
+    }
+}
+
+/// Writes frames to a pcap file with link type `LINKTYPE_AX25`.
+pub struct PcapWriter<W: Write> {
+    inner: W,
+}
+
+impl PcapWriter<BufWriter<File>> {
+    /// Create a new capture file at `path`, replacing any existing file.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// Add frames to the end of the capture file at `path`, which is created if it
+    /// does not exist.
+    ///
+    /// An existing file must have a header like those written by `PcapWriter`, with
+    /// little-endian microsecond timestamps and link type `LINKTYPE_AX25`. Otherwise
+    /// this fails with `io::ErrorKind::InvalidData` and the file is left untouched.
+    pub fn append<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .create(true)
+            .append(true)
+            .open(path)?;
+        if file.metadata()?.len() == 0 {
+            return Self::new(BufWriter::new(file));
+        }
+        let mut header = [0u8; 24];
+        let compatible = file.read_exact(&mut header).is_ok()
+            && header[0..4] == MAGIC_MICROS.to_le_bytes()
+            && header[20..24] == LINKTYPE_AX25.to_le_bytes();
+        if !compatible {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "existing file is not a capture that PcapWriter can append to",
+            ));
+        }
+        Ok(Self {
+            inner: BufWriter::new(file),
+        })
+    }
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Begin a capture by writing the pcap file header to `inner`.
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        let mut header = Vec::with_capacity(24);
+        header.extend(MAGIC_MICROS.to_le_bytes());
+        header.extend(2u16.to_le_bytes());
+        header.extend(4u16.to_le_bytes());
+        header.extend(0i32.to_le_bytes());
+        header.extend(0u32.to_le_bytes());
+        header.extend(SNAPLEN.to_le_bytes());
+        header.extend(LINKTYPE_AX25.to_le_bytes());
+        inner.write_all(&header)?;
+        Ok(Self { inner })
+    }
+
+    /// Append a frame to the capture, timestamped with the current time.
+    pub fn write_frame(&mut self, frame: &Ax25Frame) -> io::Result<()> {
+        self.write_bytes(&frame.to_bytes())
+    }
+
+    /// Append an encoded frame to the capture, timestamped with the current time.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut record = Vec::with_capacity(16 + bytes.len());
+        record.extend((now.as_secs() as u32).to_le_bytes());
+        record.extend(now.subsec_micros().to_le_bytes());
+        record.extend((bytes.len() as u32).to_le_bytes());
+        record.extend((bytes.len() as u32).to_le_bytes());
+        record.extend(bytes);
+        self.inner.write_all(&record)
+    }
+
+    /// Flush any buffered data to the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A single packet read from a capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcapRecord {
+    /// When the packet was captured, as time since the Unix epoch
+    pub timestamp: Duration,
+    /// The encoded AX.25 frame, with any KISS command byte removed
+    pub bytes: Vec<u8>,
+}
+
+/// Reads frames from a pcap file with link type `LINKTYPE_AX25` or `LINKTYPE_AX25_KISS`.
+///
+/// This is an `Iterator` over the frames in the capture. A frame that cannot be parsed
+/// produces an error but iteration can continue past it. After an `Io` error, such as a
+/// truncated or corrupt record, the iterator ends.
+pub struct PcapReader<R: Read> {
+    inner: R,
+    big_endian: bool,
+    nanos: bool,
+    snaplen: u32,
+    linktype: u32,
+    failed: bool,
+}
+
+impl PcapReader<BufReader<File>> {
+    /// Open the capture file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, PcapError> {
+        let file = File::open(path).map_err(|e| PcapError::Io { source: e })?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Read the pcap file header from `inner` and prepare to read frames.
+    pub fn new(mut inner: R) -> Result<Self, PcapError> {
+        let mut header = [0u8; 24];
+        inner
+            .read_exact(&mut header)
+            .map_err(|e| PcapError::Io { source: e })?;
+        let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let big_endian = if magic == MAGIC_MICROS || magic == MAGIC_NANOS {
+            false
+        } else if magic.swap_bytes() == MAGIC_MICROS || magic.swap_bytes() == MAGIC_NANOS {
+            true
+        } else {
+            return Err(PcapError::BadMagic { magic });
+        };
+        let mut reader = Self {
+            inner,
+            big_endian,
+            nanos: magic == MAGIC_NANOS || magic.swap_bytes() == MAGIC_NANOS,
+            snaplen: 0,
+            linktype: 0,
+            failed: false,
+        };
+        reader.snaplen = reader.u32_at(&header, 16);
+        reader.linktype = reader.u32_at(&header, 20);
+        if reader.linktype != LINKTYPE_AX25 && reader.linktype != LINKTYPE_AX25_KISS {
+            return Err(PcapError::UnsupportedLinkType {
+                linktype: reader.linktype,
+            });
+        }
+        Ok(reader)
+    }
+
+    fn u32_at(&self, bytes: &[u8], offset: usize) -> u32 {
+        let b = [
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ];
+        if self.big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    }
+
+    /// Read the next packet without parsing it, or `None` at the end of the file or
+    /// after an error reading it.
+    pub fn next_record(&mut self) -> Option<Result<PcapRecord, PcapError>> {
+        if self.failed {
+            return None;
+        }
+        let record = self.read_record();
+        // The position in the file can no longer be trusted
+        self.failed = record.is_err();
+        record.map_err(|e| PcapError::Io { source: e }).transpose()
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<PcapRecord>> {
+        let mut header = [0u8; 16];
+        // A clean end of file can only occur between records
+        match self.inner.read(&mut header[..1])? {
+            0 => return Ok(None),
+            _ => self.inner.read_exact(&mut header[1..])?,
+        }
+        let secs = self.u32_at(&header, 0) as u64;
+        let fraction = self.u32_at(&header, 4);
+        let timestamp = if self.nanos {
+            Duration::new(secs, fraction)
+        } else {
+            Duration::new(secs, 0) + Duration::from_micros(fraction as u64)
+        };
+        let incl_len = self.u32_at(&header, 8);
+        if incl_len > self.snaplen || incl_len > MAX_RECORD_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("packet length {} is too long", incl_len),
+            ));
+        }
+        let mut bytes = vec![0u8; incl_len as usize];
+        self.inner.read_exact(&mut bytes)?;
+        if self.linktype == LINKTYPE_AX25_KISS && !bytes.is_empty() {
+            bytes.remove(0);
+        }
+        Ok(Some(PcapRecord { timestamp, bytes }))
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<Ax25Frame, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_record()?.and_then(|record| {
+            Ax25Frame::from_bytes(&record.bytes).map_err(|e| PcapError::Parse { source: e })
+        }))
+    }
+}
+
+#[test]
+fn test_pcap_round_trip() {
+    use std::fs::{read, read_dir};
+
+    let mut frames = Vec::new();
+    let mut paths: Vec<_> = read_dir("testdata/linux-ax0")
+        .unwrap()
+        .map(|r| r.unwrap().path())
+        .collect();
+    paths.sort();
+    for path in paths {
+        // Skip the leading null byte from Linux AF_PACKET
+        let data = read(path).unwrap();
+        frames.push(Ax25Frame::from_bytes(&data[1..]).unwrap());
+    }
+
+    let mut writer = PcapWriter::new(Vec::new()).unwrap();
+    for frame in &frames {
+        writer.write_frame(frame).unwrap();
+    }
+    let capture = writer.inner;
+    assert_eq!(&capture[0..4], &[0xd4, 0xc3, 0xb2, 0xa1]);
+
+    let read_back: Vec<Ax25Frame> = PcapReader::new(&capture[..])
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+    assert_eq!(read_back, frames);
+}
+
+#[test]
+fn test_pcap_append() {
+    let path = std::env::temp_dir().join(format!("ax25_pcap_append_{}", std::process::id()));
+    let frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        b"hello".to_vec(),
+    );
+    let _ = std::fs::remove_file(&path);
+    for _ in 0..2 {
+        let mut writer = PcapWriter::append(&path).unwrap();
+        writer.write_frame(&frame).unwrap();
+        writer.flush().unwrap();
+    }
+    let frames: Vec<Ax25Frame> = PcapReader::open(&path)
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+    assert_eq!(frames, vec![frame.clone(), frame.clone()]);
+
+    // Anything else is left alone
+    for contents in [&[0x00, 0x05, 0x01][..], &[0xd4, 0xc3, 0xb2, 0xa1]] {
+        std::fs::write(&path, contents).unwrap();
+        let e = PcapWriter::append(&path).err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(std::fs::read(&path).unwrap(), contents);
+    }
+    let mut kiss = PcapWriter::new(Vec::new()).unwrap().inner;
+    kiss[20..24].copy_from_slice(&LINKTYPE_AX25_KISS.to_le_bytes());
+    std::fs::write(&path, &kiss).unwrap();
+    assert!(PcapWriter::append(&path).is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_pcap_kiss_big_endian() {
+    let frame = Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        b"hello".to_vec(),
+    );
+    let bytes = frame.to_bytes();
+    let mut capture = Vec::new();
+    capture.extend(MAGIC_MICROS.to_be_bytes());
+    capture.extend([0, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0]);
+    capture.extend(SNAPLEN.to_be_bytes());
+    capture.extend(LINKTYPE_AX25_KISS.to_be_bytes());
+    capture.extend([0; 8]);
+    capture.extend((bytes.len() as u32 + 1).to_be_bytes());
+    capture.extend((bytes.len() as u32 + 1).to_be_bytes());
+    capture.push(0x00);
+    capture.extend(&bytes);
+
+    let mut reader = PcapReader::new(&capture[..]).unwrap();
+    assert_eq!(reader.next().unwrap().unwrap(), frame);
+    assert!(reader.next().is_none());
+
+    // Truncated record
+    capture.pop();
+    let mut reader = PcapReader::new(&capture[..]).unwrap();
+    assert!(matches!(reader.next(), Some(Err(PcapError::Io { .. }))));
+    assert!(reader.next().is_none());
+
+    // Longer than the snaplen, which is checked before allocating
+    capture.truncate(32);
+    capture.extend((SNAPLEN + 1).to_be_bytes());
+    capture.extend((SNAPLEN + 1).to_be_bytes());
+    capture.extend([0; 64]);
+    let mut reader = PcapReader::new(&capture[..]).unwrap();
+    assert!(matches!(
+        reader.next(),
+        Some(Err(PcapError::Io { source })) if source.kind() == io::ErrorKind::InvalidData
+    ));
+    assert!(reader.next().is_none());
+}
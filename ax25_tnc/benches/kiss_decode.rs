@@ -0,0 +1,75 @@
+//! Compares decoding a 256 KB stream of small KISS frames with `FrameDecoder` against
+//! calling the stateless `kiss_decode` on a growing buffer.
+//!
+//! Run with `cargo bench -p ax25_tnc --bench kiss_decode`.
+
+use ax25_tnc::kiss::{kiss_decode, kiss_encode, FrameDecoder};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const STREAM_LEN: usize = 256 * 1024;
+
+fn stream() -> (Vec<u8>, usize) {
+    let frame: Vec<u8> = (0..40u8).collect();
+    let encoded = kiss_encode(&frame);
+    let count = STREAM_LEN / encoded.len();
+    (encoded.repeat(count), count)
+}
+
+fn with_decoder(stream: &[u8], read_size: usize) -> usize {
+    let mut decoder = FrameDecoder::new().with_max_buffer(usize::MAX);
+    let mut frames = 0;
+    for chunk in stream.chunks(read_size) {
+        decoder.push(chunk);
+        while let Some(frame) = decoder.next_frame() {
+            black_box(frame);
+            frames += 1;
+        }
+    }
+    frames
+}
+
+fn with_kiss_decode(stream: &[u8], read_size: usize) -> usize {
+    let mut buffer = Vec::new();
+    let mut frames = 0;
+    for chunk in stream.chunks(read_size) {
+        buffer.extend_from_slice(chunk);
+        while let Some(frame) = kiss_decode(&mut buffer) {
+            black_box(frame);
+            frames += 1;
+        }
+    }
+    frames
+}
+
+fn time(name: &str, iterations: u32, expected: usize, f: impl Fn() -> usize) {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        assert_eq!(f(), expected);
+    }
+    let per_iteration = start.elapsed() / iterations;
+    println!("{:<40} {:>10.3} ms", name, as_millis(per_iteration));
+}
+
+fn as_millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+fn main() {
+    let (stream, count) = stream();
+    println!("{} frames in {} bytes", count, stream.len());
+    for read_size in [1024, STREAM_LEN] {
+        time(
+            &format!("FrameDecoder, {} byte reads", read_size),
+            20,
+            count,
+            || with_decoder(&stream, read_size),
+        );
+        time(
+            &format!("kiss_decode, {} byte reads", read_size),
+            3,
+            count,
+            || with_kiss_decode(&stream, read_size),
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use ax25::digipeater::DigipeaterRules;
+use ax25::frame::Address;
+use ax25_tnc::tnc::{Tnc, TncAddress};
+use std::env;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        println!("Usage: {} <tnc-address> <my-callsign>", args[0]);
+        std::process::exit(1);
+    }
+
+    let addr = args[1].parse::<TncAddress>()?;
+    let mycall = args[2].parse::<Address>()?;
+    let tnc = Tnc::open(&addr)?;
+
+    // Repeat any frame where we or a WIDEn-N alias are the next hop in its route
+    let rules = DigipeaterRules::default();
+    for frame in tnc.frames() {
+        let frame = frame?;
+        if let Some(repeated) = rules.process(&frame, &mycall) {
+            println!(
+                "Repeating frame from {} to {}",
+                frame.source, frame.destination
+            );
+            tnc.send_frame(&repeated)?;
+        }
+    }
+    Ok(())
+}
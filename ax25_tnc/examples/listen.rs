@@ -15,8 +15,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let addr = args[1].parse::<TncAddress>()?;
     let tnc = Tnc::open(&addr)?;
 
-    let receiver = tnc.incoming();
-    while let Ok(frame) = receiver.recv().unwrap() {
+    for frame in tnc.frames() {
+        let frame = frame?;
         println!("{}", OffsetDateTime::now_utc());
         println!("{}", frame);
     }
@@ -1,6 +1,4 @@
-use ax25::frame::{
-    Address, Ax25Frame, CommandResponse, FrameContent, ProtocolIdentifier, UnnumberedInformation,
-};
+use ax25::frame::{Address, Ax25Frame, FrameContent, ProtocolIdentifier, UnnumberedInformation};
 use ax25_tnc::tnc::{Tnc, TncAddress};
 use std::env;
 
@@ -22,17 +20,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let dest = args[3].parse::<Address>()?;
     let tnc = Tnc::open(&addr)?;
 
-    let frame = Ax25Frame {
-        source: src,
-        destination: dest,
-        route: Vec::new(),
-        command_or_response: Some(CommandResponse::Command),
-        content: FrameContent::UnnumberedInformation(UnnumberedInformation {
+    let frame = Ax25Frame::new(
+        src,
+        dest,
+        FrameContent::UnnumberedInformation(UnnumberedInformation {
             pid: ProtocolIdentifier::None,
             info: args[4].as_bytes().to_vec(),
             poll_or_final: false,
         }),
-    };
+    );
 
     tnc.send_frame(&frame)?;
     println!("Transmitted!");
@@ -1,10 +1,8 @@
-use ax25::frame::{
-    Address, Ax25Frame, CommandResponse, FrameContent, ProtocolIdentifier, UnnumberedInformation,
-};
+use ax25::frame::{Address, Ax25Frame, FrameContent, ProtocolIdentifier, UnnumberedInformation};
+use ax25_tnc::beacon::{random_offset, Beacon};
 use ax25_tnc::tnc::{Tnc, TncAddress};
 use std::env;
 use std::error::Error;
-use std::thread;
 use std::time::Duration;
 use time::OffsetDateTime;
 
@@ -19,24 +17,26 @@ fn main() -> Result<(), Box<dyn Error>> {
     let src = args[2].parse::<Address>()?;
     let tnc = Tnc::open(&addr)?;
 
-    // Do periodic announcements on a second thread
-    let broadcast_dest = "TIME-0".parse::<Address>().unwrap();
+    // Do periodic announcements in the background
+    let broadcast_dest = Address::new("TIME", 0)?;
     let src_1 = src.clone();
-    let tnc_1 = tnc.clone();
-    thread::spawn(move || loop {
-        if transmit_time(&tnc_1, &src_1, &broadcast_dest).is_err() {
-            break;
-        }
-        thread::sleep(Duration::from_secs(60));
-    });
+    let _beacon = Beacon::start_with(
+        tnc.clone(),
+        Duration::from_secs(60),
+        random_offset(Duration::from_secs(5)),
+        move || time_frame(&src_1, &broadcast_dest),
+    );
 
     // Receive on the initial thread
-    let receiver = tnc.incoming();
-    while let Ok(frame) = receiver.recv().unwrap() {
+    for frame in tnc.frames() {
+        let frame = frame?;
         // If someone asks us what the time is, tell them immediately
         if let Some(text) = frame.info_string_lossy() {
             if text.contains("what is the time?") {
-                transmit_time(&tnc, &src, &frame.source)?;
+                // Answer from our own callsign whatever they addressed the question to
+                let mut reply = frame.reply(time_info(), ProtocolIdentifier::None);
+                reply.source = src.clone();
+                tnc.send_frame(&reply)?;
             }
         }
     }
@@ -44,20 +44,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn transmit_time(tnc: &Tnc, src: &Address, dest: &Address) -> Result<(), Box<dyn Error>> {
-    let frame = Ax25Frame {
-        source: src.clone(),
-        destination: dest.clone(),
-        route: Vec::new(),
-        command_or_response: Some(CommandResponse::Command),
-        content: FrameContent::UnnumberedInformation(UnnumberedInformation {
+fn time_frame(src: &Address, dest: &Address) -> Ax25Frame {
+    Ax25Frame::new(
+        src.clone(),
+        dest.clone(),
+        FrameContent::UnnumberedInformation(UnnumberedInformation {
             pid: ProtocolIdentifier::None,
-            info: format!("The time is: {}", OffsetDateTime::now_utc())
-                .as_bytes()
-                .to_vec(),
+            info: time_info(),
             poll_or_final: false,
         }),
-    };
-    tnc.send_frame(&frame)?;
-    Ok(())
+    )
+}
+
+fn time_info() -> Vec<u8> {
+    format!("The time is: {}", OffsetDateTime::now_utc()).into_bytes()
 }
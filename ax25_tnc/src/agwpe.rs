@@ -0,0 +1,257 @@
+use std::io;
+use std::io::prelude::*;
+use std::net::Shutdown;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const HEADER_LEN: usize = 36;
+/// Largest data length accepted from the server. Raw AX.25 frames are far smaller, so
+/// anything beyond this means the stream is corrupt.
+const MAX_DATA_LEN: usize = 64 * 1024;
+/// Send or receive a raw AX.25 frame
+const KIND_RAW_FRAME: u8 = b'K';
+/// Toggle reception of raw AX.25 frames
+const KIND_ENABLE_RAW: u8 = b'k';
+
+/// A single message in the AGWPE protocol: a fixed 36-byte header and variable data.
+#[derive(Debug, PartialEq, Eq)]
+struct AgwFrame {
+    port: u8,
+    data_kind: u8,
+    data: Vec<u8>,
+}
+
+impl AgwFrame {
+    fn encode(&self) -> Vec<u8> {
+        let mut encoded = vec![0u8; HEADER_LEN];
+        encoded[0] = self.port;
+        encoded[4] = self.data_kind;
+        // CallFrom, CallTo and PID are unused for the frame kinds we send
+        encoded[28..32].copy_from_slice(&(self.data.len() as u32).to_le_bytes());
+        encoded.extend(&self.data);
+        encoded
+    }
+}
+
+/// Take the next complete AGWPE message off the front of the buffer, if there is one.
+///
+/// Fails with `io::ErrorKind::InvalidData` if the header claims more than
+/// `MAX_DATA_LEN` bytes of data, since there is no way to find the next header.
+fn take_agw_frame(buffer: &mut Vec<u8>) -> io::Result<Option<AgwFrame>> {
+    if buffer.len() < HEADER_LEN {
+        return Ok(None);
+    }
+    let data_len = u32::from_le_bytes([buffer[28], buffer[29], buffer[30], buffer[31]]) as usize;
+    if data_len > MAX_DATA_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("AGWPE data length {} is too long", data_len),
+        ));
+    }
+    if buffer.len() < HEADER_LEN + data_len {
+        return Ok(None);
+    }
+    let frame = AgwFrame {
+        port: buffer[0],
+        data_kind: buffer[4],
+        data: buffer[HEADER_LEN..HEADER_LEN + data_len].to_vec(),
+    };
+    buffer.drain(0..HEADER_LEN + data_len);
+    Ok(Some(frame))
+}
+
+/// A connection to an AGWPE-compatible server such as AGW Packet Engine or Dire Wolf.
+pub(crate) struct AgwpeInterface {
+    tx_stream: Mutex<TcpStream>,
+    rx_stream: Mutex<TcpStream>,
+    buffer: Mutex<Vec<u8>>,
+    radio_port: u8,
+    is_shutdown: AtomicBool,
+}
+
+impl AgwpeInterface {
+    /// Connect to the server and ask it to deliver raw frames heard on `radio_port`.
+    pub(crate) fn new<A: ToSocketAddrs>(addr: A, radio_port: u8) -> io::Result<AgwpeInterface> {
+        let tx_stream = TcpStream::connect(addr)?;
+        let rx_stream = tx_stream.try_clone()?;
+        let iface = AgwpeInterface {
+            tx_stream: Mutex::new(tx_stream),
+            rx_stream: Mutex::new(rx_stream),
+            buffer: Mutex::new(Vec::new()),
+            radio_port,
+            is_shutdown: AtomicBool::new(false),
+        };
+        iface.send(&AgwFrame {
+            port: radio_port,
+            data_kind: KIND_ENABLE_RAW,
+            data: Vec::new(),
+        })?;
+        Ok(iface)
+    }
+
+    /// Wait up to `timeout` for a raw AX.25 frame to be received.
+    ///
+    /// If the server sends a corrupt header the connection is shut down and an error
+    /// of kind `io::ErrorKind::InvalidData` is returned.
+    pub(crate) fn receive_frame_timeout(&self, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(frame) = self.take_buffered_frame()? {
+                return Ok(Some(frame));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || !self.read_into_buffer(remaining)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn take_buffered_frame(&self) -> io::Result<Option<Vec<u8>>> {
+        let mut buffer = self.buffer.lock().unwrap();
+        loop {
+            let frame = match take_agw_frame(&mut buffer) {
+                Ok(Some(frame)) => frame,
+                Ok(None) => return Ok(None),
+                Err(e) => {
+                    buffer.clear();
+                    self.shutdown();
+                    return Err(e);
+                }
+            };
+            // Raw frames are prefixed with a KISS-style command byte
+            if frame.data_kind == KIND_RAW_FRAME
+                && frame.port == self.radio_port
+                && !frame.data.is_empty()
+            {
+                return Ok(Some(frame.data[1..].to_vec()));
+            }
+        }
+    }
+
+    fn read_into_buffer(&self, timeout: Duration) -> io::Result<bool> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "interface has been shut down",
+            ));
+        }
+        let mut buf = vec![0u8; 1024];
+        let n_bytes = {
+            let mut rx_stream = self.rx_stream.lock().unwrap();
+            rx_stream.set_read_timeout(Some(timeout))?;
+            match rx_stream.read(&mut buf) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "AGWPE server closed the connection",
+                    ))
+                }
+                Ok(n) => n,
+                Err(e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut =>
+                {
+                    return Ok(false)
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.extend(buf.iter().take(n_bytes));
+        Ok(true)
+    }
+
+    /// Transmit a raw AX.25 frame.
+    pub(crate) fn send_frame(&self, frame: &[u8]) -> io::Result<()> {
+        let mut data = Vec::with_capacity(frame.len() + 1);
+        data.push(0x00);
+        data.extend(frame);
+        self.send(&AgwFrame {
+            port: self.radio_port,
+            data_kind: KIND_RAW_FRAME,
+            data,
+        })
+    }
+
+    fn send(&self, frame: &AgwFrame) -> io::Result<()> {
+        let mut tx_stream = self.tx_stream.lock().unwrap();
+        tx_stream.write_all(&frame.encode())?;
+        tx_stream.flush()
+    }
+
+    pub(crate) fn shutdown(&self) {
+        if !self.is_shutdown.swap(true, Ordering::SeqCst) {
+            let tx_stream = self.tx_stream.lock().unwrap();
+            let _ = tx_stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl Drop for AgwpeInterface {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[test]
+fn test_agw_frame_round_trip() {
+    let frame = AgwFrame {
+        port: 1,
+        data_kind: KIND_RAW_FRAME,
+        data: vec![0x00, 0x82, 0xA0],
+    };
+    let mut buffer = frame.encode();
+    assert_eq!(buffer.len(), HEADER_LEN + 3);
+    assert_eq!(buffer[4], b'K');
+    assert_eq!(&buffer[28..32], &[3, 0, 0, 0]);
+    buffer.push(0x55);
+    assert_eq!(take_agw_frame(&mut buffer).unwrap(), Some(frame));
+    assert_eq!(buffer, vec![0x55]);
+}
+
+#[test]
+fn test_agw_partial_frame() {
+    let mut buffer = AgwFrame {
+        port: 0,
+        data_kind: KIND_RAW_FRAME,
+        data: vec![0x00, 0x01],
+    }
+    .encode();
+    let tail = buffer.split_off(HEADER_LEN + 1);
+    assert_eq!(take_agw_frame(&mut buffer).unwrap(), None);
+    buffer.extend(tail);
+    assert!(take_agw_frame(&mut buffer).unwrap().is_some());
+}
+
+#[test]
+fn test_agw_data_len_too_long() {
+    let mut buffer = vec![0u8; HEADER_LEN];
+    buffer[28..32].copy_from_slice(&(MAX_DATA_LEN as u32 + 1).to_le_bytes());
+    assert_eq!(
+        take_agw_frame(&mut buffer).unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+
+    // A server sending such a header is disconnected rather than buffered without limit
+    let listener = crate::test_util::local_listener();
+    let iface = AgwpeInterface::new(listener.local_addr().unwrap(), 0).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+    server.write_all(&buffer).unwrap();
+    assert_eq!(
+        iface
+            .receive_frame_timeout(Duration::from_secs(5))
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::InvalidData
+    );
+    assert_eq!(
+        iface
+            .receive_frame_timeout(Duration::from_secs(5))
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::NotConnected
+    );
+}
@@ -0,0 +1,280 @@
+use crate::kiss::{kiss_encode, overflow_error, smack_encode, FrameDecoder};
+use crate::tnc::{resolved, Ax25FrameResult, ConnectConfig, Tnc, TncAddress, TncError};
+use ax25::frame::Ax25Frame;
+use futures_core::Stream;
+use std::io;
+use std::pin::Pin;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::thread;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+
+/// An asynchronous counterpart to `Tnc` for use with the tokio runtime.
+///
+/// TCP KISS TNCs are driven entirely by tokio's non-blocking I/O. Other TNC types, and
+/// TCP KISS TNCs with `TcpKissConfig::reconnect` set, are opened as a regular `Tnc` and
+/// bridged, which uses a thread per subscriber.
+///
+/// The `AsyncTnc` can be cloned to share it between tasks.
+#[derive(Clone)]
+pub struct AsyncTnc(Arc<AsyncTncInner>);
+
+struct AsyncTncInner {
+    transport: Transport,
+    senders: Arc<Mutex<Vec<UnboundedSender<Ax25FrameResult>>>>,
+}
+
+enum Transport {
+    TcpKiss {
+        tx: tokio::sync::Mutex<OwnedWriteHalf>,
+        reader: JoinHandle<()>,
+        smack: bool,
+    },
+    Blocking(Tnc),
+}
+
+impl AsyncTnc {
+    /// Attempt to obtain a connection to a TNC using the provided address.
+    ///
+    /// This must be called from within a tokio runtime. For TNC types other than
+    /// `tcpkiss` the underlying open call is blocking, though usually brief.
+    pub async fn open(address: &TncAddress) -> Result<Self, TncError> {
+        let senders = Arc::new(Mutex::new(Vec::new()));
+        let transport = match &address.config {
+            ConnectConfig::TcpKiss(config) if config.reconnect.is_none() => {
+                let lookup = tokio::net::lookup_host((config.host.as_str(), config.port)).await;
+                let addrs = resolved(&config.host, lookup)?;
+                let connect = TcpStream::connect(&addrs[..]);
+                let stream = tokio::time::timeout(config.connect_timeout, connect)
+                    .await
+                    .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))
+                    .map_err(|e| TncError::OpenTnc { source: e })?;
+                if let Some(policy) = &config.keepalive {
+                    policy
+                        .apply(&stream)
+                        .map_err(|e| TncError::OpenTnc { source: e })?;
+                }
+                let (rx, tx) = stream.into_split();
+                Transport::TcpKiss {
+                    tx: tokio::sync::Mutex::new(tx),
+                    reader: tokio::spawn(read_kiss(
+                        rx,
+                        config.smack,
+                        config.max_buffer,
+                        senders.clone(),
+                    )),
+                    smack: config.smack,
+                }
+            }
+            _ => Transport::Blocking(Tnc::open(address)?),
+        };
+        Ok(AsyncTnc(Arc::new(AsyncTncInner { transport, senders })))
+    }
+
+    /// Transmit a frame on the radio. Transmission is not guaranteed even if a
+    /// `Ok` result is returned.
+    pub async fn send_frame(&self, frame: &Ax25Frame) -> Result<(), TncError> {
+        match &self.0.transport {
+            Transport::TcpKiss { tx, smack, .. } => {
+                let encoded = if *smack {
+                    smack_encode(&frame.to_bytes())
+                } else {
+                    kiss_encode(&frame.to_bytes())
+                };
+                let mut tx = tx.lock().await;
+                tx.write_all(&encoded)
+                    .await
+                    .map_err(|e| TncError::SendFrame { source: e })
+            }
+            Transport::Blocking(tnc) => {
+                let tnc = tnc.clone();
+                let frame = frame.clone();
+                tokio::task::spawn_blocking(move || tnc.send_frame(&frame))
+                    .await
+                    .map_err(|e| TncError::SendFrame {
+                        source: io::Error::new(io::ErrorKind::Other, e),
+                    })?
+            }
+        }
+    }
+
+    /// Create a new `Stream` which will receive a copy of all incoming frames.
+    ///
+    /// The stream ends after yielding an error, or once the TNC is closed. The exception
+    /// is `TncError::ReceiveOverflow`, after which frames continue to be received.
+    pub fn incoming(&self) -> IncomingFrames {
+        match &self.0.transport {
+            Transport::TcpKiss { .. } => {
+                let (sender, receiver) = unbounded_channel();
+                self.0.senders.lock().unwrap().push(sender);
+                IncomingFrames { receiver }
+            }
+            Transport::Blocking(tnc) => IncomingFrames::bridge(tnc.incoming()),
+        }
+    }
+}
+
+impl Drop for AsyncTncInner {
+    fn drop(&mut self) {
+        if let Transport::TcpKiss { reader, .. } = &self.transport {
+            reader.abort();
+        }
+        self.senders.lock().unwrap().clear();
+    }
+}
+
+/// A `Stream` of frames received by an `AsyncTnc`.
+pub struct IncomingFrames {
+    receiver: UnboundedReceiver<Ax25FrameResult>,
+}
+
+impl IncomingFrames {
+    /// Forward frames from a blocking `Receiver` on a thread of their own, until either
+    /// side is closed.
+    pub(crate) fn bridge(incoming: Receiver<Ax25FrameResult>) -> IncomingFrames {
+        let (sender, receiver) = unbounded_channel();
+        thread::spawn(move || {
+            for frame in incoming.iter() {
+                if sender.send(frame).is_err() {
+                    break;
+                }
+            }
+        });
+        IncomingFrames { receiver }
+    }
+}
+
+impl Stream for IncomingFrames {
+    type Item = Ax25FrameResult;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+async fn read_kiss(
+    mut rx: OwnedReadHalf,
+    smack: bool,
+    max_buffer: usize,
+    senders: Arc<Mutex<Vec<UnboundedSender<Ax25FrameResult>>>>,
+) {
+    let distribute = |x: Ax25FrameResult| {
+        senders
+            .lock()
+            .unwrap()
+            .retain(|s| s.send(x.clone()).is_ok());
+    };
+    let mut decoder = FrameDecoder::new().with_max_buffer(max_buffer);
+    if smack {
+        decoder = decoder.with_smack();
+    }
+    let mut buf = vec![0u8; 1024];
+    loop {
+        while let Some(frame) = decoder.next_frame() {
+            if let Ok(parsed) = Ax25Frame::from_bytes(&frame) {
+                distribute(Ok(parsed));
+            }
+        }
+        if let Some(discarded) = decoder.take_discarded() {
+            distribute(Err(Arc::new(TncError::ReceiveOverflow {
+                source: overflow_error(discarded),
+            })));
+        }
+        match rx.read(&mut buf).await {
+            Ok(0) => {
+                distribute(Err(Arc::new(TncError::ReceiveFrame {
+                    source: io::ErrorKind::UnexpectedEof.into(),
+                })));
+                break;
+            }
+            Ok(n) => decoder.push(&buf[..n]),
+            Err(e) => {
+                distribute(Err(Arc::new(TncError::ReceiveFrame { source: e })));
+                break;
+            }
+        }
+    }
+    senders.lock().unwrap().clear();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kiss::ReconnectPolicy;
+    use crate::test_util::test_frame;
+    use crate::tnc::TcpKissConfig;
+    use std::future::poll_fn;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn send_and_receive_kiss() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let addr = TncAddress::new_tcpkiss(TcpKissConfig::new("127.0.0.1", port));
+        let tnc = AsyncTnc::open(&addr).await.unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+        let mut incoming = tnc.incoming();
+
+        let frame = test_frame(b"hello");
+        tnc.send_frame(&frame).await.unwrap();
+        let expected = kiss_encode(&frame.to_bytes());
+        let mut received = vec![0u8; expected.len()];
+        server.read_exact(&mut received).await.unwrap();
+        assert_eq!(received, expected);
+
+        server.write_all(&expected).await.unwrap();
+        let next = poll_fn(|cx| Pin::new(&mut incoming).poll_next(cx)).await;
+        assert_eq!(next.unwrap().unwrap(), frame);
+    }
+
+    #[tokio::test]
+    async fn unresolvable_host_is_distinct() {
+        let addr = TncAddress::new_tcpkiss(TcpKissConfig::new("no-such-host.invalid", 8001));
+        assert!(matches!(
+            AsyncTnc::open(&addr).await,
+            Err(TncError::ResolveHost { host, .. }) if host == "no-such-host.invalid"
+        ));
+    }
+
+    #[tokio::test]
+    async fn tcp_kiss_reconnects() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut config = TcpKissConfig::new("127.0.0.1", port);
+        config.reconnect = Some(ReconnectPolicy {
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+        });
+        let tnc = AsyncTnc::open(&TncAddress::new_tcpkiss(config))
+            .await
+            .unwrap();
+        let mut incoming = tnc.incoming();
+        let (server, _) = listener.accept().await.unwrap();
+        drop(server);
+
+        let (mut server, _) = listener.accept().await.unwrap();
+        let frame = test_frame(b"hello");
+        server
+            .write_all(&kiss_encode(&frame.to_bytes()))
+            .await
+            .unwrap();
+        let next = poll_fn(|cx| Pin::new(&mut incoming).poll_next(cx)).await;
+        assert_eq!(next.unwrap().unwrap(), frame);
+    }
+
+    #[tokio::test]
+    async fn blocking_tnc_as_stream() {
+        let (tnc, other) = Tnc::loopback_pair();
+        let mut incoming = tnc.incoming_stream();
+        let frame = test_frame(b"hello");
+        other.send_frame(&frame).unwrap();
+        let next = poll_fn(|cx| Pin::new(&mut incoming).poll_next(cx)).await;
+        assert_eq!(next.unwrap().unwrap(), frame);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::kiss::same_host;
+use ax25::hdlc::{check_fcs, fcs};
+use std::io;
+use std::net::SocketAddr;
+use std::net::ToSocketAddrs;
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Largest datagram accepted. AX.25 frames are far smaller than this.
+const MAX_DATAGRAM: usize = 2048;
+
+/// Wrap an AX.25 frame in an AXUDP datagram by appending its FCS, least significant
+/// byte first.
+pub fn axudp_encode(frame: &[u8]) -> Vec<u8> {
+    let mut datagram = Vec::with_capacity(frame.len() + 2);
+    datagram.extend_from_slice(frame);
+    datagram.extend_from_slice(&fcs(frame).to_le_bytes());
+    datagram
+}
+
+/// Take the AX.25 frame from an AXUDP datagram, or `None` if the datagram is too short
+/// or its FCS does not match.
+pub fn axudp_decode(datagram: &[u8]) -> Option<&[u8]> {
+    match check_fcs(datagram) {
+        Ok((frame, check)) if check.is_valid() => Some(frame),
+        _ => None,
+    }
+}
+
+/// A link to an AXUDP peer, such as `ax25ipd`, JNOS or URONode, where each UDP datagram
+/// carries one AX.25 frame followed by its FCS.
+pub struct AxudpInterface {
+    socket: UdpSocket,
+    remote: SocketAddr,
+    is_shutdown: AtomicBool,
+    bad_checksums: AtomicU64,
+    any_sender: bool,
+}
+
+impl AxudpInterface {
+    /// Bind a UDP socket to `local` and exchange frames with the peer at `remote`.
+    ///
+    /// Frames are only accepted from the IP address of `remote`, but from any port,
+    /// since a node may send from a different port to the one it receives on.
+    pub fn new<A: ToSocketAddrs, B: ToSocketAddrs>(
+        local: A,
+        remote: B,
+    ) -> io::Result<AxudpInterface> {
+        let remote = remote.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "remote address did not resolve")
+        })?;
+        Ok(AxudpInterface {
+            socket: UdpSocket::bind(local)?,
+            remote,
+            is_shutdown: AtomicBool::new(false),
+            bad_checksums: AtomicU64::new(0),
+            any_sender: false,
+        })
+    }
+
+    /// Accept frames from any sender rather than only the peer's IP address.
+    ///
+    /// Any host which can reach the local port is then able to inject frames.
+    pub fn with_any_sender(mut self) -> Self {
+        self.any_sender = true;
+        self
+    }
+
+    /// The local address that the peer should send frames to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// The address of the peer that frames are sent to.
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote
+    }
+
+    /// How many datagrams have been dropped because their FCS did not match.
+    pub fn bad_checksums(&self) -> u64 {
+        self.bad_checksums.load(Ordering::SeqCst)
+    }
+
+    /// Wait up to `timeout` for a frame to be received from the peer.
+    pub fn receive_frame_timeout(&self, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0u8; MAX_DATAGRAM];
+        loop {
+            if self.is_shutdown.load(Ordering::SeqCst) {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "interface has been shut down",
+                ));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            self.socket.set_read_timeout(Some(remaining))?;
+            let n_bytes = match self.socket.recv_from(&mut buf) {
+                Ok((_, from)) if !self.any_sender && !same_host(from, self.remote) => continue,
+                Ok((n, _)) => n,
+                Err(e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut =>
+                {
+                    return Ok(None)
+                }
+                Err(e) => return Err(e),
+            };
+            match axudp_decode(&buf[..n_bytes]) {
+                Some(frame) => return Ok(Some(frame.to_vec())),
+                None => {
+                    self.bad_checksums.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+
+    /// Send a frame to the peer.
+    pub fn send_frame(&self, frame: &[u8]) -> io::Result<()> {
+        self.socket
+            .send_to(&axudp_encode(frame), self.remote)
+            .map(|_| ())
+    }
+
+    /// Stop receiving. Any blocked receive will return within its timeout.
+    pub fn shutdown(&self) {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_axudp_encode_decode() {
+    use crate::test_util::test_frame;
+
+    let frame = test_frame(b"hello").to_bytes();
+    let mut datagram = axudp_encode(&frame);
+    assert_eq!(datagram.len(), frame.len() + 2);
+    assert_eq!(&datagram[..frame.len()], &frame[..]);
+    assert_eq!(axudp_decode(&datagram), Some(&frame[..]));
+
+    datagram[3] ^= 0x01;
+    assert_eq!(axudp_decode(&datagram), None);
+    assert_eq!(axudp_decode(&[0x01, 0x02]), None);
+}
+
+#[test]
+fn test_axudp_round_trip() {
+    let a = AxudpInterface::new("127.0.0.1:0", "127.0.0.1:9").unwrap();
+    let b = AxudpInterface::new("127.0.0.1:0", a.local_addr().unwrap()).unwrap();
+    let timeout = Duration::from_secs(5);
+
+    b.send_frame(&[0x01, 0x02, 0x03]).unwrap();
+    assert_eq!(
+        a.receive_frame_timeout(timeout).unwrap(),
+        Some(vec![0x01, 0x02, 0x03])
+    );
+
+    // Damaged datagrams are counted and skipped
+    let mut damaged = axudp_encode(&[0x04, 0x05]);
+    damaged[0] ^= 0xff;
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender.send_to(&damaged, a.local_addr().unwrap()).unwrap();
+    b.send_frame(&[0x06]).unwrap();
+    assert_eq!(a.receive_frame_timeout(timeout).unwrap(), Some(vec![0x06]));
+    assert_eq!(a.bad_checksums(), 1);
+
+    assert_eq!(
+        a.receive_frame_timeout(Duration::from_millis(50)).unwrap(),
+        None
+    );
+    a.shutdown();
+    assert_eq!(
+        a.receive_frame_timeout(timeout).unwrap_err().kind(),
+        io::ErrorKind::NotConnected
+    );
+}
+
+#[test]
+fn test_axudp_other_sender() {
+    let iface = AxudpInterface::new("127.0.0.1:0", "127.0.0.2:9").unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let datagram = axudp_encode(&[0x01]);
+    sender
+        .send_to(&datagram, iface.local_addr().unwrap())
+        .unwrap();
+    assert_eq!(
+        iface
+            .receive_frame_timeout(Duration::from_millis(200))
+            .unwrap(),
+        None
+    );
+
+    let iface = iface.with_any_sender();
+    sender
+        .send_to(&datagram, iface.local_addr().unwrap())
+        .unwrap();
+    assert_eq!(
+        iface.receive_frame_timeout(Duration::from_secs(5)).unwrap(),
+        Some(vec![0x01])
+    );
+}
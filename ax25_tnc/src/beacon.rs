@@ -0,0 +1,264 @@
+use crate::tnc::{random_u64, Tnc, TncError};
+use ax25::frame::{Address, Ax25Frame};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Default time between identifications, leaving a margin under the common
+/// requirement to identify at least every 10 minutes.
+pub const DEFAULT_ID_INTERVAL: Duration = Duration::from_secs(9 * 60 + 30);
+
+/// Transmits a frame periodically on its own thread, such as a position report,
+/// station identification or status message.
+///
+/// The beacon runs until `stop` is called, the `Beacon` is dropped, or sending fails
+/// because the TNC has gone away.
+pub struct Beacon {
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Beacon {
+    /// Send `frame` immediately and then every `interval`.
+    pub fn start(tnc: Tnc, frame: Ax25Frame, interval: Duration) -> Beacon {
+        Self::start_with(tnc, interval, Duration::ZERO, move || frame.clone())
+    }
+
+    /// Send a frame produced by `make_frame` every `interval`, the first after `offset`.
+    ///
+    /// The closure is called just before each transmission so the frame can contain
+    /// up-to-date information. Use `random_offset` to stagger stations that were
+    /// started at the same time.
+    pub fn start_with<F>(tnc: Tnc, interval: Duration, offset: Duration, make_frame: F) -> Beacon
+    where
+        F: FnMut() -> Ax25Frame + Send + 'static,
+    {
+        let (stop, stopped) = channel::<()>();
+        let mut make_frame = make_frame;
+        let handle = thread::spawn(move || {
+            let mut next = Instant::now() + offset;
+            loop {
+                let wait = next.saturating_duration_since(Instant::now());
+                match stopped.recv_timeout(wait) {
+                    Err(RecvTimeoutError::Timeout) => (),
+                    _ => break,
+                }
+                if tnc.send_frame(&make_frame()).is_err() {
+                    break;
+                }
+                // Schedule from the previous slot so the interval does not drift
+                next += interval;
+            }
+        });
+        Beacon {
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+
+    /// Whether the beacon is still transmitting. This is false once sending has failed.
+    pub fn is_running(&self) -> bool {
+        self.handle.as_ref().is_some_and(|h| !h.is_finished())
+    }
+
+    /// Stop transmitting. Returns once the beacon thread has finished, which is
+    /// immediate unless a frame is being sent.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        // Disconnecting the channel wakes the thread
+        self.stop.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Beacon {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Configuration for an `IdKeeper`.
+#[derive(Debug, Clone)]
+pub struct IdConfig {
+    /// The station's callsign, sent as both the source and content of ID frames
+    pub callsign: Address,
+    /// Destination of ID frames. Defaults to `ID`.
+    pub destination: Address,
+    /// Maximum time between a transmission and the next identification. Defaults to
+    /// `DEFAULT_ID_INTERVAL`.
+    pub interval: Duration,
+}
+
+impl IdConfig {
+    /// Create a configuration for the given callsign with default options.
+    pub fn new(callsign: Address) -> Self {
+        Self {
+            callsign,
+            destination: Address::new("ID", 0).unwrap(),
+            interval: DEFAULT_ID_INTERVAL,
+        }
+    }
+
+    fn id_frame(&self) -> Ax25Frame {
+        Ax25Frame::new_simple_ui_frame(
+            self.callsign.clone(),
+            self.destination.clone(),
+            self.callsign.to_string().into_bytes(),
+        )
+    }
+}
+
+/// Sends frames on a `Tnc` and identifies the station as required by amateur radio
+/// regulations.
+///
+/// An ID frame is sent when `interval` has passed since the first transmission that has
+/// not yet been followed by one, and again when the `IdKeeper` is dropped if there has
+/// been any transmission since. A station that is not transmitting is never identified.
+pub struct IdKeeper {
+    tnc: Tnc,
+    config: IdConfig,
+    pending_since: Arc<Mutex<Option<Instant>>>,
+    wake: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl IdKeeper {
+    /// Start identifying the station for frames sent through the returned `IdKeeper`.
+    pub fn new(tnc: Tnc, config: IdConfig) -> IdKeeper {
+        let pending_since = Arc::new(Mutex::new(None::<Instant>));
+        let (wake, woken) = channel::<()>();
+        let handle = {
+            let tnc = tnc.clone();
+            let config = config.clone();
+            let pending_since = pending_since.clone();
+            thread::spawn(move || loop {
+                let wait = match *pending_since.lock().unwrap() {
+                    Some(t) => (t + config.interval).saturating_duration_since(Instant::now()),
+                    None => config.interval,
+                };
+                match woken.recv_timeout(wait) {
+                    Ok(()) => continue,
+                    Err(RecvTimeoutError::Timeout) => (),
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                let mut pending_since = pending_since.lock().unwrap();
+                if pending_since.is_some_and(|t| t + config.interval <= Instant::now()) {
+                    let _ = tnc.send_frame(&config.id_frame());
+                    *pending_since = None;
+                }
+            })
+        };
+        IdKeeper {
+            tnc,
+            config,
+            pending_since,
+            wake: Some(wake),
+            handle: Some(handle),
+        }
+    }
+
+    /// Transmit a frame on the radio, starting the identification timer if it is not
+    /// already running.
+    pub fn send_frame(&self, frame: &Ax25Frame) -> Result<(), TncError> {
+        self.tnc.send_frame(frame)?;
+        let mut pending_since = self.pending_since.lock().unwrap();
+        if pending_since.is_none() {
+            *pending_since = Some(Instant::now());
+            if let Some(wake) = &self.wake {
+                let _ = wake.send(());
+            }
+        }
+        Ok(())
+    }
+
+    /// The underlying TNC. Frames sent directly on it are not tracked.
+    pub fn tnc(&self) -> &Tnc {
+        &self.tnc
+    }
+}
+
+impl Drop for IdKeeper {
+    fn drop(&mut self) {
+        self.wake.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        // Identify at the end of the last transmission
+        if self.pending_since.lock().unwrap().is_some() {
+            let _ = self.tnc.send_frame(&self.config.id_frame());
+        }
+    }
+}
+
+/// A random duration between zero and `max`, for use as a beacon's initial offset.
+pub fn random_offset(max: Duration) -> Duration {
+    let random = random_u64();
+    Duration::from_nanos(random % (max.as_nanos() as u64).max(1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::test_frame;
+
+    #[test]
+    fn beacon_repeats_until_stopped() {
+        let (tnc, other) = Tnc::loopback_pair();
+        let incoming = other.incoming();
+        let frame = test_frame(b"beacon");
+        let beacon = Beacon::start(tnc, frame.clone(), Duration::from_millis(20));
+        assert!(beacon.is_running());
+        for _ in 0..3 {
+            let received = incoming.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(received.unwrap(), frame);
+        }
+        beacon.stop();
+        // Anything sent before stopping has arrived by now
+        while incoming.recv_timeout(Duration::from_millis(200)).is_ok() {}
+        assert!(incoming.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn id_keeper_identifies_after_transmitting() {
+        let (tnc, other) = Tnc::loopback_pair();
+        let incoming = other.incoming();
+        let callsign: Address = "VK7NTK-1".parse().unwrap();
+        let mut config = IdConfig::new(callsign.clone());
+        config.interval = Duration::from_millis(50);
+        let id_frame = config.id_frame();
+        let keeper = IdKeeper::new(tnc, config);
+
+        // Nothing is sent while idle
+        assert!(incoming.recv_timeout(Duration::from_millis(150)).is_err());
+
+        let frame =
+            Ax25Frame::new_simple_ui_frame(callsign, "APRS".parse().unwrap(), b"hello".to_vec());
+        keeper.send_frame(&frame).unwrap();
+        let timeout = Duration::from_secs(5);
+        assert_eq!(incoming.recv_timeout(timeout).unwrap().unwrap(), frame);
+        assert_eq!(incoming.recv_timeout(timeout).unwrap().unwrap(), id_frame);
+        assert_eq!(id_frame.info_string_lossy().unwrap(), "VK7NTK-1");
+        assert!(incoming.recv_timeout(Duration::from_millis(150)).is_err());
+
+        // Stopping straight after transmitting still identifies
+        keeper.send_frame(&frame).unwrap();
+        drop(keeper);
+        assert_eq!(incoming.recv_timeout(timeout).unwrap().unwrap(), frame);
+        assert_eq!(incoming.recv_timeout(timeout).unwrap().unwrap(), id_frame);
+    }
+
+    #[test]
+    fn random_offset_is_within_range() {
+        let max = Duration::from_secs(30);
+        for _ in 0..100 {
+            assert!(random_offset(max) < max);
+        }
+        assert_eq!(random_offset(Duration::ZERO), Duration::ZERO);
+    }
+}
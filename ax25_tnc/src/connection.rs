@@ -0,0 +1,822 @@
+use crate::tnc::{Tnc, TncError};
+use ax25::frame::{
+    Address, Ax25Frame, CommandResponse, Disconnect, FrameContent, Information, ProtocolIdentifier,
+    ReceiveReady, Reject, SetAsynchronousBalancedMode, UnnumberedAcknowledge,
+};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Parameters of a connected-mode link. Slow or long-delay links such as satellites
+/// usually need a longer `t1` and smaller `window_size`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkConfig {
+    /// Maximum number of unacknowledged I frames outstanding at once (k), from 1 to 7.
+    /// Defaults to 4.
+    pub window_size: usize,
+    /// Maximum number of bytes in the information field of an I frame. Defaults to 256.
+    pub paclen: usize,
+    /// How long to wait for an acknowledgement before retransmitting. Defaults to 3
+    /// seconds.
+    pub t1: Duration,
+    /// Maximum number of retries before the link is considered to have failed. Defaults
+    /// to 10.
+    pub n2: u32,
+    /// How long the link may be idle before polling the remote station to check it is
+    /// still there, or `None` to never poll. Defaults to 5 minutes.
+    pub t3: Option<Duration>,
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        Self {
+            window_size: 4,
+            paclen: 256,
+            t1: Duration::from_secs(3),
+            n2: 10,
+            t3: Some(Duration::from_secs(300)),
+        }
+    }
+}
+
+/// Errors that can occur on a connected-mode AX.25 link.
+#[derive(Debug)]
+pub enum ConnectionError {
+    Refused,
+    Timeout,
+    FrameRejected,
+    Disconnected,
+    /// A `LinkConfig` parameter is outside the range allowed by the protocol
+    InvalidConfig {
+        reason: &'static str,
+    },
+    Tnc {
+        source: Arc<TncError>,
+    },
+}
+
+impl Error for ConnectionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Tnc { source } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Refused => write!(f, "Remote station refused the connection"),
+            Self::Timeout => write!(f, "Remote station stopped responding"),
+            Self::FrameRejected => write!(f, "Remote station rejected a frame (FRMR)"),
+            Self::Disconnected => write!(f, "Link is not connected"),
+            Self::InvalidConfig { reason } => write!(f, "Invalid link configuration: {}", reason),
+            Self::Tnc { source } => write!(f, "TNC error on connected link: {}", source),
+        }
+    }
+}
+
+enum Event {
+    Frame(Ax25Frame),
+    Write(Vec<u8>),
+    Disconnect,
+    TncFailed(Arc<TncError>),
+}
+
+/// A connected-mode (LAPB) AX.25 session with a remote station.
+///
+/// Data written to the connection is split into I frames no longer than the paclen
+/// and delivered reliably and in order. Acknowledgement, retransmission and flow
+/// control are handled by a background thread for the lifetime of the link.
+///
+/// Dropping a `Connection` requests a disconnect without waiting for it to complete.
+pub struct Connection {
+    local: Address,
+    remote: Address,
+    events: Sender<Event>,
+    data: Mutex<Receiver<Vec<u8>>>,
+    worker: Mutex<Option<JoinHandle<Result<(), ConnectionError>>>>,
+}
+
+impl Connection {
+    /// Establish a connection from `local` to `remote` by sending SABM and waiting for
+    /// the remote station to acknowledge with UA.
+    pub fn connect(tnc: &Tnc, local: Address, remote: Address) -> Result<Self, ConnectionError> {
+        Self::connect_with_config(tnc, local, remote, LinkConfig::default())
+    }
+
+    /// Establish a connection like `connect`, using the given link parameters.
+    pub fn connect_with_config(
+        tnc: &Tnc,
+        local: Address,
+        remote: Address,
+        config: LinkConfig,
+    ) -> Result<Self, ConnectionError> {
+        if !(1..=7).contains(&config.window_size) {
+            return Err(ConnectionError::InvalidConfig {
+                reason: "window size must be between 1 and 7",
+            });
+        }
+        if config.paclen == 0 {
+            return Err(ConnectionError::InvalidConfig {
+                reason: "paclen must be at least 1",
+            });
+        }
+        let (events_tx, events_rx) = channel();
+        let (data_tx, data_rx) = channel();
+        let (status_tx, status_rx) = channel();
+        let finished = Arc::new(AtomicBool::new(false));
+
+        // Subscribe before sending anything so the UA can't be missed
+        let incoming = tnc.incoming();
+        {
+            let events = events_tx.clone();
+            let finished = finished.clone();
+            let (local, remote) = (local.clone(), remote.clone());
+            thread::spawn(move || loop {
+                if finished.load(Ordering::SeqCst) {
+                    break;
+                }
+                let event = match incoming.recv_timeout(Duration::from_millis(500)) {
+                    Ok(Ok(frame)) if frame.source == remote && frame.destination == local => {
+                        Event::Frame(frame)
+                    }
+                    Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => continue,
+                    Ok(Err(e)) => Event::TncFailed(e),
+                    Err(RecvTimeoutError::Disconnected) => {
+                        Event::TncFailed(Arc::new(TncError::ReceiveFrame {
+                            source: std::io::ErrorKind::NotConnected.into(),
+                        }))
+                    }
+                };
+                let stop = matches!(event, Event::TncFailed(_));
+                if events.send(event).is_err() || stop {
+                    break;
+                }
+            });
+        }
+
+        let link = Link {
+            tnc: tnc.clone(),
+            local: local.clone(),
+            remote: remote.clone(),
+            config,
+            state: State::AwaitingConnection,
+            vs: 0,
+            vr: 0,
+            va: 0,
+            pending: VecDeque::new(),
+            unacked: VecDeque::new(),
+            t1_expiry: None,
+            t3_expiry: None,
+            retries: 0,
+            remote_busy: false,
+            reject_sent: false,
+            disconnect_requested: false,
+            data: data_tx,
+            status: Some(status_tx),
+        };
+        let worker = thread::spawn(move || {
+            let result = link.run(events_rx);
+            finished.store(true, Ordering::SeqCst);
+            result
+        });
+
+        match status_rx.recv() {
+            Ok(Ok(())) => Ok(Connection {
+                local,
+                remote,
+                events: events_tx,
+                data: Mutex::new(data_rx),
+                worker: Mutex::new(Some(worker)),
+            }),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(match worker.join() {
+                Ok(Err(e)) => e,
+                _ => ConnectionError::Disconnected,
+            }),
+        }
+    }
+
+    /// The local station's address on this link.
+    pub fn local(&self) -> &Address {
+        &self.local
+    }
+
+    /// The remote station's address on this link.
+    pub fn remote(&self) -> &Address {
+        &self.remote
+    }
+
+    /// Queue data for reliable delivery to the remote station.
+    ///
+    /// This returns once the data is queued; it does not wait for acknowledgement.
+    pub fn write(&self, data: &[u8]) -> Result<(), ConnectionError> {
+        self.events
+            .send(Event::Write(data.to_vec()))
+            .map_err(|_| ConnectionError::Disconnected)
+    }
+
+    /// Block until the next information field arrives from the remote station.
+    ///
+    /// Returns `ConnectionError::Disconnected` once the link has closed and all
+    /// received data has been read.
+    pub fn read(&self) -> Result<Vec<u8>, ConnectionError> {
+        self.data
+            .lock()
+            .unwrap()
+            .recv()
+            .map_err(|_| ConnectionError::Disconnected)
+    }
+
+    /// Send any queued data, then disconnect and wait for the remote station to confirm.
+    pub fn disconnect(&self) -> Result<(), ConnectionError> {
+        let _ = self.events.send(Event::Disconnect);
+        match self.worker.lock().unwrap().take() {
+            Some(worker) => worker.join().unwrap_or(Err(ConnectionError::Disconnected)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        let _ = self.events.send(Event::Disconnect);
+    }
+}
+
+/// The supervisory frames that the link sends in response to I frames
+enum Supervisory {
+    ReceiveReady,
+    Reject,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum State {
+    AwaitingConnection,
+    Connected,
+    AwaitingRelease,
+    Disconnected,
+}
+
+/// The state machine for one side of a connected link, owned by the worker thread.
+struct Link {
+    tnc: Tnc,
+    local: Address,
+    remote: Address,
+    config: LinkConfig,
+    state: State,
+    /// Send state variable V(S): sequence number of the next I frame to send
+    vs: u8,
+    /// Receive state variable V(R): sequence number of the next I frame expected
+    vr: u8,
+    /// Acknowledge state variable V(A): oldest I frame not yet acknowledged
+    va: u8,
+    /// Segments waiting for space in the window
+    pending: VecDeque<Vec<u8>>,
+    /// Segments that have been sent but not acknowledged, starting at V(A)
+    unacked: VecDeque<Vec<u8>>,
+    t1_expiry: Option<Instant>,
+    /// When to poll an idle link. Only runs while T1 is stopped.
+    t3_expiry: Option<Instant>,
+    retries: u32,
+    remote_busy: bool,
+    reject_sent: bool,
+    disconnect_requested: bool,
+    data: Sender<Vec<u8>>,
+    status: Option<Sender<Result<(), ConnectionError>>>,
+}
+
+impl Link {
+    fn run(mut self, events: Receiver<Event>) -> Result<(), ConnectionError> {
+        self.send_sabm()?;
+        while self.state != State::Disconnected {
+            let event = match self.t1_expiry.or(self.t3_expiry) {
+                Some(expiry) => {
+                    match events.recv_timeout(expiry.saturating_duration_since(Instant::now())) {
+                        Ok(event) => Some(event),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                None => match events.recv() {
+                    Ok(event) => Some(event),
+                    Err(_) => break,
+                },
+            };
+            match event {
+                None if self.t1_expiry.is_some() => self.t1_expired()?,
+                None => self.t3_expired()?,
+                Some(Event::Frame(frame)) => {
+                    self.handle_frame(frame)?;
+                    // Anything heard from the remote station shows the link is alive
+                    if self.t1_expiry.is_none() {
+                        self.start_t3();
+                    }
+                }
+                Some(Event::Write(data)) => self
+                    .pending
+                    .extend(data.chunks(self.config.paclen).map(|c| c.to_vec())),
+                Some(Event::Disconnect) => {
+                    if self.state == State::AwaitingConnection {
+                        return self.fail(ConnectionError::Disconnected);
+                    }
+                    self.disconnect_requested = true;
+                }
+                Some(Event::TncFailed(source)) => {
+                    return self.fail(ConnectionError::Tnc { source });
+                }
+            }
+            self.transmit_pending()?;
+        }
+        Ok(())
+    }
+
+    fn handle_frame(&mut self, frame: Ax25Frame) -> Result<(), ConnectionError> {
+        match (&self.state, frame.content) {
+            (State::AwaitingConnection, FrameContent::UnnumberedAcknowledge(_)) => {
+                self.state = State::Connected;
+                self.stop_t1();
+                if let Some(status) = self.status.take() {
+                    let _ = status.send(Ok(()));
+                }
+            }
+            (State::AwaitingConnection, FrameContent::DisconnectedMode(_)) => {
+                return self.fail(ConnectionError::Refused);
+            }
+            (State::AwaitingRelease, FrameContent::UnnumberedAcknowledge(_))
+            | (State::AwaitingRelease, FrameContent::DisconnectedMode(_)) => {
+                self.state = State::Disconnected;
+            }
+            (State::Connected, FrameContent::Information(i)) => {
+                self.process_nr(i.receive_sequence);
+                if i.send_sequence == self.vr {
+                    self.vr = (self.vr + 1) % 8;
+                    self.reject_sent = false;
+                    let _ = self.data.send(i.info);
+                    self.send_supervisory(Supervisory::ReceiveReady, i.poll)?;
+                } else if !self.reject_sent {
+                    self.reject_sent = true;
+                    self.send_supervisory(Supervisory::Reject, i.poll)?;
+                } else if i.poll {
+                    self.send_supervisory(Supervisory::ReceiveReady, true)?;
+                }
+            }
+            (State::Connected, FrameContent::ReceiveReady(rr)) => {
+                self.remote_busy = false;
+                self.process_nr(rr.receive_sequence);
+                self.answer_poll(frame.command_or_response, rr.poll_or_final)?;
+            }
+            (State::Connected, FrameContent::ReceiveNotReady(rnr)) => {
+                self.remote_busy = true;
+                self.process_nr(rnr.receive_sequence);
+                self.answer_poll(frame.command_or_response, rnr.poll_or_final)?;
+            }
+            (State::Connected, FrameContent::Reject(rej)) => {
+                self.remote_busy = false;
+                self.process_nr(rej.receive_sequence);
+                self.answer_poll(frame.command_or_response, rej.poll_or_final)?;
+                self.retransmit()?;
+            }
+            (State::Connected, FrameContent::SetAsynchronousBalancedMode(sabm)) => {
+                // The remote end has reset the link; anything in flight is lost
+                self.vs = 0;
+                self.vr = 0;
+                self.va = 0;
+                self.unacked.clear();
+                self.stop_t1();
+                self.send_ua(sabm.poll)?;
+            }
+            (State::Connected, FrameContent::Disconnect(disc)) => {
+                self.send_ua(disc.poll)?;
+                self.state = State::Disconnected;
+            }
+            (State::Connected, FrameContent::DisconnectedMode(_)) => {
+                return self.fail(ConnectionError::Disconnected);
+            }
+            (State::Connected, FrameContent::FrameReject(_)) => {
+                return self.fail(ConnectionError::FrameRejected);
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Handle an acknowledgement N(R) from the remote station.
+    fn process_nr(&mut self, nr: u8) {
+        let outstanding = (self.vs + 8 - self.va) % 8;
+        let acked = (nr + 8 - self.va) % 8;
+        if acked > outstanding {
+            // Not within V(A)..=V(S), ignore
+            return;
+        }
+        for _ in 0..acked {
+            self.unacked.pop_front();
+        }
+        self.va = nr;
+        if acked > 0 || self.unacked.is_empty() {
+            self.retries = 0;
+        }
+        if self.unacked.is_empty() {
+            self.stop_t1();
+        } else if acked > 0 {
+            self.start_t1();
+        }
+    }
+
+    fn answer_poll(
+        &mut self,
+        command_or_response: Option<CommandResponse>,
+        poll: bool,
+    ) -> Result<(), ConnectionError> {
+        if poll && command_or_response == Some(CommandResponse::Command) {
+            self.send_supervisory(Supervisory::ReceiveReady, true)?;
+        }
+        Ok(())
+    }
+
+    fn t1_expired(&mut self) -> Result<(), ConnectionError> {
+        self.retries += 1;
+        if self.retries > self.config.n2 {
+            if self.state == State::AwaitingRelease {
+                self.state = State::Disconnected;
+                return Ok(());
+            }
+            return self.fail(ConnectionError::Timeout);
+        }
+        match self.state {
+            State::AwaitingConnection => self.send_sabm(),
+            State::AwaitingRelease => self.send_disc(),
+            State::Connected if self.unacked.is_empty() => self.send_enquiry(),
+            State::Connected => self.retransmit(),
+            State::Disconnected => Ok(()),
+        }
+    }
+
+    /// The link has been idle for T3, so check that the remote station is still there.
+    fn t3_expired(&mut self) -> Result<(), ConnectionError> {
+        self.t3_expiry = None;
+        if self.state != State::Connected {
+            return Ok(());
+        }
+        self.retries = 0;
+        self.send_enquiry()
+    }
+
+    /// Send new I frames while there is room in the window, then disconnect if that
+    /// has been requested and everything has been acknowledged.
+    fn transmit_pending(&mut self) -> Result<(), ConnectionError> {
+        if self.state != State::Connected {
+            return Ok(());
+        }
+        while !self.remote_busy && self.unacked.len() < self.config.window_size {
+            let info = match self.pending.pop_front() {
+                Some(info) => info,
+                None => break,
+            };
+            self.send_i(self.vs, info.clone())?;
+            self.unacked.push_back(info);
+            self.vs = (self.vs + 1) % 8;
+            if self.t1_expiry.is_none() {
+                self.start_t1();
+            }
+        }
+        if self.disconnect_requested && self.pending.is_empty() && self.unacked.is_empty() {
+            self.state = State::AwaitingRelease;
+            self.retries = 0;
+            self.send_disc()?;
+        }
+        Ok(())
+    }
+
+    /// Go back to V(A) and send every unacknowledged I frame again.
+    fn retransmit(&mut self) -> Result<(), ConnectionError> {
+        let frames: Vec<Vec<u8>> = self.unacked.iter().cloned().collect();
+        for (i, info) in frames.into_iter().enumerate() {
+            self.send_i((self.va + i as u8) % 8, info)?;
+        }
+        if !self.unacked.is_empty() {
+            self.start_t1();
+        }
+        Ok(())
+    }
+
+    fn fail(&mut self, error: ConnectionError) -> Result<(), ConnectionError> {
+        self.state = State::Disconnected;
+        match self.status.take() {
+            Some(status) => {
+                let _ = status.send(Err(error));
+                Ok(())
+            }
+            None => Err(error),
+        }
+    }
+
+    fn start_t1(&mut self) {
+        self.t1_expiry = Some(Instant::now() + self.config.t1);
+        self.t3_expiry = None;
+    }
+
+    fn stop_t1(&mut self) {
+        self.t1_expiry = None;
+        self.start_t3();
+    }
+
+    fn start_t3(&mut self) {
+        self.t3_expiry = match self.state {
+            State::Connected => self.config.t3.map(|t3| Instant::now() + t3),
+            _ => None,
+        };
+    }
+
+    fn send_sabm(&mut self) -> Result<(), ConnectionError> {
+        self.start_t1();
+        self.send(
+            CommandResponse::Command,
+            FrameContent::SetAsynchronousBalancedMode(SetAsynchronousBalancedMode {
+                poll: true,
+                trailing: Vec::new(),
+            }),
+        )
+    }
+
+    fn send_disc(&mut self) -> Result<(), ConnectionError> {
+        self.start_t1();
+        self.send(
+            CommandResponse::Command,
+            FrameContent::Disconnect(Disconnect {
+                poll: true,
+                trailing: Vec::new(),
+            }),
+        )
+    }
+
+    /// Poll the remote station with RR, which it must answer, and wait T1 for the answer.
+    fn send_enquiry(&mut self) -> Result<(), ConnectionError> {
+        self.start_t1();
+        self.send(
+            CommandResponse::Command,
+            FrameContent::ReceiveReady(ReceiveReady {
+                receive_sequence: self.vr,
+                poll_or_final: true,
+                trailing: Vec::new(),
+            }),
+        )
+    }
+
+    fn send_ua(&mut self, final_bit: bool) -> Result<(), ConnectionError> {
+        self.send(
+            CommandResponse::Response,
+            FrameContent::UnnumberedAcknowledge(UnnumberedAcknowledge {
+                final_bit,
+                trailing: Vec::new(),
+            }),
+        )
+    }
+
+    fn send_i(&mut self, send_sequence: u8, info: Vec<u8>) -> Result<(), ConnectionError> {
+        self.send(
+            CommandResponse::Command,
+            FrameContent::Information(Information {
+                pid: ProtocolIdentifier::None,
+                info,
+                receive_sequence: self.vr,
+                send_sequence,
+                poll: false,
+            }),
+        )
+    }
+
+    fn send_supervisory(
+        &mut self,
+        kind: Supervisory,
+        final_bit: bool,
+    ) -> Result<(), ConnectionError> {
+        let content = match kind {
+            Supervisory::ReceiveReady => FrameContent::ReceiveReady(ReceiveReady {
+                receive_sequence: self.vr,
+                poll_or_final: final_bit,
+                trailing: Vec::new(),
+            }),
+            Supervisory::Reject => FrameContent::Reject(Reject {
+                receive_sequence: self.vr,
+                poll_or_final: final_bit,
+                trailing: Vec::new(),
+            }),
+        };
+        self.send(CommandResponse::Response, content)
+    }
+
+    fn send(
+        &mut self,
+        command_or_response: CommandResponse,
+        content: FrameContent,
+    ) -> Result<(), ConnectionError> {
+        let mut frame = Ax25Frame::new(self.local.clone(), self.remote.clone(), content);
+        frame.command_or_response = Some(command_or_response);
+        match self.tnc.send_frame(&frame) {
+            Ok(()) => Ok(()),
+            Err(e) => self.fail(ConnectionError::Tnc {
+                source: Arc::new(e),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kiss::{kiss_decode, kiss_encode};
+    use crate::test_util::{local_listener, tcp_kiss_address};
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    /// Minimal scripted remote station on the far side of a TCP KISS link
+    struct Remote {
+        stream: TcpStream,
+        buffer: Vec<u8>,
+    }
+
+    impl Remote {
+        fn receive(&mut self) -> Ax25Frame {
+            loop {
+                if let Some(frame) = kiss_decode(&mut self.buffer) {
+                    return Ax25Frame::from_bytes(&frame).unwrap();
+                }
+                let mut buf = [0u8; 1024];
+                let n = self.stream.read(&mut buf).unwrap();
+                self.buffer.extend(&buf[..n]);
+            }
+        }
+
+        fn send(&mut self, command_or_response: CommandResponse, content: FrameContent) {
+            let mut frame = Ax25Frame::new(
+                "VK7XT-2".parse().unwrap(),
+                "VK7NTK-1".parse().unwrap(),
+                content,
+            );
+            frame.command_or_response = Some(command_or_response);
+            self.stream
+                .write_all(&kiss_encode(&frame.to_bytes()))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn connect_exchange_disconnect() {
+        let listener = local_listener();
+        let addr = tcp_kiss_address(&listener);
+        let remote = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut remote = Remote {
+                stream,
+                buffer: Vec::new(),
+            };
+            assert!(matches!(
+                remote.receive().content,
+                FrameContent::SetAsynchronousBalancedMode(_)
+            ));
+            remote.send(
+                CommandResponse::Response,
+                FrameContent::UnnumberedAcknowledge(UnnumberedAcknowledge {
+                    final_bit: true,
+                    trailing: vec![],
+                }),
+            );
+            match remote.receive().content {
+                FrameContent::Information(i) => {
+                    assert_eq!(i.send_sequence, 0);
+                    assert_eq!(i.info, b"hello");
+                }
+                c => panic!("expected I frame, got {:?}", c),
+            }
+            remote.send(
+                CommandResponse::Command,
+                FrameContent::Information(Information {
+                    pid: ProtocolIdentifier::None,
+                    info: b"hi".to_vec(),
+                    receive_sequence: 1,
+                    send_sequence: 0,
+                    poll: false,
+                }),
+            );
+            match remote.receive().content {
+                FrameContent::ReceiveReady(rr) => assert_eq!(rr.receive_sequence, 1),
+                c => panic!("expected RR, got {:?}", c),
+            }
+            assert!(matches!(
+                remote.receive().content,
+                FrameContent::Disconnect(_)
+            ));
+            remote.send(
+                CommandResponse::Response,
+                FrameContent::UnnumberedAcknowledge(UnnumberedAcknowledge {
+                    final_bit: true,
+                    trailing: vec![],
+                }),
+            );
+        });
+
+        let tnc = Tnc::open(&addr).unwrap();
+        let conn = Connection::connect(
+            &tnc,
+            "VK7NTK-1".parse().unwrap(),
+            "VK7XT-2".parse().unwrap(),
+        )
+        .unwrap();
+        conn.write(b"hello").unwrap();
+        assert_eq!(conn.read().unwrap(), b"hi");
+        conn.disconnect().unwrap();
+        remote.join().unwrap();
+    }
+
+    #[test]
+    fn idle_link_is_polled() {
+        let listener = local_listener();
+        let addr = tcp_kiss_address(&listener);
+        let remote = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut remote = Remote {
+                stream,
+                buffer: Vec::new(),
+            };
+            remote.receive();
+            remote.send(
+                CommandResponse::Response,
+                FrameContent::UnnumberedAcknowledge(UnnumberedAcknowledge {
+                    final_bit: true,
+                    trailing: vec![],
+                }),
+            );
+            let mut polls = 0;
+            loop {
+                let frame = remote.receive();
+                match frame.content {
+                    FrameContent::ReceiveReady(rr) => {
+                        assert!(rr.poll_or_final);
+                        assert_eq!(frame.command_or_response, Some(CommandResponse::Command));
+                        polls += 1;
+                    }
+                    FrameContent::Disconnect(_) => break,
+                    c => panic!("expected RR poll, got {:?}", c),
+                }
+                remote.send(
+                    CommandResponse::Response,
+                    FrameContent::ReceiveReady(ReceiveReady {
+                        receive_sequence: 0,
+                        poll_or_final: true,
+                        trailing: vec![],
+                    }),
+                );
+            }
+            // Each poll was answered, so the link stayed up until asked to disconnect
+            assert!(polls >= 2);
+            remote.send(
+                CommandResponse::Response,
+                FrameContent::UnnumberedAcknowledge(UnnumberedAcknowledge {
+                    final_bit: true,
+                    trailing: vec![],
+                }),
+            );
+        });
+
+        let tnc = Tnc::open(&addr).unwrap();
+        let config = LinkConfig {
+            t3: Some(Duration::from_millis(50)),
+            ..LinkConfig::default()
+        };
+        let conn = Connection::connect_with_config(
+            &tnc,
+            "VK7NTK-1".parse().unwrap(),
+            "VK7XT-2".parse().unwrap(),
+            config,
+        )
+        .unwrap();
+        // Long enough for several polls to be answered
+        thread::sleep(Duration::from_millis(500));
+        conn.disconnect().unwrap();
+        remote.join().unwrap();
+    }
+
+    #[test]
+    fn invalid_window_size_rejected() {
+        let (tnc, _other) = Tnc::loopback_pair();
+        let config = LinkConfig {
+            window_size: 8,
+            ..LinkConfig::default()
+        };
+        let result = Connection::connect_with_config(
+            &tnc,
+            "VK7NTK-1".parse().unwrap(),
+            "VK7XT-2".parse().unwrap(),
+            config,
+        );
+        assert!(matches!(result, Err(ConnectionError::InvalidConfig { .. })));
+    }
+}
@@ -0,0 +1,242 @@
+use crate::tnc::FilePacing;
+use ax25::pcap::{PcapError, PcapReader, PcapWriter};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+enum Source {
+    Pcap(PcapReader<BufReader<File>>),
+    /// Each frame is preceded by its length as a 16-bit big-endian integer
+    LengthPrefixed(BufReader<File>),
+}
+
+impl Source {
+    /// The next frame and its timestamp if known, or `None` at the end of the file.
+    fn next_frame(&mut self) -> io::Result<Option<(Option<Duration>, Vec<u8>)>> {
+        match self {
+            Source::Pcap(reader) => match reader.next_record() {
+                Some(Ok(record)) => Ok(Some((Some(record.timestamp), record.bytes))),
+                Some(Err(PcapError::Io { source })) => Err(source),
+                Some(Err(e)) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+                None => Ok(None),
+            },
+            Source::LengthPrefixed(reader) => {
+                let mut len = [0u8; 2];
+                match reader.read(&mut len[..1])? {
+                    0 => return Ok(None),
+                    _ => reader.read_exact(&mut len[1..])?,
+                }
+                let mut frame = vec![0u8; u16::from_be_bytes(len) as usize];
+                reader.read_exact(&mut frame)?;
+                Ok(Some((None, frame)))
+            }
+        }
+    }
+}
+
+struct ReplayState {
+    source: Source,
+    /// A frame that has been read but whose time has not yet come
+    pending: Option<(Option<Duration>, Vec<u8>)>,
+    /// Capture timestamp of the first frame and the moment it was delivered
+    start: Option<(Duration, Instant)>,
+}
+
+/// Replays frames from a capture file and records transmitted frames to another.
+pub(crate) struct FileInterface {
+    state: Mutex<ReplayState>,
+    output: Option<Mutex<PcapWriter<BufWriter<File>>>>,
+    pacing: FilePacing,
+    is_shutdown: AtomicBool,
+}
+
+impl FileInterface {
+    /// Open `input` for replay, detecting whether it is a pcap or length-prefixed file.
+    /// Sent frames are appended to `output` as a pcap file, or discarded if it is `None`.
+    pub(crate) fn new(input: &Path, output: Option<&Path>, pacing: FilePacing) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        let is_pcap = File::open(input)?.read_exact(&mut magic).is_ok()
+            && matches!(
+                u32::from_le_bytes(magic),
+                0xa1b2_c3d4 | 0xd4c3_b2a1 | 0xa1b2_3c4d | 0x4d3c_b2a1
+            );
+        let file = BufReader::new(File::open(input)?);
+        let source = if is_pcap {
+            Source::Pcap(PcapReader::new(file).map_err(|e| match e {
+                PcapError::Io { source } => source,
+                e => io::Error::new(io::ErrorKind::InvalidData, e),
+            })?)
+        } else {
+            Source::LengthPrefixed(file)
+        };
+        let output = match output {
+            Some(path) => Some(Mutex::new(PcapWriter::append(path)?)),
+            None => None,
+        };
+        Ok(Self {
+            state: Mutex::new(ReplayState {
+                source,
+                pending: None,
+                start: None,
+            }),
+            output,
+            pacing,
+            is_shutdown: AtomicBool::new(false),
+        })
+    }
+
+    /// Wait up to `timeout` for the next frame from the file to become due.
+    /// Once the file is exhausted this always waits for the full timeout.
+    pub(crate) fn receive_frame_timeout(&self, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "interface has been shut down",
+            ));
+        }
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state.lock().unwrap();
+        if state.pending.is_none() {
+            state.pending = state.source.next_frame()?;
+        }
+        let timestamp = match &state.pending {
+            Some((timestamp, _)) => *timestamp,
+            None => {
+                drop(state);
+                thread::sleep(timeout);
+                return Ok(None);
+            }
+        };
+        if let (FilePacing::Realtime, Some(timestamp)) = (self.pacing, timestamp) {
+            let (first, started) = *state
+                .start
+                .get_or_insert_with(|| (timestamp, Instant::now()));
+            let due = started + timestamp.saturating_sub(first);
+            if due > deadline {
+                drop(state);
+                thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                return Ok(None);
+            }
+            thread::sleep(due.saturating_duration_since(Instant::now()));
+        }
+        Ok(state.pending.take().map(|(_, frame)| frame))
+    }
+
+    /// Record a transmitted frame in the output file.
+    pub(crate) fn send_frame(&self, frame: &[u8]) -> io::Result<()> {
+        match &self.output {
+            Some(output) => {
+                let mut output = output.lock().unwrap();
+                output.write_bytes(frame)?;
+                output.flush()
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn shutdown(&self) {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+    }
+}
+
+/// A path in the temporary directory that is unique to this test process.
+#[cfg(test)]
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("ax25_tnc_{}_{}", name, std::process::id()))
+}
+
+#[test]
+fn test_length_prefixed() {
+    let path = temp_path("length_prefixed");
+    std::fs::write(&path, [0, 3, 1, 2, 3, 0, 1, 4]).unwrap();
+    let iface = FileInterface::new(&path, None, FilePacing::Burst).unwrap();
+    let timeout = Duration::from_millis(50);
+    assert_eq!(
+        iface.receive_frame_timeout(timeout).unwrap(),
+        Some(vec![1, 2, 3])
+    );
+    assert_eq!(iface.receive_frame_timeout(timeout).unwrap(), Some(vec![4]));
+    assert_eq!(iface.receive_frame_timeout(timeout).unwrap(), None);
+
+    // A file which ends part way through a length or a frame
+    for contents in [&[0, 1, 4, 0][..], &[0, 1, 4, 0, 3, 1, 2]] {
+        std::fs::write(&path, contents).unwrap();
+        let iface = FileInterface::new(&path, None, FilePacing::Burst).unwrap();
+        assert_eq!(iface.receive_frame_timeout(timeout).unwrap(), Some(vec![4]));
+        let e = iface.receive_frame_timeout(timeout).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+    }
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_realtime_pacing() {
+    use crate::test_util::test_frame;
+
+    let frame = test_frame(b"hello").to_bytes();
+    // Two frames captured 300 ms apart
+    let mut capture = Vec::new();
+    capture.extend(0xa1b2_c3d4u32.to_le_bytes());
+    capture.extend([2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    capture.extend(65535u32.to_le_bytes());
+    capture.extend(3u32.to_le_bytes());
+    for micros in [0u32, 300_000] {
+        capture.extend(0u32.to_le_bytes());
+        capture.extend(micros.to_le_bytes());
+        capture.extend((frame.len() as u32).to_le_bytes());
+        capture.extend((frame.len() as u32).to_le_bytes());
+        capture.extend(&frame);
+    }
+    let path = temp_path("realtime.pcap");
+    std::fs::write(&path, &capture).unwrap();
+
+    let iface = FileInterface::new(&path, None, FilePacing::Realtime).unwrap();
+    let started = Instant::now();
+    let timeout = Duration::from_secs(5);
+    assert_eq!(
+        iface.receive_frame_timeout(timeout).unwrap(),
+        Some(frame.clone())
+    );
+    // Not due yet
+    assert_eq!(
+        iface
+            .receive_frame_timeout(Duration::from_millis(50))
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        iface.receive_frame_timeout(timeout).unwrap(),
+        Some(frame.clone())
+    );
+    assert!(started.elapsed() >= Duration::from_millis(300));
+
+    let iface = FileInterface::new(&path, None, FilePacing::Burst).unwrap();
+    let started = Instant::now();
+    for _ in 0..2 {
+        assert_eq!(
+            iface.receive_frame_timeout(timeout).unwrap(),
+            Some(frame.clone())
+        );
+    }
+    assert!(started.elapsed() < Duration::from_millis(300));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_output_must_be_pcap() {
+    let input = temp_path("input");
+    let output = temp_path("output");
+    std::fs::write(&input, [0, 1, 4]).unwrap();
+    std::fs::write(&output, [0, 1, 4]).unwrap();
+    let e = FileInterface::new(&input, Some(&output), FilePacing::Burst)
+        .err()
+        .unwrap();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(std::fs::read(&output).unwrap(), [0, 1, 4]);
+    let _ = std::fs::remove_file(&input);
+    let _ = std::fs::remove_file(&output);
+}
@@ -0,0 +1,138 @@
+use crate::tnc::{Ax25FrameResult, Tnc, TncAddress, TncError};
+use ax25::frame::Ax25Frame;
+use std::io;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+
+/// Several TNCs treated as one channel, such as KISS servers for different radios or
+/// sites. Frames received by any member are merged, and frames are sent on a chosen
+/// member or all of them.
+///
+/// Members are identified by their position in the group, which is the order their
+/// addresses were given to `open`.
+#[derive(Clone)]
+pub struct TncGroup {
+    members: Vec<Tnc>,
+}
+
+/// A frame received by a member of a `TncGroup`.
+#[derive(Debug, Clone)]
+pub struct GroupFrame {
+    /// Index of the member that received the frame
+    pub member: usize,
+    /// The frame, or the error the member reported instead
+    pub frame: Ax25FrameResult,
+}
+
+impl TncGroup {
+    /// Open a TNC for each address. Fails if any of them cannot be opened.
+    pub fn open(addresses: &[TncAddress]) -> Result<TncGroup, TncError> {
+        let members = addresses
+            .iter()
+            .map(Tnc::open)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(TncGroup { members })
+    }
+
+    /// Group TNCs which have already been opened.
+    pub fn from_tncs(members: Vec<Tnc>) -> TncGroup {
+        TncGroup { members }
+    }
+
+    /// The member at `index`, for using features that the group does not provide.
+    pub fn member(&self, index: usize) -> Option<&Tnc> {
+        self.members.get(index)
+    }
+
+    /// The number of TNCs in the group.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Whether the group has no TNCs.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Transmit a frame on the member at `index`.
+    pub fn send_frame(&self, index: usize, frame: &Ax25Frame) -> Result<(), TncError> {
+        match self.members.get(index) {
+            Some(tnc) => tnc.send_frame(frame),
+            None => Err(TncError::SendFrame {
+                source: io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("group has no member {}", index),
+                ),
+            }),
+        }
+    }
+
+    /// Transmit a frame on every member. All members are tried even if one fails, and
+    /// the first error is returned.
+    pub fn send_frame_all(&self, frame: &Ax25Frame) -> Result<(), TncError> {
+        let mut result = Ok(());
+        for tnc in &self.members {
+            let sent = tnc.send_frame(frame);
+            if result.is_ok() {
+                result = sent;
+            }
+        }
+        result
+    }
+
+    /// Create a new `Receiver<GroupFrame>`
+    /// This will receive a copy of all frames received by every member, tagged with the
+    /// member that received them. Each member's frames stop when it is closed.
+    ///
+    /// This uses a thread per member.
+    pub fn incoming(&self) -> Receiver<GroupFrame> {
+        let (sender, receiver) = channel();
+        for (member, tnc) in self.members.iter().enumerate() {
+            let incoming = tnc.incoming();
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for frame in incoming.iter() {
+                    if sender.send(GroupFrame { member, frame }).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        receiver
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::test_frame;
+    use std::time::Duration;
+
+    #[test]
+    fn group_merges_and_routes() {
+        let (a1, b1) = Tnc::loopback_pair();
+        let (a2, b2) = Tnc::loopback_pair();
+        let group = TncGroup::from_tncs(vec![a1, a2]);
+        let incoming = group.incoming();
+        let incoming_b1 = b1.incoming();
+        let incoming_b2 = b2.incoming();
+        let frame = test_frame(b"hello");
+        let timeout = Duration::from_secs(5);
+
+        b2.send_frame(&frame).unwrap();
+        let received = incoming.recv_timeout(timeout).unwrap();
+        assert_eq!(received.member, 1);
+        assert_eq!(received.frame.unwrap(), frame);
+        b1.send_frame(&frame).unwrap();
+        assert_eq!(incoming.recv_timeout(timeout).unwrap().member, 0);
+
+        group.send_frame(1, &frame).unwrap();
+        assert_eq!(incoming_b2.recv_timeout(timeout).unwrap().unwrap(), frame);
+        assert!(incoming_b1.try_recv().is_err());
+        assert!(group.send_frame(2, &frame).is_err());
+
+        group.send_frame_all(&frame).unwrap();
+        assert_eq!(incoming_b1.recv_timeout(timeout).unwrap().unwrap(), frame);
+        assert_eq!(incoming_b2.recv_timeout(timeout).unwrap().unwrap(), frame);
+    }
+}
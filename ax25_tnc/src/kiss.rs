@@ -1,70 +1,773 @@
+use std::collections::VecDeque;
 use std::io;
 use std::io::prelude::*;
+use std::net::IpAddr;
 use std::net::Shutdown;
+use std::net::SocketAddr;
 use std::net::TcpStream;
 use std::net::ToSocketAddrs;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Mutex;
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 const FEND: u8 = 0xC0;
 const FESC: u8 = 0xDB;
 const TFEND: u8 = 0xDC;
 const TFESC: u8 = 0xDD;
 
-pub(crate) struct TcpKissInterface {
+// KISS commands, which occupy the low nybble of the command byte
+/// An AX.25 frame to transmit or that was received
+pub const CMD_DATA: u8 = 0x00;
+/// Delay between keying the transmitter and sending data, in units of 10 ms
+pub const CMD_TX_DELAY: u8 = 0x01;
+/// CSMA persistence parameter
+pub const CMD_PERSISTENCE: u8 = 0x02;
+/// CSMA slot interval, in units of 10 ms
+pub const CMD_SLOT_TIME: u8 = 0x03;
+/// Time to keep the transmitter keyed after the last frame, in units of 10 ms
+pub const CMD_TX_TAIL: u8 = 0x04;
+/// Full duplex operation on or off
+pub const CMD_FULL_DUPLEX: u8 = 0x05;
+/// A hardware-specific command
+pub const CMD_SET_HARDWARE: u8 = 0x06;
+/// Leave KISS mode. Unlike the other commands this is the whole command byte, so it
+/// applies to every port.
+pub const CMD_RETURN: u8 = 0xFF;
+
+/// Default limit on buffered bytes that have not yet formed a complete frame.
+pub const DEFAULT_MAX_BUFFER: usize = 64 * 1024;
+
+/// Largest UDP KISS datagram accepted. This holds several escaped AX.25 frames.
+const MAX_DATAGRAM: usize = 4096;
+
+/// Default time allowed for each attempt to connect to a TCP KISS TNC.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// High bit of the command byte, set on SMACK frames which end with a CRC
+const SMACK_FLAG: u8 = 0x80;
+
+/// How many control frames are kept for `take_control_frame` before the oldest is lost
+const MAX_QUEUED_CONTROL_FRAMES: usize = 64;
+
+/// A KISS frame received from the TNC carrying a command other than data, such as
+/// the response to a SetHardware query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KissControlFrame {
+    /// The TNC port the frame relates to
+    pub port: u8,
+    /// The KISS command, from the low nybble of the command byte
+    pub command: u8,
+    /// The contents of the frame after the command byte
+    pub data: Vec<u8>,
+}
+
+/// A complete KISS frame received from the TNC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KissFrame {
+    /// An AX.25 frame received on `port`
+    Data { port: u8, payload: Vec<u8> },
+    /// A frame carrying any other KISS command
+    Control(KissControlFrame),
+}
+
+impl KissFrame {
+    /// The TNC port the frame was received on or relates to.
+    pub fn port(&self) -> u8 {
+        match self {
+            KissFrame::Data { port, .. } => *port,
+            KissFrame::Control(control) => control.port,
+        }
+    }
+}
+
+/// How a `TcpKissInterface` should re-establish a connection that has been lost.
+///
+/// The first attempt is made after `initial_backoff`. Each failed attempt doubles the
+/// delay before the next one, up to `max_backoff`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Delay before the first reconnection attempt
+    pub initial_backoff: Duration,
+    /// Upper limit on the delay between reconnection attempts
+    pub max_backoff: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+/// TCP keepalive settings, so that a TNC which disappears without closing the
+/// connection is noticed.
+///
+/// Once nothing has been received for `idle`, a probe is sent every `interval`. If
+/// `retries` probes in a row go unanswered the connection is treated as lost.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeepalivePolicy {
+    /// How long the connection may be silent before probing starts
+    pub idle: Duration,
+    /// Delay between unanswered probes
+    pub interval: Duration,
+    /// Unanswered probes after which the connection is lost
+    pub retries: u32,
+}
+
+impl Default for KeepalivePolicy {
+    /// Notices a vanished TNC about 11 seconds after it was last heard from.
+    fn default() -> Self {
+        Self {
+            idle: Duration::from_secs(5),
+            interval: Duration::from_secs(2),
+            retries: 3,
+        }
+    }
+}
+
+impl KeepalivePolicy {
+    /// Enable keepalive on a connected socket with these settings.
+    pub(crate) fn apply<S>(&self, stream: &S) -> io::Result<()>
+    where
+        for<'s> socket2::SockRef<'s>: From<&'s S>,
+    {
+        let keepalive = socket2::TcpKeepalive::new()
+            .with_time(self.idle)
+            .with_interval(self.interval)
+            .with_retries(self.retries);
+        socket2::SockRef::from(stream).set_tcp_keepalive(&keepalive)
+    }
+}
+
+struct ReconnectState {
+    next_attempt: Option<Instant>,
+    backoff: Duration,
+}
+
+/// A push-style KISS decoder for bytes arriving in arbitrary chunks.
+///
+/// Bytes from any transport are given to `push` as they arrive and complete data frames
+/// are collected with `next_frame`. Partial frames are kept until the rest arrives.
+/// Frames carrying other KISS commands are set aside for `take_control_frame`.
+#[derive(Debug)]
+pub struct FrameDecoder {
+    /// Bytes received and not yet discarded
+    buffer: Vec<u8>,
+    /// Where the bytes of the frame being scanned begin in `buffer`. Anything before
+    /// this has been consumed and is removed in bulk by `compact`.
+    start: usize,
+    /// How much of `buffer` has been scanned
+    scanned: usize,
+    state: Scan,
+    /// Unescaped contents of the frame being scanned
+    frame: Vec<u8>,
+    control: VecDeque<KissControlFrame>,
+    smack: bool,
+    max_buffer: usize,
+    discarded: Option<usize>,
+}
+
+impl FrameDecoder {
+    /// Create a decoder for plain KISS framing.
+    pub fn new() -> Self {
+        FrameDecoder {
+            buffer: Vec::new(),
+            start: 0,
+            scanned: 0,
+            state: Scan::LookingForStartMarker,
+            frame: Vec::new(),
+            control: VecDeque::new(),
+            smack: false,
+            max_buffer: DEFAULT_MAX_BUFFER,
+            discarded: None,
+        }
+    }
+
+    /// Verify and remove the CRC from frames with the SMACK flag set, dropping those
+    /// that do not match.
+    pub fn with_smack(mut self) -> Self {
+        self.smack = true;
+        self
+    }
+
+    /// Limit how many bytes may be buffered without forming a complete frame. The
+    /// default is `DEFAULT_MAX_BUFFER`.
+    pub fn with_max_buffer(mut self, max_buffer: usize) -> Self {
+        self.max_buffer = max_buffer;
+        self
+    }
+
+    /// Add bytes received from the TNC.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.compact();
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Take the next complete data frame, without its KISS command byte.
+    ///
+    /// Returns `None` once no complete frame remains. If the incomplete data left over
+    /// is larger than the limit it is discarded, which `take_discarded` reports.
+    pub fn next_frame(&mut self) -> Option<Vec<u8>> {
+        while let Some(frame) = self.scan_frame() {
+            match frame {
+                KissFrame::Data { payload, .. } => return Some(payload),
+                KissFrame::Control(control) => {
+                    if self.control.len() >= MAX_QUEUED_CONTROL_FRAMES {
+                        self.control.pop_front();
+                    }
+                    self.control.push_back(control);
+                }
+            }
+        }
+        self.finish_scan();
+        None
+    }
+
+    /// Take the next complete data frame like `next_frame`, replacing the contents of
+    /// `buf` with it rather than allocating. Returns `false` once no complete frame
+    /// remains.
+    pub fn next_frame_into(&mut self, buf: &mut Vec<u8>) -> bool {
+        while self.scan_raw() {
+            if self.frame[0] & 0x0f == CMD_DATA {
+                if let Some(end) = verified_len(&self.frame, self.smack) {
+                    buf.clear();
+                    buf.extend_from_slice(&self.frame[1..end]);
+                    self.frame.clear();
+                    return true;
+                }
+                self.frame.clear();
+            } else if let Some(KissFrame::Control(control)) =
+                check_frame(std::mem::take(&mut self.frame), self.smack)
+            {
+                if self.control.len() >= MAX_QUEUED_CONTROL_FRAMES {
+                    self.control.pop_front();
+                }
+                self.control.push_back(control);
+            }
+        }
+        self.finish_scan();
+        false
+    }
+
+    /// Take the next complete frame of any kind, in the order they were received.
+    ///
+    /// Control frames set aside by earlier calls to `next_frame` are returned first.
+    /// Oversized incomplete data is handled as for `next_frame`.
+    pub fn next_kiss_frame(&mut self) -> Option<KissFrame> {
+        if let Some(control) = self.control.pop_front() {
+            return Some(KissFrame::Control(control));
+        }
+        if let Some(frame) = self.scan_frame() {
+            return Some(frame);
+        }
+        self.finish_scan();
+        None
+    }
+
+    /// Called once the buffer holds no complete frame.
+    fn finish_scan(&mut self) {
+        self.compact();
+        if let Some(discarded) = resync_buffer(&mut self.buffer, self.max_buffer) {
+            *self.discarded.get_or_insert(0) += discarded;
+            // Whatever is left is scanned again from the start
+            self.restart_scan();
+        }
+    }
+
+    /// Continue scanning where the last call stopped, so that each byte is only looked
+    /// at once however it was split across calls to `push`.
+    fn scan_frame(&mut self) -> Option<KissFrame> {
+        while self.scan_raw() {
+            let frame = std::mem::take(&mut self.frame);
+            if let Some(frame) = check_frame(frame, self.smack) {
+                return Some(frame);
+            }
+        }
+        None
+    }
+
+    /// Scan until `frame` holds a complete frame, including its command byte. Returns
+    /// `false` if the buffer runs out first.
+    fn scan_raw(&mut self) -> bool {
+        while self.scanned < self.buffer.len() {
+            let c = self.buffer[self.scanned];
+            self.scanned += 1;
+            if scan_byte(&mut self.state, &mut self.frame, c) {
+                // The final FEND can also start the next frame
+                self.start = self.scanned;
+                self.state = Scan::Data;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Remove consumed bytes from the front of the buffer. Doing this once per batch of
+    /// frames rather than once per frame keeps decoding linear in the bytes received.
+    fn compact(&mut self) {
+        if self.start > 0 {
+            self.buffer.drain(..self.start);
+            self.scanned -= self.start;
+            self.start = 0;
+        }
+    }
+
+    fn restart_scan(&mut self) {
+        self.start = 0;
+        self.scanned = 0;
+        self.state = Scan::LookingForStartMarker;
+        self.frame.clear();
+    }
+
+    /// Take the oldest control frame found by `next_frame`, if there is one.
+    pub fn take_control_frame(&mut self) -> Option<KissControlFrame> {
+        self.control.pop_front()
+    }
+
+    /// How many bytes have been discarded to resynchronise since this was last called,
+    /// or `None` if there were none.
+    pub fn take_discarded(&mut self) -> Option<usize> {
+        self.discarded.take()
+    }
+
+    /// Forget any partial frame, such as when the connection to the TNC is replaced.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.restart_scan();
+    }
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Connect to the first of `addrs` which accepts, waiting at most `timeout` for each.
+fn connect(addrs: &[SocketAddr], timeout: Option<Duration>) -> io::Result<TcpStream> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return TcpStream::connect(addrs),
+    };
+    let mut last_err = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "could not resolve to any addresses",
+        )
+    }))
+}
+
+/// A connection to a KISS TNC over TCP, such as the KISS port provided by Dire Wolf.
+pub struct TcpKissInterface {
     // Interior mutability is desirable so that we can clone the TNC and have
     // different threads sending and receiving concurrently.
     tx_stream: Mutex<TcpStream>,
     rx_stream: Mutex<TcpStream>,
-    buffer: Mutex<Vec<u8>>,
+    decoder: Mutex<FrameDecoder>,
     is_shutdown: AtomicBool,
+    addrs: Vec<SocketAddr>,
+    reconnect: Option<ReconnectPolicy>,
+    reconnect_state: Mutex<ReconnectState>,
+    is_connected: AtomicBool,
+    reconnects: AtomicU64,
+    smack: bool,
+    connect_timeout: Option<Duration>,
+    keepalive: Option<KeepalivePolicy>,
 }
 
 impl TcpKissInterface {
-    pub(crate) fn new<A: ToSocketAddrs>(addr: A) -> io::Result<TcpKissInterface> {
-        let tx_stream = TcpStream::connect(addr)?;
+    /// Connect to a KISS TNC at the given address.
+    pub fn new<A: ToSocketAddrs>(addr: A) -> io::Result<TcpKissInterface> {
+        Self::open(addr, None)
+    }
+
+    /// Connect to a KISS TNC at the given address, giving up on each of its resolved
+    /// addresses after `timeout`. Reconnection attempts use the same timeout.
+    ///
+    /// If the TNC cannot be reached in time the error has kind
+    /// `io::ErrorKind::TimedOut`. The timeout must not be zero.
+    pub fn new_with_timeout<A: ToSocketAddrs>(
+        addr: A,
+        timeout: Duration,
+    ) -> io::Result<TcpKissInterface> {
+        Self::open(addr, Some(timeout))
+    }
+
+    fn open<A: ToSocketAddrs>(
+        addr: A,
+        connect_timeout: Option<Duration>,
+    ) -> io::Result<TcpKissInterface> {
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        let tx_stream = connect(&addrs, connect_timeout)?;
         let rx_stream = tx_stream.try_clone()?;
         Ok(TcpKissInterface {
             tx_stream: Mutex::new(tx_stream),
             rx_stream: Mutex::new(rx_stream),
-            buffer: Mutex::new(Vec::new()),
+            decoder: Mutex::new(FrameDecoder::new()),
             is_shutdown: AtomicBool::new(false),
+            addrs,
+            reconnect: None,
+            reconnect_state: Mutex::new(ReconnectState {
+                next_attempt: None,
+                backoff: Duration::ZERO,
+            }),
+            is_connected: AtomicBool::new(true),
+            reconnects: AtomicU64::new(0),
+            smack: false,
+            connect_timeout,
+            keepalive: None,
         })
     }
 
-    pub(crate) fn receive_frame(&self) -> io::Result<Vec<u8>> {
+    /// Automatically re-dial the TNC if the connection is lost.
+    ///
+    /// Reconnection happens as part of receiving, so something must be calling
+    /// `receive_frame` or `receive_frame_timeout`. While the connection is down
+    /// `send_frame` fails with `io::ErrorKind::NotConnected`.
+    pub fn with_reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect_state.get_mut().unwrap().backoff = policy.initial_backoff;
+        self.reconnect = Some(policy);
+        self
+    }
+
+    /// Send TCP keepalive probes so that a TNC which vanishes without closing the
+    /// connection is noticed, rather than waiting for a send to fail.
+    ///
+    /// A receive in progress when the probes go unanswered fails, or with
+    /// `with_reconnect` starts reconnecting, and `is_connected` becomes `false`. The
+    /// same settings are applied to each new connection.
+    pub fn with_keepalive(mut self, policy: KeepalivePolicy) -> io::Result<Self> {
+        policy.apply(&*self.tx_stream.get_mut().unwrap())?;
+        self.keepalive = Some(policy);
+        Ok(self)
+    }
+
+    /// Use SMACK framing, which adds a CRC to each data frame.
+    ///
+    /// Outgoing data frames are sent with a checksum. Incoming frames that carry a
+    /// checksum are verified and dropped if it does not match.
+    pub fn with_smack(mut self) -> Self {
+        self.smack = true;
+        let decoder = self.decoder.get_mut().unwrap();
+        *decoder = std::mem::take(decoder).with_smack();
+        self
+    }
+
+    /// Limit how many bytes may be buffered without forming a complete frame.
+    ///
+    /// If the TNC sends more than this without a frame boundary, the incomplete data
+    /// is discarded and the receive call fails with `io::ErrorKind::InvalidData`.
+    /// Receiving can continue afterwards. The default is `DEFAULT_MAX_BUFFER`.
+    pub fn with_max_buffer(mut self, max_buffer: usize) -> Self {
+        let decoder = self.decoder.get_mut().unwrap();
+        *decoder = std::mem::take(decoder).with_max_buffer(max_buffer);
+        self
+    }
+
+    /// Block until a complete data frame has been received from the TNC.
+    pub fn receive_frame(&self) -> io::Result<Vec<u8>> {
+        let mut frame = Vec::new();
+        self.receive_frame_into(&mut frame)?;
+        Ok(frame)
+    }
+
+    /// Block until a complete data frame has been received from the TNC, replacing the
+    /// contents of `buf` with it. Reusing one buffer avoids allocating for each frame.
+    pub fn receive_frame_into(&self, buf: &mut Vec<u8>) -> io::Result<()> {
         loop {
-            {
-                let mut buffer = self.buffer.lock().unwrap();
-                if let Some(frame) = make_frame_from_buffer(&mut buffer) {
-                    return Ok(frame);
+            if self.take_buffered_frame(buf)? {
+                return Ok(());
+            }
+            self.read_into_buffer(None)?;
+        }
+    }
+
+    /// Block until a complete frame of any kind has been received from the TNC.
+    ///
+    /// Unlike `receive_frame`, this returns control frames as they arrive and reports
+    /// which port each data frame was received on.
+    pub fn receive_kiss_frame(&self) -> io::Result<KissFrame> {
+        loop {
+            if let Some(frame) = take_decoded(&self.decoder, FrameDecoder::next_kiss_frame)? {
+                return Ok(frame);
+            }
+            self.read_into_buffer(None)?;
+        }
+    }
+
+    /// Wait up to `timeout` for a complete frame of any kind to be received from the TNC.
+    pub fn receive_kiss_frame_timeout(&self, timeout: Duration) -> io::Result<Option<KissFrame>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(frame) = take_decoded(&self.decoder, FrameDecoder::next_kiss_frame)? {
+                return Ok(Some(frame));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || !self.read_into_buffer(Some(remaining))? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Wait up to `timeout` for a complete data frame to be received from the TNC.
+    ///
+    /// Returns `Ok(None)` if no frame arrived in time. Any partial frame that has been
+    /// read so far is retained for the next call.
+    pub fn receive_frame_timeout(&self, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+        let mut frame = Vec::new();
+        Ok(self
+            .receive_frame_timeout_into(timeout, &mut frame)?
+            .then_some(frame))
+    }
+
+    /// Wait up to `timeout` for a complete data frame like `receive_frame_timeout`,
+    /// replacing the contents of `buf` with it. Returns `false` if no frame arrived in
+    /// time, in which case `buf` is unchanged.
+    pub fn receive_frame_timeout_into(
+        &self,
+        timeout: Duration,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<bool> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.take_buffered_frame(buf)? {
+                return Ok(true);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || !self.read_into_buffer(Some(remaining))? {
+                return Ok(false);
+            }
+        }
+    }
+
+    fn take_buffered_frame(&self, buf: &mut Vec<u8>) -> io::Result<bool> {
+        Ok(take_decoded(&self.decoder, |d| d.next_frame_into(buf).then_some(()))?.is_some())
+    }
+
+    /// Take the oldest control frame received from the TNC, if there is one.
+    ///
+    /// Control frames are collected while receiving data frames, so something must be
+    /// calling `receive_frame` or `receive_frame_timeout`.
+    pub fn take_control_frame(&self) -> Option<KissControlFrame> {
+        self.decoder.lock().unwrap().take_control_frame()
+    }
+
+    /// Perform a single read from the stream and append the result to the buffer.
+    /// Returns `false` if the read timed out.
+    fn read_into_buffer(&self, timeout: Option<Duration>) -> io::Result<bool> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "interface has been shut down",
+            ));
+        }
+        if let Some(policy) = &self.reconnect {
+            if !self.is_connected.load(Ordering::SeqCst) {
+                return self.reconnect(policy, timeout);
+            }
+        }
+        let mut buf = [0u8; 1024];
+        let n_bytes = {
+            let mut rx_stream = self.rx_stream.lock().unwrap();
+            rx_stream.set_read_timeout(timeout)?;
+            match rx_stream.read(&mut buf) {
+                // The remote end closed the connection
+                Ok(0) if self.reconnect.is_some() => {
+                    drop(rx_stream);
+                    self.connection_lost();
+                    return Ok(true);
+                }
+                // Without reconnection there is nothing more to read, ever
+                Ok(0) => {
+                    drop(rx_stream);
+                    self.connection_lost();
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "TNC closed the connection",
+                    ));
+                }
+                Ok(n) => n,
+                // A read timeout is WouldBlock on unix and TimedOut on windows. On unix
+                // TimedOut instead means that keepalive probes went unanswered.
+                Err(e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || (cfg!(windows) && e.kind() == io::ErrorKind::TimedOut) =>
+                {
+                    return Ok(false)
+                }
+                Err(_) if self.reconnect.is_some() => {
+                    drop(rx_stream);
+                    self.connection_lost();
+                    return Ok(true);
+                }
+                Err(e) => {
+                    drop(rx_stream);
+                    self.connection_lost();
+                    return Err(e);
                 }
             }
-            let mut buf = vec![0u8; 1024];
-            let n_bytes = {
-                let mut rx_stream = self.rx_stream.lock().unwrap();
-                rx_stream.read(&mut buf)?
-            };
-            {
-                let mut buffer = self.buffer.lock().unwrap();
-                buffer.extend(buf.iter().take(n_bytes));
+        };
+        self.decoder.lock().unwrap().push(&buf[..n_bytes]);
+        Ok(true)
+    }
+
+    fn connection_lost(&self) {
+        if self.is_connected.swap(false, Ordering::SeqCst) {
+            let tx_stream = self.tx_stream.lock().unwrap();
+            let _ = tx_stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// Wait for the next scheduled attempt, or until `timeout` expires, then try to
+    /// connect again. Returns `false` if the timeout expired first.
+    fn reconnect(&self, policy: &ReconnectPolicy, timeout: Option<Duration>) -> io::Result<bool> {
+        // Don't hold the lock while sleeping or connecting
+        let wait = {
+            let mut state = self.reconnect_state.lock().unwrap();
+            let backoff = state.backoff;
+            let next_attempt = *state
+                .next_attempt
+                .get_or_insert_with(|| Instant::now() + backoff);
+            next_attempt.saturating_duration_since(Instant::now())
+        };
+        match timeout {
+            Some(timeout) if timeout < wait => {
+                thread::sleep(timeout);
+                return Ok(false);
+            }
+            _ => thread::sleep(wait),
+        }
+        if self.is_shutdown.load(Ordering::SeqCst) || self.is_connected.load(Ordering::SeqCst) {
+            return Ok(true);
+        }
+
+        let connected = connect(&self.addrs, self.connect_timeout).and_then(|s| {
+            if let Some(keepalive) = &self.keepalive {
+                keepalive.apply(&s)?;
+            }
+            Ok((s.try_clone()?, s))
+        });
+        let mut state = self.reconnect_state.lock().unwrap();
+        match connected {
+            Ok((tx_stream, rx_stream)) => {
+                *self.tx_stream.lock().unwrap() = tx_stream;
+                *self.rx_stream.lock().unwrap() = rx_stream;
+                // Anything left over from the old connection can't be trusted
+                self.decoder.lock().unwrap().clear();
+                state.next_attempt = None;
+                state.backoff = policy.initial_backoff;
+                self.is_connected.store(true, Ordering::SeqCst);
+                self.reconnects.fetch_add(1, Ordering::SeqCst);
+            }
+            Err(_) => {
+                state.next_attempt = Some(Instant::now() + state.backoff);
+                state.backoff = (state.backoff * 2).min(policy.max_backoff);
             }
         }
+        Ok(true)
     }
 
-    pub(crate) fn send_frame(&self, frame: &[u8]) -> io::Result<()> {
-        let mut tx_stream = self.tx_stream.lock().unwrap();
-        // 0x00 is the KISS command byte, which is two nybbles
-        // port = 0
-        // command = 0 (all following bytes are a data frame to transmit)
-        tx_stream.write_all(&[FEND, 0x00])?;
-        tx_stream.write_all(frame)?;
-        tx_stream.write_all(&[FEND])?;
-        tx_stream.flush()?;
-        Ok(())
+    /// The address of the TNC at the other end of the current connection.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.tx_stream.lock().unwrap().peer_addr()
     }
 
-    pub(crate) fn shutdown(&self) {
+    /// Whether the connection to the TNC is believed to be working.
+    ///
+    /// This becomes `false` once receiving or sending finds that the connection has
+    /// failed or been closed by the TNC, and `true` again if it is re-established.
+    /// Enable `with_keepalive` to notice promptly when the TNC silently disappears.
+    pub fn is_connected(&self) -> bool {
+        self.is_connected.load(Ordering::SeqCst) && !self.is_shutdown.load(Ordering::SeqCst)
+    }
+
+    /// How many times the connection has been successfully re-established.
+    pub fn reconnect_count(&self) -> u64 {
+        self.reconnects.load(Ordering::SeqCst)
+    }
+
+    /// Send a data frame to the TNC for transmission.
+    pub fn send_frame(&self, frame: &[u8]) -> io::Result<()> {
+        if self.smack {
+            self.send_encoded(&smack_encode(frame))
+        } else {
+            self.send_encoded(&kiss_encode(frame))
+        }
+    }
+
+    /// Set the delay between keying the transmitter and sending data, in units of 10 ms.
+    pub fn set_tx_delay(&self, units: u8) -> io::Result<()> {
+        self.send_kiss_command(CMD_TX_DELAY, 0, &[units])
+    }
+
+    /// Set the persistence parameter `p` used for CSMA, where the chance of
+    /// transmitting in a given slot is `(p + 1) / 256`.
+    pub fn set_persistence(&self, p: u8) -> io::Result<()> {
+        self.send_kiss_command(CMD_PERSISTENCE, 0, &[p])
+    }
+
+    /// Set the CSMA slot interval, in units of 10 ms.
+    pub fn set_slot_time(&self, units: u8) -> io::Result<()> {
+        self.send_kiss_command(CMD_SLOT_TIME, 0, &[units])
+    }
+
+    /// Set how long to keep the transmitter keyed after the last frame, in units of 10 ms.
+    pub fn set_tx_tail(&self, units: u8) -> io::Result<()> {
+        self.send_kiss_command(CMD_TX_TAIL, 0, &[units])
+    }
+
+    /// Enable or disable full duplex operation, in which the TNC transmits without
+    /// waiting for a clear channel.
+    pub fn set_full_duplex(&self, full_duplex: bool) -> io::Result<()> {
+        self.send_kiss_command(CMD_FULL_DUPLEX, 0, &[full_duplex as u8])
+    }
+
+    /// Send a hardware-specific configuration command. Its meaning depends on the TNC.
+    pub fn set_hardware(&self, data: &[u8]) -> io::Result<()> {
+        self.send_kiss_command(CMD_SET_HARDWARE, 0, data)
+    }
+
+    /// Send a KISS command frame with arbitrary contents, such as `CMD_RETURN` to take
+    /// the TNC out of KISS mode. `port` must be from 0 to 15.
+    ///
+    /// The command and port are combined into the command byte and `data` is escaped.
+    /// Prefer the specific methods such as `set_tx_delay` where one exists.
+    pub fn send_kiss_command(&self, command: u8, port: u8, data: &[u8]) -> io::Result<()> {
+        self.send_encoded(&encode_command(command, check_port(port)?, data))
+    }
+
+    fn send_encoded(&self, encoded: &[u8]) -> io::Result<()> {
+        if !self.is_connected.load(Ordering::SeqCst) {
+            let msg = if self.reconnect.is_some() {
+                "connection to TNC lost, waiting to reconnect"
+            } else {
+                "connection to TNC lost"
+            };
+            return Err(io::Error::new(io::ErrorKind::NotConnected, msg));
+        }
+        let result = {
+            let mut tx_stream = self.tx_stream.lock().unwrap();
+            tx_stream.write_all(encoded).and_then(|_| tx_stream.flush())
+        };
+        if result.is_err() {
+            self.connection_lost();
+        }
+        result
+    }
+
+    /// Close the connection to the TNC. Any blocked receive will be interrupted.
+    pub fn shutdown(&self) {
         if !self.is_shutdown.load(Ordering::SeqCst) {
             self.is_shutdown.store(true, Ordering::SeqCst);
             let tx_stream = self.tx_stream.lock().unwrap();
@@ -79,63 +782,608 @@ impl Drop for TcpKissInterface {
     }
 }
 
-fn make_frame_from_buffer(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
-    let mut possible_frame = Vec::new();
+/// Whether two addresses have the same IP address, treating IPv4-mapped IPv6 addresses
+/// as the IPv4 address they contain.
+pub(crate) fn same_host(a: SocketAddr, b: SocketAddr) -> bool {
+    let canonical = |addr: SocketAddr| match addr.ip() {
+        IpAddr::V6(v6) => v6
+            .to_ipv4_mapped()
+            .map(IpAddr::V4)
+            .unwrap_or(IpAddr::V6(v6)),
+        ip => ip,
+    };
+    canonical(a) == canonical(b)
+}
 
-    enum Scan {
-        LookingForStartMarker,
-        Data,
-        Escaped,
+/// A KISS TNC reached over UDP, where each datagram carries a KISS frame.
+pub struct UdpKissInterface {
+    socket: UdpSocket,
+    remote: SocketAddr,
+    pending: Mutex<VecDeque<Vec<u8>>>,
+    control: Mutex<VecDeque<KissControlFrame>>,
+    is_shutdown: AtomicBool,
+    any_sender: bool,
+}
+
+impl UdpKissInterface {
+    /// Bind a UDP socket to `local` and exchange KISS frames with the TNC at `remote`.
+    ///
+    /// Frames are only accepted from the IP address of `remote`, but from any port,
+    /// since some TNCs reply from a different port to the one they listen on.
+    pub fn new<A: ToSocketAddrs, B: ToSocketAddrs>(
+        local: A,
+        remote: B,
+    ) -> io::Result<UdpKissInterface> {
+        let remote = remote.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "remote address did not resolve")
+        })?;
+        Ok(UdpKissInterface {
+            socket: UdpSocket::bind(local)?,
+            remote,
+            pending: Mutex::new(VecDeque::new()),
+            control: Mutex::new(VecDeque::new()),
+            is_shutdown: AtomicBool::new(false),
+            any_sender: false,
+        })
     }
-    let mut state = Scan::LookingForStartMarker;
-    let mut final_idx = 0;
 
-    // Check for possible frame read-only until we know we have a complete frame
-    // If we take one out, clear out buffer up to the final index
-    for (idx, &c) in buffer.iter().enumerate() {
-        match state {
-            Scan::LookingForStartMarker => {
-                if c == FEND {
-                    state = Scan::Data;
+    /// Accept frames from any sender rather than only the TNC's IP address.
+    ///
+    /// Any host which can reach the local port is then able to inject frames.
+    pub fn with_any_sender(mut self) -> Self {
+        self.any_sender = true;
+        self
+    }
+
+    /// The local address that the TNC should send frames to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// The address of the TNC that frames are sent to.
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote
+    }
+
+    /// Wait up to `timeout` for a data frame to be received from the TNC.
+    pub fn receive_frame_timeout(&self, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0u8; MAX_DATAGRAM];
+        loop {
+            if let Some(frame) = self.pending.lock().unwrap().pop_front() {
+                return Ok(Some(frame));
+            }
+            if self.is_shutdown.load(Ordering::SeqCst) {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "interface has been shut down",
+                ));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            self.socket.set_read_timeout(Some(remaining))?;
+            let n_bytes = match self.socket.recv_from(&mut buf) {
+                Ok((_, from)) if !self.any_sender && !same_host(from, self.remote) => continue,
+                Ok((n, _)) => n,
+                Err(e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut =>
+                {
+                    return Ok(None)
                 }
+                Err(e) => return Err(e),
+            };
+            self.queue_datagram(&buf[..n_bytes]);
+        }
+    }
+
+    fn queue_datagram(&self, datagram: &[u8]) {
+        let mut frames = Vec::new();
+        if datagram.contains(&FEND) {
+            let mut buffer = datagram.to_vec();
+            while let Some(frame) = take_frame(&mut buffer, false) {
+                frames.push(frame);
             }
-            Scan::Data => {
-                if c == FEND {
-                    if !possible_frame.is_empty() {
-                        // Successfully read a non-zero-length frame
-                        final_idx = idx;
-                        break;
-                    }
-                } else if c == FESC {
-                    state = Scan::Escaped;
-                } else {
-                    possible_frame.push(c);
+        } else if !datagram.is_empty() {
+            // Without FENDs the datagram is taken to be a single unescaped frame
+            frames.push(split_command(datagram.to_vec(), false));
+        }
+        let mut pending = self.pending.lock().unwrap();
+        for frame in frames {
+            match frame {
+                KissFrame::Data { payload, .. } => pending.push_back(payload),
+                KissFrame::Control(control) => queue_control(&self.control, control),
+            }
+        }
+    }
+
+    /// Take the oldest control frame received from the TNC, if there is one.
+    pub fn take_control_frame(&self) -> Option<KissControlFrame> {
+        self.control.lock().unwrap().pop_front()
+    }
+
+    /// Send a data frame to the TNC for transmission.
+    pub fn send_frame(&self, frame: &[u8]) -> io::Result<()> {
+        self.send_kiss_command(CMD_DATA, 0, frame)
+    }
+
+    /// Send a KISS command frame with arbitrary contents. See
+    /// `TcpKissInterface::send_kiss_command`.
+    pub fn send_kiss_command(&self, command: u8, port: u8, data: &[u8]) -> io::Result<()> {
+        self.socket
+            .send_to(
+                &encode_command(command, check_port(port)?, data),
+                self.remote,
+            )
+            .map(|_| ())
+    }
+
+    /// Stop receiving. Any blocked receive will return within its timeout.
+    pub fn shutdown(&self) {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+    }
+}
+
+/// How long to wait before reading again from a stream that had no data.
+const STREAM_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// KISS framing over caller-supplied streams, such as a TLS session, an SSH channel or
+/// a serial port.
+///
+/// Reads must not block indefinitely. The reader should either be non-blocking or have
+/// a read timeout, in which case reads failing with `WouldBlock` or `TimedOut` are
+/// treated as no data having arrived.
+pub(crate) struct StreamKissInterface {
+    reader: Mutex<Box<dyn Read + Send>>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    decoder: Mutex<FrameDecoder>,
+    is_shutdown: AtomicBool,
+}
+
+/// One stream used for both reading and writing by a `StreamKissInterface`.
+struct SharedStream<S>(Arc<Mutex<S>>);
+
+impl<S: Read> Read for SharedStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().read(buf)
+    }
+}
+
+impl<S: Write> Write for SharedStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
+impl StreamKissInterface {
+    /// Use a single stream for both directions. Sends wait for any read in progress.
+    pub(crate) fn new<S: Read + Write + Send + 'static>(stream: S) -> StreamKissInterface {
+        let stream = Arc::new(Mutex::new(stream));
+        Self::new_split(SharedStream(stream.clone()), SharedStream(stream))
+    }
+
+    /// Use separate handles for reading and writing so that neither waits for the other.
+    pub(crate) fn new_split<R, W>(reader: R, writer: W) -> StreamKissInterface
+    where
+        R: Read + Send + 'static,
+        W: Write + Send + 'static,
+    {
+        StreamKissInterface {
+            reader: Mutex::new(Box::new(reader)),
+            writer: Mutex::new(Box::new(writer)),
+            decoder: Mutex::new(FrameDecoder::new()),
+            is_shutdown: AtomicBool::new(false),
+        }
+    }
+
+    /// Wait up to `timeout` for a complete data frame to be received from the stream.
+    pub(crate) fn receive_frame_timeout(&self, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0u8; 1024];
+        loop {
+            if let Some(frame) = take_decoded(&self.decoder, FrameDecoder::next_frame)? {
+                return Ok(Some(frame));
+            }
+            if self.is_shutdown.load(Ordering::SeqCst) {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "interface has been shut down",
+                ));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            let result = self.reader.lock().unwrap().read(&mut buf);
+            match result {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => self.decoder.lock().unwrap().push(&buf[..n]),
+                Err(e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut =>
+                {
+                    thread::sleep(STREAM_POLL_INTERVAL.min(remaining));
                 }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub(crate) fn take_control_frame(&self) -> Option<KissControlFrame> {
+        self.decoder.lock().unwrap().take_control_frame()
+    }
+
+    /// Send a data frame to the TNC for transmission.
+    pub(crate) fn send_frame(&self, frame: &[u8]) -> io::Result<()> {
+        self.send_kiss_command(CMD_DATA, 0, frame)
+    }
+
+    pub(crate) fn send_kiss_command(&self, command: u8, port: u8, data: &[u8]) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(&encode_command(command, check_port(port)?, data))?;
+        writer.flush()
+    }
+
+    /// Stop receiving. A receive will only notice once its read from the stream returns.
+    pub(crate) fn shutdown(&self) {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Wrap an AX.25 frame in KISS framing so it can be sent to a TNC.
+///
+/// The frame is addressed to port 0 as a data frame. Any FEND or FESC bytes in the
+/// frame are escaped and the result is delimited by FEND at both ends.
+pub fn kiss_encode(frame: &[u8]) -> Vec<u8> {
+    encode_command(CMD_DATA, 0, frame)
+}
+
+fn check_port(port: u8) -> io::Result<u8> {
+    if port > 0x0f {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "KISS port must be from 0 to 15",
+        ));
+    }
+    Ok(port)
+}
+
+fn encode_command(command: u8, port: u8, data: &[u8]) -> Vec<u8> {
+    // The KISS command byte is two nybbles: the port and the command
+    let command_byte = match command {
+        CMD_RETURN => CMD_RETURN,
+        _ => (port << 4) | (command & 0x0f),
+    };
+    let mut contents = Vec::with_capacity(data.len() + 1);
+    contents.push(command_byte);
+    contents.extend(data);
+    escape_frame(&contents)
+}
+
+/// Wrap an AX.25 frame in SMACK framing so it can be sent to a TNC.
+///
+/// This is the same as `kiss_encode` except that the high bit of the command byte is
+/// set and a CRC-16 of the command byte and frame is appended before escaping.
+pub fn smack_encode(frame: &[u8]) -> Vec<u8> {
+    let mut contents = Vec::with_capacity(frame.len() + 3);
+    contents.push(SMACK_FLAG | CMD_DATA);
+    contents.extend(frame);
+    let crc = smack_crc(&contents);
+    contents.extend(crc.to_le_bytes());
+    escape_frame(&contents)
+}
+
+/// Extract the next data frame from a receive buffer that may contain SMACK frames.
+///
+/// Frames with the SMACK flag set have their CRC verified and removed, and are skipped
+/// if it does not match. Frames without the flag are handled as in `kiss_decode`.
+pub fn smack_decode(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+    loop {
+        if let KissFrame::Data { payload, .. } = take_frame(buffer, true)? {
+            return Some(payload);
+        }
+    }
+}
+
+/// Discard data from a buffer which has grown beyond `max_buffer` bytes without
+/// containing a complete frame. Anything from the last FEND onwards is kept if it is
+/// small enough, since it may be the start of the next frame.
+///
+/// Returns the number of bytes discarded, or `None` if the buffer was within the limit.
+pub(crate) fn resync_buffer(buffer: &mut Vec<u8>, max_buffer: usize) -> Option<usize> {
+    if buffer.len() <= max_buffer {
+        return None;
+    }
+    let keep_from = match buffer.iter().rposition(|&b| b == FEND) {
+        Some(idx) if buffer.len() - idx <= max_buffer => idx,
+        _ => buffer.len(),
+    };
+    buffer.drain(..keep_from);
+    Some(keep_from)
+}
+
+pub(crate) fn overflow_error(discarded: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("no KISS frame boundary found, discarded {discarded} bytes"),
+    )
+}
+
+/// CRC-16 as used by SMACK: polynomial 0x8005, reflected, initial value 0.
+fn smack_crc(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &b in data {
+        crc ^= b as u16;
+        for _ in 0..8 {
+            if crc & 0x0001 != 0 {
+                crc = (crc >> 1) ^ 0xA001;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    crc
+}
+
+/// Delimit a command byte and its data with FEND, escaping any special bytes.
+fn escape_frame(contents: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(contents.len() + 2);
+    encoded.push(FEND);
+    for &b in contents {
+        match b {
+            FEND => encoded.extend([FESC, TFEND]),
+            FESC => encoded.extend([FESC, TFESC]),
+            b => encoded.push(b),
+        }
+    }
+    encoded.push(FEND);
+    encoded
+}
+
+/// Extract the next KISS data frame from the start of a receive buffer.
+///
+/// Returns `None` if the buffer does not yet contain a complete frame, in which case
+/// the caller should append more bytes and try again. When a frame is found, the bytes
+/// it occupied are removed from `buffer` and the unescaped contents are returned,
+/// without the leading KISS command byte. Frames carrying KISS commands other than
+/// data are consumed and skipped.
+pub fn kiss_decode(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+    loop {
+        if let KissFrame::Data { payload, .. } =
+            split_command(make_frame_from_buffer(buffer)?, false)
+        {
+            return Some(payload);
+        }
+    }
+}
+
+/// Separate the contents of a KISS frame into data, or a control frame for any other
+/// command. With `smack` the top bit of the port is the SMACK flag, which is ignored.
+fn split_command(frame: Vec<u8>, smack: bool) -> KissFrame {
+    // Low nybble of the command byte is the command; 0 means a data frame
+    let port = (frame[0] >> 4) & if smack { 0x07 } else { 0x0f };
+    if frame[0] & 0x0f == CMD_DATA {
+        return KissFrame::Data {
+            port,
+            payload: frame[1..].to_vec(),
+        };
+    }
+    KissFrame::Control(KissControlFrame {
+        port,
+        command: frame[0] & 0x0f,
+        data: frame[1..].to_vec(),
+    })
+}
+
+/// Take the next frame of any kind from the buffer, verifying and removing the CRC
+/// from SMACK frames if `smack` is set.
+fn take_frame(buffer: &mut Vec<u8>, smack: bool) -> Option<KissFrame> {
+    loop {
+        if let Some(frame) = check_frame(make_frame_from_buffer(buffer)?, smack) {
+            return Some(frame);
+        }
+    }
+}
+
+/// Split a complete frame into data or control, or `None` if it carries a SMACK CRC
+/// that does not match.
+fn check_frame(mut frame: Vec<u8>, smack: bool) -> Option<KissFrame> {
+    let end = verified_len(&frame, smack)?;
+    frame.truncate(end);
+    Some(split_command(frame, smack))
+}
+
+/// The length of a frame without any SMACK CRC, or `None` if the CRC does not match.
+fn verified_len(frame: &[u8], smack: bool) -> Option<usize> {
+    if smack && frame[0] & SMACK_FLAG != 0 {
+        if frame.len() < 3 {
+            return None;
+        }
+        let end = frame.len() - 2;
+        let crc = u16::from_le_bytes([frame[end], frame[end + 1]]);
+        if smack_crc(&frame[..end]) != crc {
+            return None;
+        }
+        return Some(end);
+    }
+    Some(frame.len())
+}
+
+/// Take the next frame from a shared decoder using `next`, reporting any data discarded
+/// to resynchronise as an error.
+pub(crate) fn take_decoded<T>(
+    decoder: &Mutex<FrameDecoder>,
+    next: impl FnOnce(&mut FrameDecoder) -> Option<T>,
+) -> io::Result<Option<T>> {
+    let mut decoder = decoder.lock().unwrap();
+    if let Some(frame) = next(&mut decoder) {
+        return Ok(Some(frame));
+    }
+    match decoder.take_discarded() {
+        Some(discarded) => Err(overflow_error(discarded)),
+        None => Ok(None),
+    }
+}
+
+/// Remember a control frame for `take_control_frame`, forgetting the oldest if nobody
+/// has been collecting them.
+fn queue_control(control: &Mutex<VecDeque<KissControlFrame>>, frame: KissControlFrame) {
+    let mut control = control.lock().unwrap();
+    if control.len() >= MAX_QUEUED_CONTROL_FRAMES {
+        control.pop_front();
+    }
+    control.push_back(frame);
+}
+
+/// Progress through the bytes of a KISS frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scan {
+    LookingForStartMarker,
+    Data,
+    Escaped,
+}
+
+/// Advance the scan by one byte, adding any data to `frame`. Returns true once `c` has
+/// completed a non-empty frame.
+fn scan_byte(state: &mut Scan, frame: &mut Vec<u8>, c: u8) -> bool {
+    match *state {
+        Scan::LookingForStartMarker => {
+            if c == FEND {
+                *state = Scan::Data;
             }
-            Scan::Escaped => {
-                if c == TFEND {
-                    possible_frame.push(FEND);
-                } else if c == TFESC {
-                    possible_frame.push(FESC);
-                } else if c == FEND && !possible_frame.is_empty() {
-                    // Successfully read a non-zero-length frame
-                    final_idx = idx;
-                    break;
+        }
+        Scan::Data => {
+            if c == FEND {
+                if !frame.is_empty() {
+                    return true;
                 }
-                state = Scan::Data;
+            } else if c == FESC {
+                *state = Scan::Escaped;
+            } else {
+                frame.push(c);
+            }
+        }
+        Scan::Escaped => {
+            if c == TFEND {
+                frame.push(FEND);
+            } else if c == TFESC {
+                frame.push(FESC);
+            } else if c == FEND && !frame.is_empty() {
+                return true;
             }
+            *state = Scan::Data;
         }
     }
+    false
+}
+
+fn make_frame_from_buffer(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+    let mut possible_frame = Vec::new();
+    let mut state = Scan::LookingForStartMarker;
+
+    // Check for possible frame read-only until we know we have a complete frame
+    // If we take one out, clear out buffer up to the final index
+    let final_idx = buffer
+        .iter()
+        .position(|&c| scan_byte(&mut state, &mut possible_frame, c))?;
+    // Draining up to the final index will leave the final FEND in place
+    // This way we can use it as the start marker for the next frame
+    buffer.drain(0..final_idx);
+    Some(possible_frame)
+}
+
+#[test]
+fn test_frame_decoder_chunks() {
+    let mut encoded = kiss_encode(&[0x01, FEND, 0x02]);
+    encoded.extend(encode_command(CMD_TX_DELAY, 0, &[30]));
+    encoded.extend(kiss_encode(&[0x03]));
+    let mut decoder = FrameDecoder::new();
+    let mut frames = Vec::new();
+    // Every chunk boundary, including inside escape sequences, must be handled
+    for chunk in encoded.chunks(3) {
+        decoder.push(chunk);
+        while let Some(frame) = decoder.next_frame() {
+            frames.push(frame);
+        }
+    }
+    assert_eq!(frames, vec![vec![0x01, FEND, 0x02], vec![0x03]]);
+    assert_eq!(
+        decoder.take_control_frame(),
+        Some(KissControlFrame {
+            port: 0,
+            command: CMD_TX_DELAY,
+            data: vec![30],
+        })
+    );
+    assert_eq!(decoder.take_control_frame(), None);
+
+    let mut decoder = FrameDecoder::new().with_max_buffer(4);
+    decoder.push(&[FEND, 0x00, 0x01, 0x02, 0x03, 0x04]);
+    assert_eq!(decoder.next_frame(), None);
+    assert_eq!(decoder.take_discarded(), Some(6));
+    assert_eq!(decoder.take_discarded(), None);
+}
 
-    match final_idx {
-        0 => None,
-        n => {
-            // Draining up to "n" will leave the final FEND in place
-            // This way we can use it as the start marker for the next frame
-            buffer.drain(0..n);
-            Some(possible_frame)
+#[test]
+fn test_frame_decoder_escape_across_pushes() {
+    let data = vec![0x01, FESC, 0x02, FEND, 0x03, FESC];
+    let encoded = kiss_encode(&data);
+    assert!(encoded.windows(2).any(|w| w == [FESC, TFESC]));
+    let mut decoder = FrameDecoder::new();
+    for (i, &b) in encoded.iter().enumerate() {
+        decoder.push(&[b]);
+        let frame = decoder.next_frame();
+        if i + 1 < encoded.len() {
+            assert_eq!(frame, None);
+            // Consumed bytes are not kept around to be scanned again
+            assert_eq!(decoder.scanned, decoder.buffer.len());
+        } else {
+            assert_eq!(frame, Some(data.clone()));
         }
     }
+    // The closing FEND also opens the next frame
+    decoder.push(&[0x00, 0x04, FEND]);
+    assert_eq!(decoder.next_frame(), Some(vec![0x04]));
+}
+
+#[test]
+fn test_control_frames_separated() {
+    let mut rx = vec![FEND, 0x16, b'v', b'1', FEND, 0x00, 0x01, FEND];
+    assert_eq!(
+        take_frame(&mut rx, false),
+        Some(KissFrame::Control(KissControlFrame {
+            port: 1,
+            command: CMD_SET_HARDWARE,
+            data: b"v1".to_vec(),
+        }))
+    );
+    assert_eq!(
+        take_frame(&mut rx, false),
+        Some(KissFrame::Data {
+            port: 0,
+            payload: vec![0x01]
+        })
+    );
+
+    let mut rx = vec![FEND, 0x16, b'v', b'1', FEND, 0x00, 0x01, FEND];
+    assert_eq!(kiss_decode(&mut rx), Some(vec![0x01]));
+
+    // Ports above 7 use the top bit of the port nybble, which SMACK takes as its flag
+    let mut rx = vec![FEND, 0x90, 0x01, FEND];
+    assert_eq!(
+        take_frame(&mut rx, false),
+        Some(KissFrame::Data {
+            port: 9,
+            payload: vec![0x01]
+        })
+    );
 }
 
 #[test]
@@ -204,3 +1452,413 @@ fn test_two_frames_double_fend() {
     assert_eq!(make_frame_from_buffer(&mut rx), Some(vec![0x03, 0x04]));
     assert_eq!(rx, vec![FEND]);
 }
+
+#[test]
+fn test_encode_escapes() {
+    assert_eq!(
+        kiss_encode(&[0x01, FEND, 0x02, FESC, 0x03]),
+        vec![FEND, 0x00, 0x01, FESC, TFEND, 0x02, FESC, TFESC, 0x03, FEND]
+    );
+}
+
+#[test]
+fn test_encode_commands() {
+    assert_eq!(
+        encode_command(CMD_TX_DELAY, 0, &[50]),
+        vec![FEND, 0x01, 50, FEND]
+    );
+    assert_eq!(
+        encode_command(CMD_FULL_DUPLEX, 0, &[1]),
+        vec![FEND, 0x05, 0x01, FEND]
+    );
+    assert_eq!(
+        encode_command(CMD_SET_HARDWARE, 0, &[FEND]),
+        vec![FEND, 0x06, FESC, TFEND, FEND]
+    );
+    assert_eq!(
+        encode_command(CMD_TX_DELAY, 2, &[50]),
+        vec![FEND, 0x21, 50, FEND]
+    );
+    // Return applies to all ports so the port is not encoded
+    assert_eq!(encode_command(CMD_RETURN, 3, &[]), vec![FEND, 0xFF, FEND]);
+    assert_eq!(
+        check_port(16).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn test_frame_split_across_reads() {
+    use crate::test_util::test_frame;
+
+    let frame = test_frame(&[b'a', FEND, b'b', FESC, b'c', FESC, FEND, b'd']).to_bytes();
+    for smack in [false, true] {
+        let encoded = if smack {
+            smack_encode(&frame)
+        } else {
+            kiss_encode(&frame)
+        };
+        for size in [1, 3, 7] {
+            let mut decoder = FrameDecoder::new();
+            if smack {
+                decoder = decoder.with_smack();
+            }
+            let mut frames = Vec::new();
+            // As if each chunk were the result of one read from the TNC
+            for chunk in encoded.chunks(size) {
+                decoder.push(chunk);
+                while let Some(frame) = decoder.next_frame() {
+                    frames.push(frame);
+                }
+            }
+            assert_eq!(frames, vec![frame.clone()], "chunks of {}", size);
+            assert_eq!(decoder.take_discarded(), None);
+        }
+    }
+}
+
+#[test]
+fn test_smack_round_trip() {
+    // CRC-16/ARC check value
+    assert_eq!(smack_crc(b"123456789"), 0xBB3D);
+
+    let frame = vec![0x01, FEND, 0x02, FESC, 0x03];
+    let mut buffer = smack_encode(&frame);
+    assert_eq!(buffer[1], 0x80);
+    assert_eq!(smack_decode(&mut buffer), Some(frame.clone()));
+
+    // Plain KISS frames are still accepted
+    let mut buffer = kiss_encode(&frame);
+    assert_eq!(smack_decode(&mut buffer), Some(frame));
+}
+
+#[test]
+fn test_smack_bad_crc_dropped() {
+    let mut buffer = smack_encode(&[0x01, 0x02, 0x03]);
+    buffer[3] ^= 0xff;
+    buffer.extend(smack_encode(&[0x04]));
+    assert_eq!(smack_decode(&mut buffer), Some(vec![0x04]));
+}
+
+#[test]
+fn test_decode_round_trip() {
+    let frame = vec![0x82, FEND, 0xA0, FESC, 0x00, 0x03];
+    let mut rx = kiss_encode(&frame);
+    assert_eq!(kiss_decode(&mut rx), Some(frame));
+    assert_eq!(rx, vec![FEND]);
+}
+
+#[test]
+fn test_decode_skips_non_data_frames() {
+    // TXDELAY command followed by a data frame
+    let mut rx = vec![FEND, 0x01, 0x32, FEND, 0x00, 0x01, 0x02, FEND];
+    assert_eq!(kiss_decode(&mut rx), Some(vec![0x01, 0x02]));
+    assert_eq!(kiss_decode(&mut rx), None);
+}
+
+#[test]
+fn test_receive_timeout_keeps_partial_frame() {
+    use crate::test_util::tcp_kiss_interface_pair;
+
+    let (iface, mut server) = tcp_kiss_interface_pair();
+
+    let timeout = Duration::from_millis(50);
+    assert_eq!(iface.receive_frame_timeout(timeout).unwrap(), None);
+    server.write_all(&[FEND, 0x00, 0x01]).unwrap();
+    assert_eq!(iface.receive_frame_timeout(timeout).unwrap(), None);
+    server.write_all(&[0x02, FEND]).unwrap();
+    assert_eq!(
+        iface.receive_frame_timeout(Duration::from_secs(5)).unwrap(),
+        Some(vec![0x01, 0x02])
+    );
+}
+
+#[test]
+fn test_next_frame_into_reuses_buffer() {
+    let mut decoder = FrameDecoder::new().with_smack();
+    decoder.push(&smack_encode(&[0x01, 0x02, 0x03]));
+    decoder.push(&[FEND, 0x16, b'v', b'1', FEND]);
+    let mut bad = smack_encode(&[0x04]);
+    bad[3] ^= 0xff;
+    decoder.push(&bad);
+    decoder.push(&kiss_encode(&[0x05]));
+
+    let mut buf = Vec::with_capacity(64);
+    let ptr = buf.as_ptr();
+    assert!(decoder.next_frame_into(&mut buf));
+    assert_eq!(buf, vec![0x01, 0x02, 0x03]);
+    // The bad frame is dropped and the control frame set aside
+    assert!(decoder.next_frame_into(&mut buf));
+    assert_eq!(buf, vec![0x05]);
+    assert_eq!(buf.as_ptr(), ptr);
+    assert!(!decoder.next_frame_into(&mut buf));
+    assert_eq!(buf, vec![0x05]);
+    assert_eq!(decoder.take_control_frame().unwrap().data, b"v1".to_vec());
+}
+
+#[test]
+fn test_receive_frame_into() {
+    use crate::test_util::tcp_kiss_interface_pair;
+
+    let (iface, mut server) = tcp_kiss_interface_pair();
+
+    let mut buf = vec![0xAA; 10];
+    assert!(!iface
+        .receive_frame_timeout_into(Duration::from_millis(50), &mut buf)
+        .unwrap());
+    assert_eq!(buf, vec![0xAA; 10]);
+    server.write_all(&kiss_encode(&[0x01, FEND])).unwrap();
+    server.write_all(&kiss_encode(&[0x02])).unwrap();
+    iface.receive_frame_into(&mut buf).unwrap();
+    assert_eq!(buf, vec![0x01, FEND]);
+    assert!(iface
+        .receive_frame_timeout_into(Duration::from_secs(5), &mut buf)
+        .unwrap());
+    assert_eq!(buf, vec![0x02]);
+}
+
+#[test]
+fn test_receive_after_remote_close() {
+    use crate::test_util::tcp_kiss_interface_pair;
+
+    let (iface, mut server) = tcp_kiss_interface_pair();
+    server.write_all(&kiss_encode(&[0x01])).unwrap();
+    drop(server);
+
+    // Frames already sent are still delivered before the end of the stream
+    let timeout = Duration::from_secs(5);
+    assert_eq!(
+        iface.receive_frame_timeout(timeout).unwrap(),
+        Some(vec![0x01])
+    );
+    for _ in 0..2 {
+        assert_eq!(
+            iface.receive_frame_timeout(timeout).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+    assert_eq!(
+        iface.receive_frame().unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+fn test_keepalive_and_is_connected() {
+    use crate::test_util::local_listener;
+
+    let listener = local_listener();
+    let policy = KeepalivePolicy {
+        idle: Duration::from_secs(3),
+        interval: Duration::from_secs(1),
+        retries: 2,
+    };
+    let iface = TcpKissInterface::new(listener.local_addr().unwrap())
+        .unwrap()
+        .with_keepalive(policy)
+        .unwrap();
+    {
+        let stream = iface.tx_stream.lock().unwrap();
+        let socket = socket2::SockRef::from(&*stream);
+        assert!(socket.keepalive().unwrap());
+        #[cfg(target_os = "linux")]
+        {
+            assert_eq!(socket.tcp_keepalive_time().unwrap(), Duration::from_secs(3));
+            assert_eq!(
+                socket.tcp_keepalive_interval().unwrap(),
+                Duration::from_secs(1)
+            );
+            assert_eq!(socket.tcp_keepalive_retries().unwrap(), 2);
+        }
+    }
+    assert!(iface.is_connected());
+
+    let (server, _) = listener.accept().unwrap();
+    drop(server);
+    let timeout = Duration::from_secs(5);
+    assert!(iface.receive_frame_timeout(timeout).is_err());
+    assert!(!iface.is_connected());
+    assert_eq!(
+        iface.send_frame(&[0x01]).unwrap_err().kind(),
+        io::ErrorKind::NotConnected
+    );
+}
+
+#[test]
+fn test_receive_kiss_frames_with_port() {
+    use crate::test_util::tcp_kiss_interface_pair;
+
+    let (iface, mut server) = tcp_kiss_interface_pair();
+
+    server
+        .write_all(&[FEND, 0x20, 0x01, FEND, 0x16, b'v', b'1', FEND])
+        .unwrap();
+    let timeout = Duration::from_secs(5);
+    let frame = iface.receive_kiss_frame_timeout(timeout).unwrap().unwrap();
+    assert_eq!(frame.port(), 2);
+    assert_eq!(
+        frame,
+        KissFrame::Data {
+            port: 2,
+            payload: vec![0x01]
+        }
+    );
+    assert_eq!(
+        iface.receive_kiss_frame().unwrap(),
+        KissFrame::Control(KissControlFrame {
+            port: 1,
+            command: CMD_SET_HARDWARE,
+            data: b"v1".to_vec(),
+        })
+    );
+    // Control frames are not also queued for take_control_frame
+    assert_eq!(iface.take_control_frame(), None);
+}
+
+#[test]
+fn test_reconnect_after_remote_close() {
+    use crate::test_util::local_listener;
+
+    let listener = local_listener();
+    let iface = TcpKissInterface::new(listener.local_addr().unwrap())
+        .unwrap()
+        .with_reconnect(ReconnectPolicy {
+            initial_backoff: Duration::from_millis(300),
+            max_backoff: Duration::from_secs(1),
+        });
+    let (mut server, _) = listener.accept().unwrap();
+    // A partial frame from the first connection must not leak into the second
+    server.write_all(&[FEND, 0x00, 0x01]).unwrap();
+    drop(server);
+
+    // Notices the closed connection but the first attempt is not due yet
+    let timeout = Duration::from_millis(100);
+    assert_eq!(iface.receive_frame_timeout(timeout).unwrap(), None);
+    assert_eq!(
+        iface.send_frame(&[0x01]).unwrap_err().kind(),
+        io::ErrorKind::NotConnected
+    );
+
+    let server = thread::spawn(move || {
+        let (mut server, _) = listener.accept().unwrap();
+        server.write_all(&[FEND, 0x00, 0x02, FEND]).unwrap();
+        server
+    });
+    assert_eq!(
+        iface.receive_frame_timeout(Duration::from_secs(5)).unwrap(),
+        Some(vec![0x02])
+    );
+    let _server = server.join().unwrap();
+    iface.send_frame(&[0x03]).unwrap();
+    assert_eq!(iface.reconnect_count(), 1);
+}
+
+#[test]
+fn test_resync_buffer() {
+    let mut buffer = vec![0x01; 10];
+    assert_eq!(resync_buffer(&mut buffer, 10), None);
+    buffer.extend([FEND, 0x00, 0x02]);
+    assert_eq!(resync_buffer(&mut buffer, 10), Some(10));
+    assert_eq!(buffer, vec![FEND, 0x00, 0x02]);
+    buffer.extend([0x03; 10]);
+    assert_eq!(resync_buffer(&mut buffer, 10), Some(13));
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn test_garbage_does_not_grow_buffer() {
+    use crate::test_util::tcp_kiss_interface_pair;
+
+    let (iface, mut server) = tcp_kiss_interface_pair();
+    let writer = thread::spawn(move || {
+        let garbage = vec![0x55; 1024 * 1024];
+        for _ in 0..10 {
+            server.write_all(&garbage).unwrap();
+        }
+        server.write_all(&kiss_encode(&[0x01, 0x02])).unwrap();
+        server
+    });
+
+    let mut overflows = 0;
+    let frame = loop {
+        match iface.receive_frame_timeout(Duration::from_secs(5)) {
+            Ok(Some(frame)) => break frame,
+            Ok(None) => panic!("timed out"),
+            Err(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+                overflows += 1;
+            }
+        }
+        assert!(iface.decoder.lock().unwrap().buffer.len() <= DEFAULT_MAX_BUFFER);
+    };
+    assert_eq!(frame, vec![0x01, 0x02]);
+    assert!(overflows > 0);
+    let _ = writer.join();
+}
+
+#[test]
+fn test_udp_round_trip() {
+    let tnc = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let iface = UdpKissInterface::new("127.0.0.1:0", tnc.local_addr().unwrap()).unwrap();
+
+    iface.send_frame(&[0x01, FEND, 0x02]).unwrap();
+    let mut buf = [0u8; 64];
+    let (n, from) = tnc.recv_from(&mut buf).unwrap();
+    assert_eq!(&buf[..n], &kiss_encode(&[0x01, FEND, 0x02])[..]);
+    assert_eq!(from, iface.local_addr().unwrap());
+
+    // Two frames in one datagram, then a bare frame without FENDs
+    let mut datagram = kiss_encode(&[0x03]);
+    datagram.extend(kiss_encode(&[0x04]));
+    tnc.send_to(&datagram, from).unwrap();
+    tnc.send_to(&[0x00, 0x05], from).unwrap();
+    let timeout = Duration::from_secs(5);
+    assert_eq!(
+        iface.receive_frame_timeout(timeout).unwrap(),
+        Some(vec![0x03])
+    );
+    assert_eq!(
+        iface.receive_frame_timeout(timeout).unwrap(),
+        Some(vec![0x04])
+    );
+    assert_eq!(
+        iface.receive_frame_timeout(timeout).unwrap(),
+        Some(vec![0x05])
+    );
+    assert_eq!(
+        iface
+            .receive_frame_timeout(Duration::from_millis(50))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_udp_other_sender() {
+    // The TNC is at another address, so datagrams from 127.0.0.1 are not from it
+    let iface = UdpKissInterface::new("127.0.0.1:0", "127.0.0.2:9").unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender
+        .send_to(&kiss_encode(&[0x01]), iface.local_addr().unwrap())
+        .unwrap();
+    assert_eq!(
+        iface
+            .receive_frame_timeout(Duration::from_millis(200))
+            .unwrap(),
+        None
+    );
+
+    let iface = iface.with_any_sender();
+    sender
+        .send_to(&kiss_encode(&[0x02]), iface.local_addr().unwrap())
+        .unwrap();
+    assert_eq!(
+        iface.receive_frame_timeout(Duration::from_secs(5)).unwrap(),
+        Some(vec![0x02])
+    );
+
+    assert!(same_host(
+        "[::ffff:127.0.0.1]:1".parse().unwrap(),
+        "127.0.0.1:2".parse().unwrap()
+    ));
+}
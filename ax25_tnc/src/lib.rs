@@ -7,10 +7,18 @@
 //! Main features:
 //! * Connect to TNCs via multiple methods without needing to change your code
 //! * KISS protocol
+//! * 6PACK protocol
+//! * AXUDP links to packet node networks
 //!
 //! Most developers will want to focus on `tnc::TncAddress` and `tnc::Tnc`.
 //! 1. Generate or ask the user to supply an address string. This takes the form:  
-//!    `tnc:tcpkiss:192.168.0.1:8001` or  
+//!    `tnc:tcpkiss:192.168.0.1:8001` or `tnc:tcpkiss:[::1]:8001` or  
+//!    `tnc:udpkiss:192.168.0.1:8093` or  
+//!    `tnc:axudp:192.168.0.1:10093` or  
+//!    `tnc:serialkiss:/dev/ttyUSB0:9600` or `tnc:serialkiss:COM3:9600` or  
+//!    `tnc:sixpack:/dev/ttyS0:19200` or  
+//!    `tnc:agwpe:localhost:8000` or  
+//!    `tnc:file:/path/to/capture.pcap` or  
 //!    `tnc:linuxif:vk7ntk-2`
 //! 2. Parse this to an address: `let addr = string.parse::<TncAddress>()?;`
 //! 3. Attempt to open the TNC: `let tnc = Tnc::open(&addr)?;`
@@ -22,8 +30,44 @@
 /// Connect to a TNC and use it to send and receive frames.
 pub mod tnc;
 
+/// Periodic beacons and automatic station identification.
+pub mod beacon;
+
+/// Combining several TNCs into one logical channel.
+pub mod group;
+
+/// Interfacing with AGWPE servers such as AGW Packet Engine and Dire Wolf.
+mod agwpe;
+
+/// Replaying frames from capture files.
+mod file;
+
+/// Asynchronous TNC access for applications built on tokio.
+#[cfg(feature = "tokio")]
+pub mod async_tnc;
+
+/// Connected-mode AX.25 sessions with acknowledgement and retransmission.
+pub mod connection;
+
 /// Interfacing with native AX.25 network interfaces on Linux.
-mod linux;
+///
+/// On other platforms there are no interfaces to list and everything else fails with
+/// `std::io::ErrorKind::Unsupported`.
+pub mod linux;
+
+/// KISS protocol framing and interfacing with TCP and UDP KISS servers such as Dire Wolf.
+pub mod kiss;
+
+/// 6PACK protocol framing, an alternative to KISS used by some TNCs.
+pub mod sixpack;
+
+/// AXUDP encapsulation of AX.25 frames in UDP datagrams, as used to link packet nodes
+/// such as `ax25ipd`, JNOS and URONode.
+///
+/// AXIP, which carries frames directly in IP packets of protocol 93, needs raw sockets
+/// and is not supported.
+pub mod axudp;
 
-/// Interfacing with TCP KISS servers such as Dire Wolf.
-mod kiss;
+/// Fixtures shared by the tests in this crate.
+#[cfg(test)]
+mod test_util;
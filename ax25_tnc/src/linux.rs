@@ -1,93 +1,615 @@
+use crate::kiss::{kiss_encode, take_decoded, FrameDecoder};
+use ax25::frame::{Address, Ax25Frame};
+use std::fmt;
 #[cfg(not(target_os = "linux"))]
 use std::io::ErrorKind;
 use std::io::{self, Error};
+#[cfg(target_os = "linux")]
+use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The error returned by everything in this module on platforms other than Linux,
+/// which have no AX.25 network interfaces.
+#[cfg(not(target_os = "linux"))]
+fn unsupported() -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        "AX.25 network interfaces are only supported on Linux",
+    )
+}
+
+/// Frames on an `AF_PACKET` socket carry a single null byte ahead of the AX.25 frame,
+/// which is the KISS data command the kernel expects. This is the only place it is added.
+#[cfg(target_os = "linux")]
+fn add_packet_prefix(frame: &[u8]) -> Vec<u8> {
+    let mut prefixed = Vec::with_capacity(frame.len() + 1);
+    prefixed.push(0);
+    prefixed.extend_from_slice(frame);
+    prefixed
+}
+
+/// Remove the null byte added by `AF_PACKET`, leaving the AX.25 frame exactly as received.
+#[cfg(target_os = "linux")]
+fn strip_packet_prefix(packet: &mut Vec<u8>) {
+    if packet.first() == Some(&0) {
+        packet.remove(0);
+    }
+}
+
+/// Errors from setting up AX.25 sockets and interfaces on Linux.
+#[derive(Debug)]
+pub enum LinuxError {
+    /// AX.25 network interfaces only exist on Linux
+    UnsupportedPlatform,
+    /// The operation needs more privileges than the process has
+    PermissionDenied { source: io::Error },
+    /// No AX.25 interface has the given callsign as its hardware address
+    InterfaceNotFound { callsign: String },
+    /// The kernel would not describe a network interface
+    IoctlFailed {
+        request: &'static str,
+        interface: String,
+        source: io::Error,
+    },
+    /// Any other failure from the operating system
+    Io { source: io::Error },
+}
+
+impl std::error::Error for LinuxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::UnsupportedPlatform => None,
+            Self::PermissionDenied { source } => Some(source),
+            Self::InterfaceNotFound { .. } => None,
+            Self::IoctlFailed { source, .. } => Some(source),
+            Self::Io { source } => Some(source),
+        }
+    }
+}
+
+impl fmt::Display for LinuxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedPlatform => {
+                write!(f, "AX.25 network interfaces are only supported on Linux")
+            }
+            Self::PermissionDenied { source } => write!(
+                f,
+                "Permission denied, raw AX.25 access requires root or CAP_NET_ADMIN: {}",
+                source
+            ),
+            Self::InterfaceNotFound { callsign } => {
+                write!(f, "No AX.25 interface has the callsign '{}'", callsign)
+            }
+            Self::IoctlFailed {
+                request,
+                interface,
+                source,
+            } => write!(
+                f,
+                "{} failed for interface {}: {}",
+                request, interface, source
+            ),
+            Self::Io { source } => write!(f, "{}", source),
+        }
+    }
+}
+
+impl From<io::Error> for LinuxError {
+    fn from(source: io::Error) -> Self {
+        if source.kind() == io::ErrorKind::PermissionDenied {
+            Self::PermissionDenied { source }
+        } else {
+            Self::Io { source }
+        }
+    }
+}
 
 /// An active AX.25 network interface, e.g. "ax0"
-pub(crate) struct NetDev {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceInfo {
+    /// Name of the network interface, e.g. `ax0`
     pub name: String,
+    /// The hardware address assigned to the interface, e.g. `VK7NTK-2`
+    pub callsign: String,
+    /// Index of the interface in the kernel
     pub ifindex: i32,
 }
 
-/// An open socket for sending and receiving AX.25 frames
-pub(crate) struct Ax25RawSocket {
+impl InterfaceInfo {
+    /// Whether this interface has the given callsign, treating `VK7NTK` and `VK7NTK-0`
+    /// as the same and ignoring case.
+    pub fn has_callsign(&self, callsign: &str) -> bool {
+        match (
+            self.callsign.parse::<Address>(),
+            callsign.parse::<Address>(),
+        ) {
+            (Ok(ours), Ok(theirs)) => ours == theirs,
+            _ => self.callsign.eq_ignore_ascii_case(callsign),
+        }
+    }
+}
+
+/// Find all AX.25 network interfaces on the system, such as those set up with
+/// `kissattach`. This does not require any special privileges.
+pub fn list_interfaces() -> Result<Vec<InterfaceInfo>, LinuxError> {
+    #[cfg(target_os = "linux")]
+    {
+        sys::list_interfaces()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Ok(Vec::new())
+    }
+}
+
+/// Find the AX.25 network interface whose hardware address is `callsign`, as matched by
+/// `InterfaceInfo::has_callsign`.
+pub fn find_interface(callsign: &str) -> Result<InterfaceInfo, LinuxError> {
+    #[cfg(target_os = "linux")]
+    {
+        sys::list_interfaces()?
+            .into_iter()
+            .find(|nd| nd.has_callsign(callsign))
+            .ok_or_else(|| LinuxError::InterfaceNotFound {
+                callsign: callsign.to_string(),
+            })
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = callsign;
+        Err(LinuxError::UnsupportedPlatform)
+    }
+}
+
+/// An open socket for sending and receiving AX.25 frames on the Linux network
+/// interfaces listed by `list_interfaces`.
+///
+/// Most applications should use a `linuxif` `Tnc` instead. This is useful for servers
+/// which multiplex several descriptors on one thread: on Linux it implements `AsRawFd`
+/// so that it can be polled, then `try_receive_frame` collects frames without blocking.
+pub struct Ax25RawSocket {
     #[cfg(target_os = "linux")]
     fd: i32,
+    #[cfg(target_os = "linux")]
+    receive_buffer: AtomicUsize,
     is_shutdown: AtomicBool,
 }
 
+/// Default size of the buffer used to receive each frame on an `Ax25RawSocket`. This is
+/// comfortably more than a frame with a 256-byte information field and a full route.
+pub const DEFAULT_RECEIVE_BUFFER: usize = 4096;
+
 impl Ax25RawSocket {
     /// Create a new socket for sending and receiving raw AX.25 frames. This requires root or CAP_NET_ADMIN.
-    pub(crate) fn new() -> io::Result<Ax25RawSocket> {
+    pub fn new() -> Result<Ax25RawSocket, LinuxError> {
         #[cfg(target_os = "linux")]
         {
-            sys::socket_new()
+            Ok(sys::socket_new()?)
         }
         #[cfg(not(target_os = "linux"))]
         {
-            Err(Error::new(ErrorKind::NotFound, "only supported on linux"))
+            Err(LinuxError::UnsupportedPlatform)
+        }
+    }
+
+    /// Set the largest frame that can be received, in bytes. The default is
+    /// `DEFAULT_RECEIVE_BUFFER`.
+    ///
+    /// A larger frame is discarded and the receive fails with `io::ErrorKind::InvalidData`
+    /// rather than returning a truncated frame.
+    #[allow(unused_variables)]
+    pub fn set_receive_buffer_size(&self, size: usize) {
+        #[cfg(target_os = "linux")]
+        {
+            self.receive_buffer.store(size, Ordering::SeqCst);
         }
     }
 
-    /// Find all AX.25 interfaces on the system
-    pub(crate) fn list_ax25_interfaces(&self) -> io::Result<Vec<NetDev>> {
+    /// Ask the kernel to deliver only frames received on the interface with this index.
+    ///
+    /// Without this, frames from every AX.25 interface arrive on the socket and those for
+    /// other interfaces are discarded by `receive_frame`.
+    #[allow(unused_variables)]
+    pub fn bind_interface(&self, ifindex: i32) -> io::Result<()> {
         #[cfg(target_os = "linux")]
         {
-            sys::socket_list_ax25_interfaces(self)
+            sys::socket_bind_interface(self, ifindex)
         }
         #[cfg(not(target_os = "linux"))]
         {
-            Ok(Vec::new())
+            Err(unsupported())
         }
     }
 
     /// Send a frame to a particular interface, specified by its index
     #[allow(unused_variables)]
-    pub(crate) fn send_frame(&self, frame: &[u8], ifindex: i32) -> io::Result<()> {
+    pub fn send_frame(&self, frame: &[u8], ifindex: i32) -> io::Result<()> {
         #[cfg(target_os = "linux")]
         {
             sys::socket_send_frame(self, frame, ifindex)
         }
         #[cfg(not(target_os = "linux"))]
         {
-            Err(Error::new(
-                ErrorKind::NotConnected,
-                "only supported on linux",
-            ))
+            Err(unsupported())
+        }
+    }
+
+    /// Receive an incoming AX.25 frame from a particular interface, specified by its index.
+    /// If a timeout is supplied, returns `Ok(None)` if no frame arrives in that time.
+    #[allow(unused_variables)]
+    pub fn receive_frame(
+        &self,
+        ifindex: i32,
+        timeout: Option<Duration>,
+    ) -> io::Result<Option<Vec<u8>>> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(Error::new(
+                io::ErrorKind::NotConnected,
+                "socket has been shut down",
+            ));
+        }
+        #[cfg(target_os = "linux")]
+        {
+            sys::socket_receive_frame(self, ifindex, timeout)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(unsupported())
+        }
+    }
+
+    /// Receive an incoming AX.25 frame like `receive_frame`, replacing the contents of
+    /// `buf` with it. Reusing one buffer avoids allocating for each frame. Returns
+    /// `Ok(false)` if the timeout expired first, leaving the contents of `buf` unspecified.
+    #[allow(unused_variables)]
+    #[cfg_attr(not(target_os = "linux"), allow(clippy::ptr_arg))]
+    pub fn receive_frame_into(
+        &self,
+        ifindex: i32,
+        timeout: Option<Duration>,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<bool> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(Error::new(
+                io::ErrorKind::NotConnected,
+                "socket has been shut down",
+            ));
+        }
+        #[cfg(target_os = "linux")]
+        {
+            sys::socket_receive_frame_into(self, ifindex, timeout, buf)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(unsupported())
+        }
+    }
+
+    /// Receive an incoming AX.25 frame from a particular interface without blocking.
+    /// Returns `Ok(None)` if no frame is waiting.
+    #[allow(unused_variables)]
+    pub fn try_receive_frame(&self, ifindex: i32) -> io::Result<Option<Vec<u8>>> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(Error::new(
+                io::ErrorKind::NotConnected,
+                "socket has been shut down",
+            ));
+        }
+        #[cfg(target_os = "linux")]
+        {
+            sys::socket_try_receive_frame(self, ifindex)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(unsupported())
+        }
+    }
+
+    /// Shutdown the socket. Subsequent receives will fail, which allows a receiving
+    /// thread to notice within its timeout. The file descriptor itself is closed on drop
+    /// so that it cannot be reused while another thread is still inside a syscall.
+    pub fn shutdown(&self) {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl std::os::unix::io::AsRawFd for Ax25RawSocket {
+    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+        self.fd
+    }
+}
+
+impl Drop for Ax25RawSocket {
+    fn drop(&mut self) {
+        self.shutdown();
+        #[cfg(target_os = "linux")]
+        {
+            let _ = sys::socket_close(self);
+        }
+    }
+}
+
+/// A connected-mode AX.25 session handled by the Linux kernel's own AX.25 stack.
+///
+/// The kernel takes care of acknowledgement and retransmission, so this offers a
+/// reliable stream of data packets to a remote station. It requires an AX.25
+/// interface that has been set up with `kissattach` or similar.
+pub struct Ax25ConnectedSocket {
+    #[cfg(target_os = "linux")]
+    fd: i32,
+    is_shutdown: AtomicBool,
+}
+
+impl Ax25ConnectedSocket {
+    /// Connect from `local` to `remote` through the AX.25 interface whose hardware
+    /// address is `port`, as shown by `ifconfig` or used in a `tnc:linuxif` address.
+    ///
+    /// This blocks until the remote station accepts or refuses the connection.
+    #[allow(unused_variables)]
+    pub fn connect(port: &Address, local: &Address, remote: &Address) -> Result<Self, LinuxError> {
+        #[cfg(target_os = "linux")]
+        {
+            sys::connected_socket_connect(port, local, remote)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(LinuxError::UnsupportedPlatform)
         }
     }
 
-    /// Block to receive an incoming AX.25 frame from any interface
+    /// Send one packet of data to the remote station. Its length must not exceed the
+    /// paclen configured for the interface.
     #[allow(unused_variables)]
-    pub(crate) fn receive_frame(&self, ifindex: i32) -> io::Result<Vec<u8>> {
+    pub fn send(&self, data: &[u8]) -> io::Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            sys::connected_socket_send(self, data)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(unsupported())
+        }
+    }
+
+    /// Block until a packet of data is received from the remote station. An empty
+    /// result means the remote station has disconnected.
+    pub fn recv(&self) -> io::Result<Vec<u8>> {
         #[cfg(target_os = "linux")]
         {
-            sys::socket_receive_frame(self, ifindex)
+            sys::connected_socket_recv(self)
         }
         #[cfg(not(target_os = "linux"))]
         {
-            Err(Error::new(
-                ErrorKind::NotConnected,
-                "only supported on linux",
-            ))
+            Err(unsupported())
         }
     }
 
-    /// Shutdown the socket.
-    pub(crate) fn shutdown(&self) {
-        if !self.is_shutdown.load(Ordering::SeqCst) {
-            self.is_shutdown.store(true, Ordering::SeqCst);
+    /// Disconnect from the remote station. Any blocked `recv` will return.
+    pub fn shutdown(&self) {
+        if !self.is_shutdown.swap(true, Ordering::SeqCst) {
             #[cfg(target_os = "linux")]
             {
-                let _ = sys::socket_close(self);
+                let _ = sys::connected_socket_shutdown(self);
             }
         }
     }
 }
 
-impl Drop for Ax25RawSocket {
+impl Drop for Ax25ConnectedSocket {
+    fn drop(&mut self) {
+        self.shutdown();
+        #[cfg(target_os = "linux")]
+        {
+            unsafe { libc::close(self.fd) };
+        }
+    }
+}
+
+/// Receives UI frames addressed to one callsign through the Linux kernel's AX.25 stack.
+///
+/// An `Ax25RawSocket` sees all traffic on an interface. Here the kernel delivers only
+/// frames whose destination is the bound callsign, so several applications can share an
+/// interface with a callsign each. Other kinds of frame are not delivered.
+pub struct Ax25DatagramSocket {
+    #[cfg(target_os = "linux")]
+    fd: i32,
+    #[cfg(target_os = "linux")]
+    local: Address,
+    #[cfg(target_os = "linux")]
+    receive_buffer: AtomicUsize,
+    is_shutdown: AtomicBool,
+}
+
+impl Ax25DatagramSocket {
+    /// Receive frames addressed to `local` on the AX.25 interface whose hardware address
+    /// is `port`. No special privileges are needed.
+    #[allow(unused_variables)]
+    pub fn bind(port: &Address, local: &Address) -> Result<Self, LinuxError> {
+        #[cfg(target_os = "linux")]
+        {
+            sys::datagram_socket_bind(port, local)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(LinuxError::UnsupportedPlatform)
+        }
+    }
+
+    /// Set the largest information field that can be received, in bytes. The default is
+    /// `DEFAULT_RECEIVE_BUFFER`.
+    ///
+    /// A larger frame is discarded and the receive fails with `io::ErrorKind::InvalidData`
+    /// rather than returning a truncated frame.
+    #[allow(unused_variables)]
+    pub fn set_receive_buffer_size(&self, size: usize) {
+        #[cfg(target_os = "linux")]
+        {
+            self.receive_buffer.store(size, Ordering::SeqCst);
+        }
+    }
+
+    /// Receive the next frame, reassembled as raw bytes. If a timeout is supplied,
+    /// returns `Ok(None)` if no frame arrives in that time.
+    ///
+    /// The kernel reports which repeaters a frame came through but not which of them
+    /// had repeated it, so every route entry is marked as repeated.
+    #[allow(unused_variables)]
+    pub fn receive_frame(&self, timeout: Option<Duration>) -> io::Result<Option<Vec<u8>>> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(Error::new(
+                io::ErrorKind::NotConnected,
+                "socket has been shut down",
+            ));
+        }
+        #[cfg(target_os = "linux")]
+        {
+            sys::datagram_socket_receive(self, timeout)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(unsupported())
+        }
+    }
+
+    /// Shutdown the socket. Subsequent receives will fail, which allows a receiving
+    /// thread to notice within its timeout.
+    pub fn shutdown(&self) {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Ax25DatagramSocket {
+    fn drop(&mut self) {
+        self.shutdown();
+        #[cfg(target_os = "linux")]
+        {
+            unsafe { libc::close(self.fd) };
+        }
+    }
+}
+
+/// A KISS TNC presented to other software through a pseudo-terminal.
+///
+/// Programs such as `kissattach` or Xastir can open the device at `slave_path()` as if
+/// it were a serial TNC. Frames they send arrive through `receive_frame_timeout` and
+/// frames given to `send_frame` are delivered to them.
+pub struct KissPtyServer {
+    #[cfg(target_os = "linux")]
+    master: i32,
+    #[cfg(target_os = "linux")]
+    slave: i32,
+    path: String,
+    decoder: Mutex<FrameDecoder>,
+    is_shutdown: AtomicBool,
+}
+
+impl KissPtyServer {
+    /// Create a new pseudo-terminal. The slave side is put in raw mode and held open,
+    /// so it is ready for a client to open at any time.
+    pub fn new() -> io::Result<Self> {
+        #[cfg(target_os = "linux")]
+        {
+            sys::pty_new()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(unsupported())
+        }
+    }
+
+    /// Limit how many bytes may be buffered without forming a complete frame.
+    ///
+    /// If the client sends more than this without a frame boundary, the incomplete data
+    /// is discarded and the receive call fails with `io::ErrorKind::InvalidData`.
+    /// Receiving can continue afterwards. The default is `kiss::DEFAULT_MAX_BUFFER`.
+    pub fn with_max_buffer(mut self, max_buffer: usize) -> Self {
+        let decoder = self.decoder.get_mut().unwrap();
+        *decoder = std::mem::take(decoder).with_max_buffer(max_buffer);
+        self
+    }
+
+    /// Path to the device that clients should open, e.g. `/dev/pts/3`
+    pub fn slave_path(&self) -> &str {
+        &self.path
+    }
+
+    /// Send a frame to the connected client.
+    #[allow(unused_variables)]
+    pub fn send_frame(&self, frame: &Ax25Frame) -> io::Result<()> {
+        let encoded = kiss_encode(&frame.to_bytes());
+        #[cfg(target_os = "linux")]
+        {
+            sys::pty_write(self, &encoded)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(unsupported())
+        }
+    }
+
+    /// Wait up to `timeout` for the client to send a frame.
+    ///
+    /// Returns `Ok(None)` if no frame arrived in time. A frame that cannot be parsed, or
+    /// data discarded as described in `with_max_buffer`, results in an error of kind
+    /// `io::ErrorKind::InvalidData`.
+    pub fn receive_frame_timeout(&self, timeout: Duration) -> io::Result<Option<Ax25Frame>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(bytes) = take_decoded(&self.decoder, FrameDecoder::next_frame)? {
+                return Ax25Frame::from_bytes(&bytes)
+                    .map(Some)
+                    .map_err(|e| Error::new(io::ErrorKind::InvalidData, e));
+            }
+            if self.is_shutdown.load(Ordering::SeqCst) {
+                return Err(Error::new(
+                    io::ErrorKind::NotConnected,
+                    "pseudo-terminal has been shut down",
+                ));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            self.read_into_buffer(remaining)?;
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn read_into_buffer(&self, timeout: Duration) -> io::Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            let mut buf = [0u8; 1024];
+            if let Some(n) = sys::pty_read(self, &mut buf, timeout)? {
+                self.decoder.lock().unwrap().push(&buf[..n]);
+            }
+            Ok(())
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(unsupported())
+        }
+    }
+
+    /// Stop receiving. Any blocked receive will return within its timeout.
+    pub fn shutdown(&self) {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for KissPtyServer {
     fn drop(&mut self) {
         self.shutdown();
+        #[cfg(target_os = "linux")]
+        {
+            unsafe {
+                libc::close(self.slave);
+                libc::close(self.master);
+            }
+        }
     }
 }
 
@@ -95,23 +617,29 @@ impl Drop for Ax25RawSocket {
 #[cfg(target_os = "linux")]
 mod sys {
     use super::*;
+    use ax25::frame::{FrameContent, ProtocolIdentifier, RouteEntry, UnnumberedInformation};
     use libc::{
-        c_char, c_int, c_ulong, c_void, close, recvfrom, sendto, sockaddr_ll, socket, socklen_t,
-        AF_AX25, AF_PACKET, SOCK_RAW,
+        bind, c_char, c_int, c_ulong, c_void, close, connect, recv, recvfrom, send, sendto,
+        setsockopt, sockaddr_ll, socket, socklen_t, timeval, AF_AX25, AF_PACKET, MSG_TRUNC,
+        SHUT_RDWR, SOCK_DGRAM, SOCK_RAW, SOCK_SEQPACKET, SOL_SOCKET, SO_RCVTIMEO,
     };
     use std::fs::File;
-    use std::io::{BufRead, BufReader};
+    use std::io::{BufRead, BufReader, ErrorKind};
     use std::mem;
+    use std::time::Instant;
 
     const ETH_P_AX25: u16 = 0x0002; // from if_ether.h for SOCK_RAW
     const SIOCGIFHWADDR: c_ulong = 0x8927; // from sockios.h in the linux kernel
     const SIOCGIFINDEX: c_ulong = 0x8933;
+    const SOL_AX25: c_int = 257; // from ax25.h
+    const AX25_PIDINCL: c_int = 8;
 
     pub(crate) fn socket_new() -> io::Result<Ax25RawSocket> {
         match unsafe { socket(AF_PACKET, SOCK_RAW, ETH_P_AX25.to_be() as i32) } {
             -1 => Err(Error::last_os_error()),
             fd => Ok(Ax25RawSocket {
                 fd,
+                receive_buffer: AtomicUsize::new(DEFAULT_RECEIVE_BUFFER),
                 is_shutdown: AtomicBool::new(false),
             }),
         }
@@ -124,29 +652,56 @@ mod sys {
         }
     }
 
-    pub(crate) fn socket_list_ax25_interfaces(socket: &Ax25RawSocket) -> io::Result<Vec<NetDev>> {
+    pub(crate) fn list_interfaces() -> Result<Vec<InterfaceInfo>, LinuxError> {
         let dev_file = File::open("/proc/net/dev")?;
-        let mut devices: Vec<NetDev> = Vec::new();
+        // Any socket will do for querying interfaces, so use one that needs no privileges
+        let fd = match unsafe { socket(libc::AF_INET, libc::SOCK_DGRAM, 0) } {
+            -1 => return Err(Error::last_os_error().into()),
+            fd => fd,
+        };
+        let mut devices: Vec<InterfaceInfo> = Vec::new();
         let reader = BufReader::new(dev_file);
         let lines = reader.lines();
         for l in lines.skip(2).flatten() {
             let device_name = l.trim().split(':').next().unwrap();
-            if let Some(net_dev) = get_ax25_netdev(device_name, socket.fd) {
-                devices.push(net_dev);
+            match get_ax25_netdev(device_name, fd) {
+                Ok(Some(net_dev)) => devices.push(net_dev),
+                Ok(None) => (),
+                Err(e) => {
+                    unsafe { close(fd) };
+                    return Err(e);
+                }
             }
         }
+        unsafe { close(fd) };
         Ok(devices)
     }
 
+    pub(crate) fn socket_bind_interface(socket: &Ax25RawSocket, ifindex: i32) -> io::Result<()> {
+        let sa = sockaddr_ll {
+            sll_family: AF_PACKET as u16,
+            sll_protocol: ETH_P_AX25.to_be(),
+            sll_ifindex: ifindex,
+            sll_hatype: 0,
+            sll_pkttype: 0,
+            sll_halen: 0,
+            sll_addr: [0; 8],
+        };
+        match unsafe {
+            let sa_ptr = &sa as *const libc::sockaddr_ll as *const libc::sockaddr;
+            bind(socket.fd, sa_ptr, mem::size_of_val(&sa) as socklen_t)
+        } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
     pub(crate) fn socket_send_frame(
         socket: &Ax25RawSocket,
         frame: &[u8],
         ifindex: i32,
     ) -> io::Result<()> {
-        // The Linux interface demands a single null byte prefix on the actual packet
-        let mut prefixed_frame: Vec<u8> = Vec::with_capacity(frame.len() + 1);
-        prefixed_frame.push(0);
-        prefixed_frame.extend(frame.iter().cloned());
+        let prefixed_frame = add_packet_prefix(frame);
 
         let sa = sockaddr_ll {
             sll_family: AF_PACKET as u16,
@@ -177,41 +732,440 @@ mod sys {
     pub(crate) fn socket_receive_frame(
         socket: &Ax25RawSocket,
         ifindex: i32,
-    ) -> io::Result<Vec<u8>> {
-        let mut buf: [u8; 1024] = [0; 1024];
-        let mut addr_struct: sockaddr_ll = unsafe { mem::zeroed() };
-        let mut len: usize;
+        timeout: Option<Duration>,
+    ) -> io::Result<Option<Vec<u8>>> {
+        let mut buf = Vec::new();
+        // Copy out so the caller does not hold on to the whole receive buffer
+        Ok(socket_receive_frame_into(socket, ifindex, timeout, &mut buf)?.then(|| buf.to_vec()))
+    }
+
+    pub(crate) fn socket_receive_frame_into(
+        socket: &Ax25RawSocket,
+        ifindex: i32,
+        timeout: Option<Duration>,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<bool> {
+        let deadline = timeout.map(|t| Instant::now() + t);
         loop {
-            unsafe {
-                let sa_ptr = &mut addr_struct as *mut libc::sockaddr_ll as *mut libc::sockaddr;
-                let mut sa_in_sz: socklen_t = mem::size_of::<sockaddr_ll>() as socklen_t;
-                len = match recvfrom(
-                    socket.fd,
-                    buf.as_mut_ptr() as *mut c_void,
-                    buf.len(),
-                    0,
-                    sa_ptr,
-                    &mut sa_in_sz,
-                ) {
-                    -1 => return Err(Error::last_os_error()),
-                    len => len as usize,
-                };
-                // We actually get packets from all interfaces when receiving this way
-                // Only report ones from the interface we're interested in
-                if addr_struct.sll_ifindex == ifindex {
-                    break;
+            let remaining = match deadline {
+                Some(deadline) => match deadline.saturating_duration_since(Instant::now()) {
+                    d if d.is_zero() => return Ok(false),
+                    d => Some(d),
+                },
+                None => None,
+            };
+            set_receive_timeout(socket.fd, remaining)?;
+            match socket_recv(socket, 0, buf)? {
+                Some(from) if from == ifindex => return Ok(true),
+                Some(_) => continue,
+                None => return Ok(false),
+            }
+        }
+    }
+
+    pub(crate) fn socket_try_receive_frame(
+        socket: &Ax25RawSocket,
+        ifindex: i32,
+    ) -> io::Result<Option<Vec<u8>>> {
+        let mut buf = Vec::new();
+        loop {
+            match socket_recv(socket, libc::MSG_DONTWAIT, &mut buf)? {
+                Some(from) if from == ifindex => return Ok(Some(buf.to_vec())),
+                Some(_) => continue,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Receive one frame from any interface into `buf`, returning the index of the
+    /// interface it came from. Returns `Ok(None)` if the receive timed out or would block.
+    fn socket_recv(
+        socket: &Ax25RawSocket,
+        flags: c_int,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<Option<i32>> {
+        let mut addr_struct: sockaddr_ll = unsafe { mem::zeroed() };
+        let receive_buffer = socket.receive_buffer.load(Ordering::SeqCst);
+        if !recv_from(socket.fd, receive_buffer, flags, buf, &mut addr_struct)? {
+            return Ok(None);
+        }
+        // Unless the socket is bound to an interface we get packets from all of them,
+        // so the caller must check that this is the one it is interested in. This also
+        // covers any that were queued before binding.
+        strip_packet_prefix(buf);
+        Ok(Some(addr_struct.sll_ifindex))
+    }
+
+    /// Receive one packet of at most `receive_buffer` bytes into `buf`, and its sender
+    /// into `from`. Returns `Ok(false)` if the receive timed out or would block.
+    fn recv_from<A>(
+        fd: c_int,
+        receive_buffer: usize,
+        flags: c_int,
+        buf: &mut Vec<u8>,
+        from: &mut A,
+    ) -> io::Result<bool> {
+        buf.resize(receive_buffer, 0);
+        let len = unsafe {
+            let mut from_len = mem::size_of::<A>() as socklen_t;
+            match recvfrom(
+                fd,
+                buf.as_mut_ptr() as *mut c_void,
+                buf.len(),
+                // Report the full length of the packet even if it did not fit
+                flags | MSG_TRUNC,
+                from as *mut A as *mut libc::sockaddr,
+                &mut from_len,
+            ) {
+                -1 => {
+                    let e = Error::last_os_error();
+                    return match e.kind() {
+                        ErrorKind::WouldBlock | ErrorKind::TimedOut => Ok(false),
+                        _ => Err(e),
+                    };
+                }
+                len => len as usize,
+            }
+        };
+        if len > buf.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "discarded a {} byte frame larger than the {} byte receive buffer",
+                    len,
+                    buf.len()
+                ),
+            ));
+        }
+        buf.truncate(len);
+        Ok(true)
+    }
+
+    fn set_receive_timeout(fd: c_int, timeout: Option<Duration>) -> io::Result<()> {
+        // A zero timeval means block forever
+        let tv = match timeout {
+            Some(t) => timeval {
+                tv_sec: t.as_secs() as libc::time_t,
+                tv_usec: t.subsec_micros().max(1) as libc::suseconds_t,
+            },
+            None => timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+        };
+        match unsafe {
+            setsockopt(
+                fd,
+                SOL_SOCKET,
+                SO_RCVTIMEO,
+                &tv as *const timeval as *const c_void,
+                mem::size_of::<timeval>() as socklen_t,
+            )
+        } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    const AX25_MAX_DIGIS: usize = 8;
+
+    #[derive(Clone, Copy, Default)]
+    #[repr(C)]
+    pub(super) struct ax25_address {
+        ax25_call: [u8; 7],
+    }
+
+    impl From<&Address> for ax25_address {
+        fn from(address: &Address) -> Self {
+            let mut ax25_call = [b' ' << 1; 7];
+            for (d, s) in ax25_call.iter_mut().zip(address.callsign().as_bytes()) {
+                *d = s << 1;
+            }
+            ax25_call[6] = (address.ssid() << 1) & 0x1e;
+            ax25_address { ax25_call }
+        }
+    }
+
+    impl ax25_address {
+        pub(super) fn to_address(self) -> Option<Address> {
+            let callsign: String = self.ax25_call[..6]
+                .iter()
+                .map(|&b| (b >> 1) as char)
+                .collect();
+            Address::new(callsign.trim_end(), (self.ax25_call[6] >> 1) & 0x0f).ok()
+        }
+    }
+
+    #[derive(Default)]
+    #[repr(C)]
+    struct sockaddr_ax25 {
+        sax25_family: u16,
+        sax25_call: ax25_address,
+        sax25_ndigis: c_int,
+    }
+
+    #[derive(Default)]
+    #[repr(C)]
+    struct full_sockaddr_ax25 {
+        fsa_ax25: sockaddr_ax25,
+        fsa_digipeater: [ax25_address; AX25_MAX_DIGIS],
+    }
+
+    pub(crate) fn connected_socket_connect(
+        port: &Address,
+        local: &Address,
+        remote: &Address,
+    ) -> Result<Ax25ConnectedSocket, LinuxError> {
+        let fd = match unsafe { socket(AF_AX25, SOCK_SEQPACKET, 0) } {
+            -1 => return Err(Error::last_os_error().into()),
+            fd => fd,
+        };
+        // Construct it now so the fd is closed if anything below fails
+        let socket = Ax25ConnectedSocket {
+            fd,
+            is_shutdown: AtomicBool::new(false),
+        };
+
+        bind_ax25(fd, port, local)?;
+
+        let mut remote_sa = full_sockaddr_ax25::default();
+        remote_sa.fsa_ax25.sax25_family = AF_AX25 as u16;
+        remote_sa.fsa_ax25.sax25_call = remote.into();
+        if unsafe {
+            connect(
+                fd,
+                &remote_sa as *const full_sockaddr_ax25 as *const libc::sockaddr,
+                mem::size_of::<full_sockaddr_ax25>() as socklen_t,
+            )
+        } == -1
+        {
+            return Err(Error::last_os_error().into());
+        }
+        Ok(socket)
+    }
+
+    /// Bind an AF_AX25 socket to `local` on the interface whose callsign is `port`.
+    fn bind_ax25(fd: c_int, port: &Address, local: &Address) -> Result<(), LinuxError> {
+        // Binding with a single "digipeater" selects the interface with that callsign
+        let mut local_sa = full_sockaddr_ax25::default();
+        local_sa.fsa_ax25.sax25_family = AF_AX25 as u16;
+        local_sa.fsa_ax25.sax25_call = local.into();
+        local_sa.fsa_ax25.sax25_ndigis = 1;
+        local_sa.fsa_digipeater[0] = port.into();
+        if unsafe {
+            bind(
+                fd,
+                &local_sa as *const full_sockaddr_ax25 as *const libc::sockaddr,
+                mem::size_of::<full_sockaddr_ax25>() as socklen_t,
+            )
+        } == -1
+        {
+            let e = Error::last_os_error();
+            if e.raw_os_error() == Some(libc::EADDRNOTAVAIL) {
+                return Err(LinuxError::InterfaceNotFound {
+                    callsign: port.to_string(),
+                });
+            }
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn datagram_socket_bind(
+        port: &Address,
+        local: &Address,
+    ) -> Result<Ax25DatagramSocket, LinuxError> {
+        let fd = match unsafe { socket(AF_AX25, SOCK_DGRAM, 0) } {
+            -1 => return Err(Error::last_os_error().into()),
+            fd => fd,
+        };
+        let socket = Ax25DatagramSocket {
+            fd,
+            local: local.clone(),
+            receive_buffer: AtomicUsize::new(DEFAULT_RECEIVE_BUFFER),
+            is_shutdown: AtomicBool::new(false),
+        };
+        // Have the PID delivered as the first byte of each datagram
+        let on: c_int = 1;
+        if unsafe {
+            setsockopt(
+                fd,
+                SOL_AX25,
+                AX25_PIDINCL,
+                &on as *const c_int as *const c_void,
+                mem::size_of::<c_int>() as socklen_t,
+            )
+        } == -1
+        {
+            return Err(Error::last_os_error().into());
+        }
+        bind_ax25(fd, port, local)?;
+        Ok(socket)
+    }
+
+    pub(crate) fn datagram_socket_receive(
+        socket: &Ax25DatagramSocket,
+        timeout: Option<Duration>,
+    ) -> io::Result<Option<Vec<u8>>> {
+        set_receive_timeout(socket.fd, timeout)?;
+        let mut buf = Vec::new();
+        let mut from = full_sockaddr_ax25::default();
+        // Allow for the PID byte in front of the information field
+        let receive_buffer = socket.receive_buffer.load(Ordering::SeqCst) + 1;
+        if !recv_from(socket.fd, receive_buffer, 0, &mut buf, &mut from)? {
+            return Ok(None);
+        }
+        let invalid = |what| Error::new(ErrorKind::InvalidData, what);
+        let (&pid, info) = buf
+            .split_first()
+            .ok_or_else(|| invalid("datagram has no PID"))?;
+        let source = from
+            .fsa_ax25
+            .sax25_call
+            .to_address()
+            .ok_or_else(|| invalid("datagram has an invalid source address"))?;
+        let ndigis = (from.fsa_ax25.sax25_ndigis.max(0) as usize).min(AX25_MAX_DIGIS);
+        let route = from.fsa_digipeater[..ndigis]
+            .iter()
+            .filter_map(|digi| digi.to_address())
+            .map(|repeater| RouteEntry {
+                repeater,
+                has_repeated: true,
+            })
+            .collect();
+        let mut frame = Ax25Frame::new(
+            source,
+            socket.local.clone(),
+            FrameContent::UnnumberedInformation(UnnumberedInformation {
+                pid: ProtocolIdentifier::from_byte(pid),
+                info: info.to_vec(),
+                poll_or_final: false,
+            }),
+        );
+        frame.route = route;
+        Ok(Some(frame.to_bytes()))
+    }
+
+    pub(crate) fn connected_socket_send(
+        socket: &Ax25ConnectedSocket,
+        data: &[u8],
+    ) -> io::Result<()> {
+        match unsafe { send(socket.fd, data.as_ptr() as *const c_void, data.len(), 0) } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn connected_socket_recv(socket: &Ax25ConnectedSocket) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0u8; 4096];
+        match unsafe { recv(socket.fd, buf.as_mut_ptr() as *mut c_void, buf.len(), 0) } {
+            -1 => Err(Error::last_os_error()),
+            len => {
+                buf.truncate(len as usize);
+                Ok(buf)
+            }
+        }
+    }
+
+    pub(crate) fn connected_socket_shutdown(socket: &Ax25ConnectedSocket) -> io::Result<()> {
+        match unsafe { libc::shutdown(socket.fd, SHUT_RDWR) } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn pty_new() -> io::Result<KissPtyServer> {
+        let master = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY) };
+        if master == -1 {
+            return Err(Error::last_os_error());
+        }
+        let close_with_error = |fds: &[c_int]| {
+            let e = Error::last_os_error();
+            for &fd in fds {
+                unsafe { close(fd) };
+            }
+            Err(e)
+        };
+        if unsafe { libc::grantpt(master) } == -1 || unsafe { libc::unlockpt(master) } == -1 {
+            return close_with_error(&[master]);
+        }
+        let mut name = [0 as c_char; 64];
+        if unsafe { libc::ptsname_r(master, name.as_mut_ptr(), name.len()) } != 0 {
+            return close_with_error(&[master]);
+        }
+        let slave = unsafe { libc::open(name.as_ptr(), libc::O_RDWR | libc::O_NOCTTY) };
+        if slave == -1 {
+            return close_with_error(&[master]);
+        }
+        // Raw mode stops the line discipline echoing or translating KISS bytes
+        let mut termios: libc::termios = unsafe { mem::zeroed() };
+        if unsafe { libc::tcgetattr(slave, &mut termios) } == -1 {
+            return close_with_error(&[slave, master]);
+        }
+        unsafe { libc::cfmakeraw(&mut termios) };
+        if unsafe { libc::tcsetattr(slave, libc::TCSANOW, &termios) } == -1 {
+            return close_with_error(&[slave, master]);
+        }
+        let path = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) }
+            .to_string_lossy()
+            .into_owned();
+        Ok(KissPtyServer {
+            master,
+            slave,
+            path,
+            decoder: Mutex::new(FrameDecoder::new()),
+            is_shutdown: AtomicBool::new(false),
+        })
+    }
+
+    /// Read from the master side, returning `Ok(None)` if nothing arrives within `timeout`.
+    pub(crate) fn pty_read(
+        pty: &KissPtyServer,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> io::Result<Option<usize>> {
+        let mut pollfd = libc::pollfd {
+            fd: pty.master,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = timeout.as_millis().clamp(1, c_int::MAX as u128) as c_int;
+        match unsafe { libc::poll(&mut pollfd, 1, timeout_ms) } {
+            -1 => {
+                let e = Error::last_os_error();
+                if e.kind() == ErrorKind::Interrupted {
+                    Ok(None)
+                } else {
+                    Err(e)
+                }
+            }
+            0 => Ok(None),
+            _ => {
+                match unsafe { libc::read(pty.master, buf.as_mut_ptr() as *mut c_void, buf.len()) }
+                {
+                    -1 => Err(Error::last_os_error()),
+                    n => Ok(Some(n as usize)),
                 }
             }
         }
-        let valid_buf = &buf[0..len];
+    }
 
-        // In practice AF_PACKET gives us one leading one null byte
-        // These are unhelpful so we will skip all leading null bytes
-        let filtered: Vec<u8> = valid_buf.iter().skip_while(|&c| *c == 0).cloned().collect();
-        Ok(filtered)
+    pub(crate) fn pty_write(pty: &KissPtyServer, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            match unsafe { libc::write(pty.master, data.as_ptr() as *const c_void, data.len()) } {
+                -1 => {
+                    let e = Error::last_os_error();
+                    if e.kind() != ErrorKind::Interrupted {
+                        return Err(e);
+                    }
+                }
+                n => data = &data[n as usize..],
+            }
+        }
+        Ok(())
     }
 
-    fn get_ax25_netdev(name: &str, fd: i32) -> Option<NetDev> {
+    fn get_ax25_netdev(name: &str, fd: i32) -> Result<Option<InterfaceInfo>, LinuxError> {
         let mut req = ifreq::default();
         let if_name = name.to_owned();
         for (d, s) in req.ifr_name.iter_mut().zip(if_name.as_bytes()) {
@@ -219,25 +1173,42 @@ mod sys {
         }
 
         if unsafe { ioctl(fd, SIOCGIFHWADDR, &mut req) } == -1 {
-            return None;
+            return match Error::last_os_error() {
+                // The interface went away after it was listed, or has no hardware address
+                e if e.raw_os_error() == Some(libc::ENODEV)
+                    || e.raw_os_error() == Some(libc::EINVAL) =>
+                {
+                    Ok(None)
+                }
+                e => Err(LinuxError::IoctlFailed {
+                    request: "SIOCGIFHWADDR",
+                    interface: name.to_owned(),
+                    source: e,
+                }),
+            };
         }
         if req.data.address_family() as i32 != AF_AX25 {
-            return None;
+            return Ok(None);
         }
         let hw_addr = match req.data.ax25_address() {
-            Some(addr) => addr,
-            None => return None,
+            Some(hw_addr) => hw_addr,
+            None => return Ok(None),
         };
 
         if unsafe { ioctl(fd, SIOCGIFINDEX, &mut req) } == -1 {
-            return None;
+            return Err(LinuxError::IoctlFailed {
+                request: "SIOCGIFINDEX",
+                interface: name.to_owned(),
+                source: Error::last_os_error(),
+            });
         }
         let ifindex = req.data.ifindex();
 
-        Some(NetDev {
-            name: hw_addr,
+        Ok(Some(InterfaceInfo {
+            name: name.to_owned(),
+            callsign: hw_addr,
             ifindex,
-        })
+        }))
     }
 
     extern "C" {
@@ -287,3 +1258,190 @@ mod sys {
         }
     }
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_kiss_pty_server() {
+    use crate::test_util::test_frame;
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+
+    let server = KissPtyServer::new().unwrap();
+    let mut client = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(server.slave_path())
+        .unwrap();
+    let frame = test_frame(b"hello");
+
+    client.write_all(&kiss_encode(&frame.to_bytes())).unwrap();
+    let received = server
+        .receive_frame_timeout(Duration::from_secs(5))
+        .unwrap();
+    assert_eq!(received, Some(frame.clone()));
+
+    server.send_frame(&frame).unwrap();
+    let expected = kiss_encode(&frame.to_bytes());
+    let mut buf = vec![0u8; expected.len()];
+    client.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, expected);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_kiss_pty_server_overflow() {
+    use crate::test_util::test_frame;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    let server = KissPtyServer::new().unwrap().with_max_buffer(64);
+    let mut client = OpenOptions::new()
+        .write(true)
+        .open(server.slave_path())
+        .unwrap();
+    let frame = test_frame(b"hello");
+
+    // Garbage with no frame boundary is discarded rather than kept forever
+    client.write_all(&[0x55; 200]).unwrap();
+    let e = server
+        .receive_frame_timeout(Duration::from_secs(5))
+        .unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+
+    client.write_all(&kiss_encode(&frame.to_bytes())).unwrap();
+    let received = server
+        .receive_frame_timeout(Duration::from_secs(5))
+        .unwrap();
+    assert_eq!(received, Some(frame));
+}
+
+#[test]
+fn test_interface_has_callsign() {
+    let iface = InterfaceInfo {
+        name: "ax0".to_string(),
+        callsign: "VK7NTK-0".to_string(),
+        ifindex: 3,
+    };
+    assert!(iface.has_callsign("VK7NTK"));
+    assert!(iface.has_callsign("vk7ntk-0"));
+    assert!(!iface.has_callsign("VK7NTK-1"));
+    let iface = InterfaceInfo {
+        callsign: "VK7NTK-2".to_string(),
+        ..iface
+    };
+    assert!(iface.has_callsign("vk7ntk-2"));
+    assert!(!iface.has_callsign("VK7NTK"));
+}
+
+#[test]
+fn test_linux_error_from_io() {
+    let e = LinuxError::from(Error::from(io::ErrorKind::PermissionDenied));
+    assert!(matches!(e, LinuxError::PermissionDenied { .. }));
+    assert!(e.to_string().contains("CAP_NET_ADMIN"));
+    let e = LinuxError::from(Error::from(io::ErrorKind::AddrInUse));
+    assert!(matches!(e, LinuxError::Io { .. }));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_packet_prefix() {
+    // Only the one byte added by the kernel is removed, even if the frame itself is odd
+    let frame = vec![0x00, 0x96, 0x01];
+    let packet = add_packet_prefix(&frame);
+    assert_eq!(packet, vec![0x00, 0x00, 0x96, 0x01]);
+    let mut stripped = packet.clone();
+    strip_packet_prefix(&mut stripped);
+    assert_eq!(stripped, frame);
+    let mut unprefixed = vec![0x96, 0x01];
+    strip_packet_prefix(&mut unprefixed);
+    assert_eq!(unprefixed, vec![0x96, 0x01]);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_raw_socket_large_frames() {
+    // A datagram socket pair stands in for AF_PACKET, which needs privileges
+    let mut fds = [0; 2];
+    assert_eq!(
+        unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_DGRAM, 0, fds.as_mut_ptr()) },
+        0
+    );
+    let socket = Ax25RawSocket {
+        fd: fds[0],
+        receive_buffer: AtomicUsize::new(DEFAULT_RECEIVE_BUFFER),
+        is_shutdown: AtomicBool::new(false),
+    };
+    let send = |packet: &[u8]| unsafe {
+        libc::send(
+            fds[1],
+            packet.as_ptr() as *const libc::c_void,
+            packet.len(),
+            0,
+        )
+    };
+    let frame: Vec<u8> = (0..1500).map(|i| (i % 251) as u8 + 1).collect();
+
+    send(&add_packet_prefix(&frame));
+    assert_eq!(socket.try_receive_frame(0).unwrap(), Some(frame.clone()));
+
+    // Frames are not returned with the spare capacity of the receive buffer
+    send(&add_packet_prefix(&frame[..10]));
+    let received = socket.try_receive_frame(0).unwrap().unwrap();
+    assert_eq!(received, frame[..10]);
+    assert!(received.capacity() < DEFAULT_RECEIVE_BUFFER);
+
+    // Too large for the buffer, so it is reported rather than truncated
+    socket.set_receive_buffer_size(1024);
+    send(&add_packet_prefix(&frame));
+    send(&add_packet_prefix(&frame[..10]));
+    let e = socket.try_receive_frame(0).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(
+        socket.try_receive_frame(0).unwrap(),
+        Some(frame[..10].to_vec())
+    );
+    unsafe { libc::close(fds[1]) };
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_datagram_socket_large_frames() {
+    let mut fds = [0; 2];
+    assert_eq!(
+        unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_DGRAM, 0, fds.as_mut_ptr()) },
+        0
+    );
+    let socket = Ax25DatagramSocket {
+        fd: fds[0],
+        local: "VK7NTK".parse().unwrap(),
+        receive_buffer: AtomicUsize::new(DEFAULT_RECEIVE_BUFFER),
+        is_shutdown: AtomicBool::new(false),
+    };
+    socket.set_receive_buffer_size(1024);
+    let packet = vec![0xf0; 1500];
+    unsafe {
+        libc::send(
+            fds[1],
+            packet.as_ptr() as *const libc::c_void,
+            packet.len(),
+            0,
+        )
+    };
+    // Too large for the buffer, so it is reported rather than truncated
+    let e = socket
+        .receive_frame(Some(Duration::from_secs(1)))
+        .unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    assert!(e.to_string().contains("receive buffer"));
+    unsafe { libc::close(fds[1]) };
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_kernel_address_round_trip() {
+    for address in ["VK7NTK-15", "A1B", "ID"] {
+        let address: Address = address.parse().unwrap();
+        let kernel = sys::ax25_address::from(&address);
+        assert_eq!(kernel.to_address(), Some(address));
+    }
+}
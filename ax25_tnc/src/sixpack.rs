@@ -0,0 +1,352 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::prelude::*;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Sent by the host to find a TNC on the line
+pub const CMD_INIT: u8 = 0xE8;
+/// The TNC's answer to `CMD_INIT`
+pub const FOUND_TNC: u8 = 0xE9;
+
+/// Default delay between keying the transmitter and sending data, in units of 10 ms,
+/// matching the Linux 6pack driver
+pub const DEFAULT_TX_DELAY: u8 = 25;
+
+/// High bit set on priority commands, such as DCD and transmit counter updates
+const PRIO_CMD_MASK: u8 = 0x80;
+/// Bit set on standard commands, which are distinguished by `STD_CMD_TYPE_MASK`
+const STD_CMD_MASK: u8 = 0x40;
+/// The bits of a standard command byte which identify the command, leaving the channel
+const STD_CMD_TYPE_MASK: u8 = 0xF8;
+/// Start or end of a frame on channel 0
+const SEOF: u8 = 0x40;
+/// Priority command sent ahead of a frame to transmit
+const TX_START: u8 = PRIO_CMD_MASK | 0x20;
+/// The sum of the bytes of a frame, including its checksum
+const CHECKSUM_TOTAL: u8 = 0xFF;
+/// Largest decoded frame accepted, including the leading byte and checksum, which is the
+/// limit used by the Linux 6pack driver
+const MAX_DECODED_LEN: usize = 400;
+
+/// How long to wait before reading again when a stream has no data
+const STREAM_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Wrap an AX.25 frame in 6PACK framing so it can be sent to a TNC.
+///
+/// The frame is preceded by `tx_delay`, in units of 10 ms, and followed by a checksum.
+/// These bytes are packed six bits at a time, then delimited by start and end of frame
+/// commands.
+pub fn sixpack_encode(frame: &[u8], tx_delay: u8) -> Vec<u8> {
+    let mut contents = Vec::with_capacity(frame.len() + 2);
+    contents.push(tx_delay);
+    contents.extend_from_slice(frame);
+    let sum = contents.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
+    contents.push(CHECKSUM_TOTAL.wrapping_sub(sum));
+
+    let mut encoded = Vec::with_capacity(contents.len() * 4 / 3 + 5);
+    encoded.push(TX_START);
+    encoded.push(SEOF);
+    for chunk in contents.chunks(3) {
+        encoded.push(chunk[0] & 0x3f);
+        let mut next = (chunk[0] >> 2) & 0x30;
+        if let Some(&b) = chunk.get(1) {
+            encoded.push(next | (b & 0x0f));
+            next = (b >> 2) & 0x3c;
+        }
+        if let Some(&b) = chunk.get(2) {
+            encoded.push(next | (b & 0x03));
+            next = b >> 2;
+        }
+        encoded.push(next);
+    }
+    encoded.push(SEOF);
+    encoded
+}
+
+/// Incremental decoder for 6PACK data received from a TNC.
+///
+/// Bytes are added with `push` as they arrive and complete data frames are collected
+/// with `next_frame`. Frames with a bad checksum are dropped, as are frames longer than
+/// the Linux 6pack driver accepts. Priority commands, such as
+/// DCD and transmit counter updates, are ignored.
+#[derive(Debug, Default)]
+pub struct SixpackDecoder {
+    /// Six-bit values waiting to be combined into bytes
+    raw: Vec<u8>,
+    /// Bytes of the frame being received
+    decoded: Vec<u8>,
+    /// The frame being received has grown too long and will be dropped when it ends
+    overflow: bool,
+    frames: VecDeque<Vec<u8>>,
+    bad_checksums: u64,
+}
+
+impl SixpackDecoder {
+    /// Create an empty decoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add bytes received from the TNC.
+    pub fn push(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            if b & PRIO_CMD_MASK != 0 {
+                continue;
+            }
+            if b & STD_CMD_MASK != 0 {
+                if b & STD_CMD_TYPE_MASK == SEOF {
+                    self.end_frame();
+                }
+                continue;
+            }
+            self.raw.push(b);
+            if self.raw.len() == 4 {
+                self.unpack();
+            }
+        }
+    }
+
+    /// Take the next complete data frame, without its leading byte and checksum.
+    pub fn next_frame(&mut self) -> Option<Vec<u8>> {
+        self.frames.pop_front()
+    }
+
+    /// How many frames have been dropped because their checksum did not match.
+    pub fn bad_checksums(&self) -> u64 {
+        self.bad_checksums
+    }
+
+    /// Combine up to four six-bit values into as many whole bytes as they carry.
+    fn unpack(&mut self) {
+        let raw = &self.raw;
+        let mut bytes = [0u8; 3];
+        bytes[0] = raw[0] | ((raw.get(1).unwrap_or(&0) << 2) & 0xc0);
+        if raw.len() > 1 {
+            bytes[1] = (raw[1] & 0x0f) | ((raw.get(2).unwrap_or(&0) << 2) & 0xf0);
+        }
+        if raw.len() > 2 {
+            bytes[2] = (raw[2] & 0x03) | (raw.get(3).unwrap_or(&0) << 2);
+        }
+        let count = raw.len() - 1;
+        self.raw.clear();
+        if self.overflow {
+            return;
+        }
+        self.decoded.extend_from_slice(&bytes[..count]);
+        if self.decoded.len() > MAX_DECODED_LEN {
+            self.decoded.clear();
+            self.overflow = true;
+        }
+    }
+
+    fn end_frame(&mut self) {
+        if self.overflow {
+            self.raw.clear();
+            self.overflow = false;
+            return;
+        }
+        // A frame starts with the same command that ends one
+        if self.raw.is_empty() && self.decoded.is_empty() {
+            return;
+        }
+        // A single leftover value is too short to hold a byte
+        let valid = self.raw.len() != 1;
+        if self.raw.len() > 1 {
+            self.unpack();
+        }
+        self.raw.clear();
+        let decoded = std::mem::take(&mut self.decoded);
+        let sum = decoded.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
+        if valid && decoded.len() > 2 && sum == CHECKSUM_TOTAL {
+            self.frames
+                .push_back(decoded[1..decoded.len() - 1].to_vec());
+        } else {
+            self.bad_checksums += 1;
+        }
+    }
+}
+
+/// 6PACK framing over a serial port or other caller-supplied streams.
+///
+/// Reads must not block indefinitely, as for the streams used with KISS.
+pub(crate) struct SixpackInterface {
+    reader: Mutex<Box<dyn Read + Send>>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    decoder: Mutex<SixpackDecoder>,
+    tx_delay: AtomicU8,
+    is_shutdown: AtomicBool,
+}
+
+impl SixpackInterface {
+    /// Use separate handles for reading and writing, announcing ourselves to the TNC.
+    pub(crate) fn new_split<R, W>(reader: R, mut writer: W) -> io::Result<SixpackInterface>
+    where
+        R: Read + Send + 'static,
+        W: Write + Send + 'static,
+    {
+        writer.write_all(&[CMD_INIT])?;
+        writer.flush()?;
+        Ok(SixpackInterface {
+            reader: Mutex::new(Box::new(reader)),
+            writer: Mutex::new(Box::new(writer)),
+            decoder: Mutex::new(SixpackDecoder::new()),
+            tx_delay: AtomicU8::new(DEFAULT_TX_DELAY),
+            is_shutdown: AtomicBool::new(false),
+        })
+    }
+
+    /// Wait up to `timeout` for a complete data frame to be received from the stream.
+    pub(crate) fn receive_frame_timeout(&self, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0u8; 1024];
+        loop {
+            if let Some(frame) = self.decoder.lock().unwrap().next_frame() {
+                return Ok(Some(frame));
+            }
+            if self.is_shutdown.load(Ordering::SeqCst) {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "interface has been shut down",
+                ));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            let result = self.reader.lock().unwrap().read(&mut buf);
+            match result {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => self.decoder.lock().unwrap().push(&buf[..n]),
+                Err(e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut =>
+                {
+                    thread::sleep(STREAM_POLL_INTERVAL.min(remaining));
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Send a data frame to the TNC for transmission.
+    pub(crate) fn send_frame(&self, frame: &[u8]) -> io::Result<()> {
+        let encoded = sixpack_encode(frame, self.tx_delay.load(Ordering::SeqCst));
+        let mut writer = self.writer.lock().unwrap();
+        writer.write_all(&encoded)?;
+        writer.flush()
+    }
+
+    /// Set the delay sent with each following frame, in units of 10 ms.
+    pub(crate) fn set_tx_delay(&self, units: u8) {
+        self.tx_delay.store(units, Ordering::SeqCst);
+    }
+
+    /// Stop receiving. A receive will only notice once its read from the stream returns.
+    pub(crate) fn shutdown(&self) {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_encode() {
+    assert_eq!(
+        sixpack_encode(&[0x01], DEFAULT_TX_DELAY),
+        vec![TX_START, SEOF, 0x19, 0x01, 0x01, 0x39, SEOF]
+    );
+    // Every packed value leaves the command bits clear
+    let encoded = sixpack_encode(&[0xff; 10], 0xff);
+    assert!(encoded[2..encoded.len() - 1].iter().all(|&b| b < 0x40));
+}
+
+#[test]
+fn test_round_trip() {
+    // Cover each way the contents can fall across groups of three bytes
+    for len in 1..=20 {
+        let frame: Vec<u8> = (0..len).map(|i| (i * 37 + 200) as u8).collect();
+        let mut decoder = SixpackDecoder::new();
+        decoder.push(&sixpack_encode(&frame, DEFAULT_TX_DELAY));
+        assert_eq!(decoder.next_frame(), Some(frame));
+        assert_eq!(decoder.next_frame(), None);
+    }
+}
+
+#[test]
+fn test_decode_across_pushes_with_commands() {
+    let mut decoder = SixpackDecoder::new();
+    let encoded = sixpack_encode(b"hello", 0);
+    decoder.push(&[FOUND_TNC]);
+    for (i, b) in encoded.iter().enumerate() {
+        decoder.push(&[*b]);
+        // A DCD status update in the middle of a frame changes nothing
+        if i == 4 {
+            decoder.push(&[0x98]);
+        }
+    }
+    assert_eq!(decoder.next_frame(), Some(b"hello".to_vec()));
+}
+
+#[test]
+fn test_bad_checksum_dropped() {
+    let mut decoder = SixpackDecoder::new();
+    let mut bad = sixpack_encode(b"bad", 0);
+    bad[3] ^= 0x01;
+    decoder.push(&bad);
+    decoder.push(&sixpack_encode(b"good", 0));
+    assert_eq!(decoder.next_frame(), Some(b"good".to_vec()));
+    assert_eq!(decoder.next_frame(), None);
+    assert_eq!(decoder.bad_checksums(), 1);
+}
+
+#[test]
+fn test_oversized_frame_dropped() {
+    let mut decoder = SixpackDecoder::new();
+    // Once the leading zeroes are over the limit, the rest looks like a valid frame
+    let mut frame = vec![0; MAX_DECODED_LEN + 1];
+    frame.extend_from_slice(b"\0hi");
+    decoder.push(&sixpack_encode(&frame, 0));
+    decoder.push(&sixpack_encode(b"good", 0));
+    assert_eq!(decoder.next_frame(), Some(b"good".to_vec()));
+    assert_eq!(decoder.next_frame(), None);
+}
+
+#[test]
+fn test_interface_round_trip() {
+    use crate::test_util::local_listener;
+    use std::net::TcpStream;
+
+    let listener = local_listener();
+    let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut tnc, _) = listener.accept().unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(50)))
+        .unwrap();
+    let iface = SixpackInterface::new_split(stream.try_clone().unwrap(), stream).unwrap();
+
+    let mut init = [0u8; 1];
+    tnc.read_exact(&mut init).unwrap();
+    assert_eq!(init, [CMD_INIT]);
+
+    iface.set_tx_delay(30);
+    iface.send_frame(b"out").unwrap();
+    let expected = sixpack_encode(b"out", 30);
+    let mut sent = vec![0u8; expected.len()];
+    tnc.read_exact(&mut sent).unwrap();
+    assert_eq!(sent, expected);
+
+    assert_eq!(
+        iface
+            .receive_frame_timeout(Duration::from_millis(50))
+            .unwrap(),
+        None
+    );
+    tnc.write_all(&[FOUND_TNC]).unwrap();
+    tnc.write_all(&sixpack_encode(b"in", 0)).unwrap();
+    assert_eq!(
+        iface.receive_frame_timeout(Duration::from_secs(5)).unwrap(),
+        Some(b"in".to_vec())
+    );
+}
@@ -0,0 +1,42 @@
+use crate::kiss::TcpKissInterface;
+use crate::tnc::{TcpKissConfig, Tnc, TncAddress};
+use ax25::frame::Ax25Frame;
+use std::net::{TcpListener, TcpStream};
+
+/// A UI frame from VK7NTK-1 to IDENT carrying `info`.
+pub(crate) fn test_frame(info: &[u8]) -> Ax25Frame {
+    Ax25Frame::new_simple_ui_frame(
+        "VK7NTK-1".parse().unwrap(),
+        "IDENT".parse().unwrap(),
+        info.to_vec(),
+    )
+}
+
+/// A listener on a free local port for the connection under test.
+pub(crate) fn local_listener() -> TcpListener {
+    TcpListener::bind("127.0.0.1:0").unwrap()
+}
+
+/// The address of a TCP KISS TNC served by `listener`.
+pub(crate) fn tcp_kiss_address(listener: &TcpListener) -> TncAddress {
+    TncAddress::new_tcpkiss(TcpKissConfig::new(
+        "127.0.0.1",
+        listener.local_addr().unwrap().port(),
+    ))
+}
+
+/// A `Tnc` connected over TCP KISS, and the server end of its connection.
+pub(crate) fn tcp_kiss_pair() -> (Tnc, TcpStream) {
+    let listener = local_listener();
+    let tnc = Tnc::open(&tcp_kiss_address(&listener)).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    (tnc, server)
+}
+
+/// A `TcpKissInterface`, and the server end of its connection.
+pub(crate) fn tcp_kiss_interface_pair() -> (TcpKissInterface, TcpStream) {
+    let listener = local_listener();
+    let iface = TcpKissInterface::new(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    (iface, server)
+}
@@ -1,31 +1,123 @@
+use crate::agwpe;
+use crate::axudp;
+use crate::file;
 use crate::kiss;
 use crate::linux;
-use ax25::frame::Ax25Frame;
+use crate::sixpack;
+use ax25::frame::{Address, Ax25Frame, FrameParseError};
+use std::collections::hash_map::RandomState;
 use std::error::Error;
 use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::io::{Read, Write};
+use std::net::{Ipv6Addr, SocketAddr, ToSocketAddrs};
 use std::str::FromStr;
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{
+    channel, sync_channel, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError,
+};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 /// Errors that can occur when interacting with a `Tnc`.
+///
+/// Most variants say which operation failed and wrap the I/O error that caused it.
+/// Use `io_error_kind` to decide what to do next, for example retrying an `OpenTnc`
+/// that failed with `ConnectionRefused` but giving up on `ResolveHost`.
+///
+/// New variants may be added, so matches need a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum TncError {
-    OpenTnc { source: std::io::Error },
-    InterfaceNotFound { callsign: String },
-    SendFrame { source: std::io::Error },
-    ReceiveFrame { source: std::io::Error },
-    ConfigFailed { source: std::io::Error },
+    /// The TNC could not be connected to or opened
+    OpenTnc {
+        source: std::io::Error,
+    },
+    /// The host of a network TNC could not be found
+    ResolveHost {
+        host: String,
+        source: std::io::Error,
+    },
+    /// No Linux AX.25 interface has this callsign
+    InterfaceNotFound {
+        callsign: String,
+    },
+    SendFrame {
+        source: std::io::Error,
+    },
+    ReceiveFrame {
+        source: std::io::Error,
+    },
+    /// A KISS configuration command could not be sent
+    ConfigFailed {
+        source: std::io::Error,
+    },
+    /// The connection to the TNC is down and will be re-established automatically
+    Reconnecting,
+    /// The TNC was opened with `Tnc::open_receive_only` so nothing may be sent to it
+    ReceiveOnly,
+    /// Received data was discarded because it did not form a frame in time
+    ReceiveOverflow {
+        source: std::io::Error,
+    },
+    Linux {
+        source: linux::LinuxError,
+    },
+}
+
+impl TncError {
+    /// The I/O error which caused this one, if there is one.
+    pub fn io_error(&self) -> Option<&std::io::Error> {
+        match self {
+            Self::OpenTnc { source }
+            | Self::ResolveHost { source, .. }
+            | Self::SendFrame { source }
+            | Self::ReceiveFrame { source }
+            | Self::ConfigFailed { source }
+            | Self::ReceiveOverflow { source } => Some(source),
+            Self::Linux { source } => match source {
+                linux::LinuxError::PermissionDenied { source }
+                | linux::LinuxError::IoctlFailed { source, .. }
+                | linux::LinuxError::Io { source } => Some(source),
+                _ => None,
+            },
+            Self::InterfaceNotFound { .. } | Self::Reconnecting | Self::ReceiveOnly => None,
+        }
+    }
+
+    /// The kind of the I/O error which caused this one, if there is one.
+    ///
+    /// ```no_run
+    /// # use ax25_tnc::tnc::{Tnc, TncAddress};
+    /// # let address: TncAddress = "tnc:tcpkiss:localhost:8001".parse().unwrap();
+    /// let tnc = loop {
+    ///     match Tnc::open(&address) {
+    ///         Err(e) if e.io_error_kind() == Some(std::io::ErrorKind::ConnectionRefused) => {
+    ///             std::thread::sleep(std::time::Duration::from_secs(5));
+    ///         }
+    ///         result => break result,
+    ///     }
+    /// };
+    /// ```
+    pub fn io_error_kind(&self) -> Option<std::io::ErrorKind> {
+        self.io_error().map(std::io::Error::kind)
+    }
 }
 
 impl Error for TncError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             Self::OpenTnc { source } => Some(source),
+            Self::ResolveHost { source, .. } => Some(source),
             Self::InterfaceNotFound { .. } => None,
             Self::SendFrame { source } => Some(source),
             Self::ReceiveFrame { source } => Some(source),
             Self::ConfigFailed { source } => Some(source),
+            Self::Reconnecting => None,
+            Self::ReceiveOnly => None,
+            Self::ReceiveOverflow { source } => Some(source),
+            Self::Linux { source } => Some(source),
         }
     }
 }
@@ -34,6 +126,9 @@ impl fmt::Display for TncError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::OpenTnc { source } => write!(f, "Unable to connect to TNC: {}", source),
+            Self::ResolveHost { host, source } => {
+                write!(f, "Unable to resolve TNC host '{}': {}", host, source)
+            }
             Self::InterfaceNotFound { callsign } => write!(
                 f,
                 "Interface with specified callsign '{}' does not exist",
@@ -44,6 +139,23 @@ impl fmt::Display for TncError {
             Self::ConfigFailed { source } => {
                 write!(f, "Unable to make configuration change: {}", source)
             }
+            Self::Reconnecting => write!(f, "Connection to TNC was lost, reconnecting"),
+            Self::ReceiveOnly => write!(f, "TNC was opened receive-only"),
+            Self::ReceiveOverflow { source } => {
+                write!(f, "Received data discarded to resynchronise: {}", source)
+            }
+            Self::Linux { source } => write!(f, "Unable to open AX.25 interface: {}", source),
+        }
+    }
+}
+
+impl From<linux::LinuxError> for TncError {
+    fn from(e: linux::LinuxError) -> Self {
+        match e {
+            linux::LinuxError::InterfaceNotFound { callsign } => {
+                Self::InterfaceNotFound { callsign }
+            }
+            source => Self::Linux { source },
         }
     }
 }
@@ -66,6 +178,13 @@ pub enum ParseError {
         input: String,
         source: std::num::ParseIntError,
     },
+    InvalidBaudRate {
+        input: String,
+        source: std::num::ParseIntError,
+    },
+    InvalidHost {
+        input: String,
+    },
 }
 
 impl Error for ParseError {}
@@ -93,42 +212,326 @@ impl fmt::Display for ParseError {
                 "Supplied port '{}' should be a number from 0 to 65535",
                 input
             ),
+            Self::InvalidBaudRate { input, .. } => write!(
+                f,
+                "Supplied baud rate '{}' should be a positive number",
+                input
+            ),
+            Self::InvalidHost { input } => write!(
+                f,
+                "Supplied host '{}' is invalid - IPv6 addresses should be written like [::1]",
+                input
+            ),
         }
     }
 }
 
 /// Configuration details for a TCP KISS TNC. This structure can be created directly
 /// or indirectly by parsing a string into a `TncAddress`.
-#[derive(PartialEq, Debug, Eq)]
+#[derive(Clone, PartialEq, Debug, Eq)]
 pub struct TcpKissConfig {
-    /// Hostname or IP address of the computer with the TNC
+    /// Hostname or IP address of the computer with the TNC. An IPv6 address is given
+    /// without the brackets it needs in an address string.
     pub host: String,
     /// Port number
     pub port: u16,
+    /// If set, automatically reconnect with backoff when the connection is lost.
+    /// This cannot be specified in an address string and defaults to `None`.
+    pub reconnect: Option<kiss::ReconnectPolicy>,
+    /// Use SMACK framing, which adds a CRC to each frame. The TNC must be configured
+    /// to match. This cannot be specified in an address string and defaults to `false`.
+    pub smack: bool,
+    /// Limit on buffered bytes that have not formed a complete frame. This cannot be
+    /// specified in an address string and defaults to `kiss::DEFAULT_MAX_BUFFER`.
+    pub max_buffer: usize,
+    /// How long to wait for the TNC to accept a connection, after which opening fails
+    /// with `TncError::OpenTnc` of kind `TimedOut`. Must not be zero. This cannot be
+    /// specified in an address string and defaults to `kiss::DEFAULT_CONNECT_TIMEOUT`.
+    pub connect_timeout: Duration,
+    /// If set, send TCP keepalive probes so that a TNC which vanishes without closing
+    /// the connection is noticed within seconds. This cannot be specified in an address
+    /// string and defaults to `None`.
+    pub keepalive: Option<kiss::KeepalivePolicy>,
+}
+
+impl TcpKissConfig {
+    /// Create a configuration for the given host and port with default options.
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            reconnect: None,
+            smack: false,
+            max_buffer: kiss::DEFAULT_MAX_BUFFER,
+            connect_timeout: kiss::DEFAULT_CONNECT_TIMEOUT,
+            keepalive: None,
+        }
+    }
 }
 
 /// Configuration details for a TNC attached as a Linux network interface using
 /// `kissattach`. This structure can be created directly or indirectly by parsing
 /// a string into a `TncAddress`.
-#[derive(PartialEq, Debug, Eq)]
+///
+/// Opening the interface requires root or `CAP_NET_ADMIN`.
+#[derive(Clone, PartialEq, Debug, Eq)]
 pub struct LinuxIfConfig {
     /// The hardware address associated with the interface, e.g. "VK7NTK-2"
     pub callsign: String,
+    /// Largest frame that can be received, in bytes. This cannot be specified in an
+    /// address string and defaults to `linux::DEFAULT_RECEIVE_BUFFER`.
+    pub receive_buffer: usize,
+    /// Which frames are received. This cannot be specified in an address string and
+    /// defaults to `LinuxIfReceiveMode::Promiscuous`.
+    pub receive_mode: LinuxIfReceiveMode,
+}
+
+impl LinuxIfConfig {
+    /// Create a configuration for the interface with the given callsign.
+    pub fn new(callsign: &str) -> Self {
+        Self {
+            callsign: callsign.to_string(),
+            receive_buffer: linux::DEFAULT_RECEIVE_BUFFER,
+            receive_mode: LinuxIfReceiveMode::Promiscuous,
+        }
+    }
+}
+
+/// Which frames a `linuxif` TNC receives. Frames are always sent through a raw socket.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub enum LinuxIfReceiveMode {
+    /// Every frame on the interface, captured with a raw socket
+    Promiscuous,
+    /// Only UI frames addressed to this callsign, filtered by the kernel's AX.25 stack
+    /// using a `linux::Ax25DatagramSocket`
+    Callsign(Address),
+}
+
+/// Configuration details for an AGWPE server such as AGW Packet Engine or Dire Wolf.
+/// This structure can be created directly or indirectly by parsing a string into a
+/// `TncAddress`.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct AgwpeConfig {
+    /// Hostname or IP address of the computer running the AGWPE server. An IPv6
+    /// address is given without the brackets it needs in an address string.
+    pub host: String,
+    /// Port number, conventionally 8000
+    pub port: u16,
+    /// Which of the server's radio ports to use, starting from 0.
+    /// This cannot be specified in an address string and defaults to 0.
+    pub radio_port: u8,
+}
+
+impl AgwpeConfig {
+    /// Create a configuration for the given host and port using the first radio port.
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            radio_port: 0,
+        }
+    }
+}
+
+/// Configuration details for a KISS TNC reached over UDP. This structure can be created
+/// directly or indirectly by parsing a string into a `TncAddress`.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct UdpKissConfig {
+    /// Hostname or IP address of the computer with the TNC. An IPv6 address is given
+    /// without the brackets it needs in an address string.
+    pub host: String,
+    /// Port number the TNC receives datagrams on
+    pub port: u16,
+    /// Local port to receive datagrams from the TNC on, or 0 to pick any free port.
+    /// This cannot be specified in an address string and defaults to 0.
+    pub local_port: u16,
+    /// Accept frames from any host, not just the TNC. This cannot be specified in an
+    /// address string and defaults to `false`.
+    pub any_sender: bool,
+}
+
+impl UdpKissConfig {
+    /// Create a configuration for the given host and port with default options.
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            local_port: 0,
+            any_sender: false,
+        }
+    }
+}
+
+/// Configuration details for a link to an AXUDP peer, such as a packet node running
+/// `ax25ipd`, JNOS or URONode. This structure can be created directly or indirectly by
+/// parsing a string into a `TncAddress`.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct AxudpConfig {
+    /// Hostname or IP address of the peer. An IPv6 address is given without the
+    /// brackets it needs in an address string.
+    pub host: String,
+    /// Port number the peer receives datagrams on
+    pub port: u16,
+    /// Local port to receive datagrams from the peer on, or 0 to pick any free port.
+    /// This cannot be specified in an address string and defaults to the same as
+    /// `port`, since AXUDP peers usually send to the port they receive on.
+    pub local_port: u16,
+    /// Accept frames from any host, not just the peer. This cannot be specified in an
+    /// address string and defaults to `false`.
+    pub any_sender: bool,
+}
+
+impl AxudpConfig {
+    /// Create a configuration for the given host and port with default options.
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            local_port: port,
+            any_sender: false,
+        }
+    }
+}
+
+/// Configuration details for a KISS TNC attached to a serial port. This structure can be
+/// created directly or indirectly by parsing a string into a `TncAddress`.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct SerialKissConfig {
+    /// Name of the serial port, e.g. `/dev/ttyUSB0` or `COM3`
+    pub path: String,
+    /// Baud rate, which must match the TNC
+    pub baud_rate: u32,
+    /// Assert Data Terminal Ready, which some USB serial adapters and TNCs require before
+    /// they will pass data. This cannot be specified in an address string and defaults
+    /// to `true`.
+    pub dtr: bool,
+    /// Assert Request To Send. This cannot be specified in an address string and defaults
+    /// to `true`.
+    pub rts: bool,
+}
+
+impl SerialKissConfig {
+    /// Create a configuration for the given serial port and baud rate with default options.
+    pub fn new(path: impl Into<String>, baud_rate: u32) -> Self {
+        Self {
+            path: path.into(),
+            baud_rate,
+            dtr: true,
+            rts: true,
+        }
+    }
+}
+
+/// Configuration details for a TNC attached to a serial port which speaks 6PACK instead
+/// of KISS. This structure can be created directly or indirectly by parsing a string
+/// into a `TncAddress`.
+///
+/// 6PACK TNCs leave channel access to the host, so use `Tnc::send_frame_queued` unless
+/// the channel is full duplex.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct SixpackConfig {
+    /// Name of the serial port, e.g. `/dev/ttyS0` or `COM3`
+    pub path: String,
+    /// Baud rate, which must match the TNC
+    pub baud_rate: u32,
+    /// Delay between keying the transmitter and sending data, in units of 10 ms, which
+    /// 6PACK sends with every frame. This cannot be specified in an address string and
+    /// defaults to `sixpack::DEFAULT_TX_DELAY`. It can be changed later with
+    /// `Tnc::set_tx_delay`.
+    pub tx_delay: u8,
+    /// Assert Data Terminal Ready. This cannot be specified in an address string and
+    /// defaults to `true`.
+    pub dtr: bool,
+    /// Assert Request To Send. This cannot be specified in an address string and defaults
+    /// to `true`.
+    pub rts: bool,
+}
+
+impl SixpackConfig {
+    /// Create a configuration for the given serial port and baud rate with default options.
+    pub fn new(path: impl Into<String>, baud_rate: u32) -> Self {
+        Self {
+            path: path.into(),
+            baud_rate,
+            tx_delay: sixpack::DEFAULT_TX_DELAY,
+            dtr: true,
+            rts: true,
+        }
+    }
+}
+
+/// How quickly a file TNC delivers frames from its capture.
+#[derive(Clone, Copy, PartialEq, Debug, Eq)]
+pub enum FilePacing {
+    /// Deliver frames as fast as they are received.
+    Burst,
+    /// Deliver frames with the same spacing as when they were captured. Files without
+    /// timestamps are delivered as in `Burst`.
+    Realtime,
+}
+
+/// Configuration details for a TNC that replays frames from a file, useful for testing
+/// without a radio. The file may be a pcap capture or a sequence of frames each preceded
+/// by a 16-bit big-endian length. Replay begins once the first receiver is created with
+/// `Tnc::incoming` or `Tnc::incoming_raw`. This structure can be created directly or indirectly
+/// by parsing a string into a `TncAddress`.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct FileConfig {
+    /// Path of the file to replay
+    pub path: String,
+    /// Path of a pcap file to append sent frames to, or `None` to discard them.
+    /// This cannot be specified in an address string and defaults to `None`.
+    pub output: Option<String>,
+    /// How quickly frames are delivered.
+    /// This cannot be specified in an address string and defaults to `FilePacing::Burst`.
+    pub pacing: FilePacing,
+}
+
+impl FileConfig {
+    /// Create a configuration replaying the given file with default options.
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            output: None,
+            pacing: FilePacing::Burst,
+        }
+    }
 }
 
-#[derive(PartialEq, Debug, Eq)]
+#[derive(Clone, PartialEq, Debug, Eq)]
 pub(crate) enum ConnectConfig {
     TcpKiss(TcpKissConfig),
+    UdpKiss(UdpKissConfig),
+    Axudp(AxudpConfig),
+    SerialKiss(SerialKissConfig),
+    Sixpack(SixpackConfig),
     LinuxIf(LinuxIfConfig),
+    Agwpe(AgwpeConfig),
+    File(FileConfig),
 }
 
 /// A parsed TNC address that can be used to open a `Tnc`.
-#[derive(PartialEq, Debug, Eq)]
+#[derive(Clone, PartialEq, Debug, Eq)]
 pub struct TncAddress {
     pub(crate) config: ConnectConfig,
 }
 
 impl TncAddress {
+    /// The type of TNC this address refers to, as written in an address string,
+    /// e.g. `tcpkiss` or `linuxif`.
+    pub fn config_kind(&self) -> &'static str {
+        match self.config {
+            ConnectConfig::TcpKiss(_) => "tcpkiss",
+            ConnectConfig::UdpKiss(_) => "udpkiss",
+            ConnectConfig::Axudp(_) => "axudp",
+            ConnectConfig::SerialKiss(_) => "serialkiss",
+            ConnectConfig::Sixpack(_) => "sixpack",
+            ConnectConfig::LinuxIf(_) => "linuxif",
+            ConnectConfig::Agwpe(_) => "agwpe",
+            ConnectConfig::File(_) => "file",
+        }
+    }
+
     /// Programmatically create a `TncAddress` pointing to a Linux network interface.
     pub fn new_linuxif(linuxif: LinuxIfConfig) -> Self {
         TncAddress {
@@ -142,6 +545,48 @@ impl TncAddress {
             config: ConnectConfig::TcpKiss(tcpkiss),
         }
     }
+
+    /// Programmatically create a `TncAddress` pointing to a KISS UDP service.
+    pub fn new_udpkiss(udpkiss: UdpKissConfig) -> Self {
+        TncAddress {
+            config: ConnectConfig::UdpKiss(udpkiss),
+        }
+    }
+
+    /// Programmatically create a `TncAddress` pointing to an AXUDP peer.
+    pub fn new_axudp(axudp: AxudpConfig) -> Self {
+        TncAddress {
+            config: ConnectConfig::Axudp(axudp),
+        }
+    }
+
+    /// Programmatically create a `TncAddress` pointing to a KISS TNC on a serial port.
+    pub fn new_serialkiss(serialkiss: SerialKissConfig) -> Self {
+        TncAddress {
+            config: ConnectConfig::SerialKiss(serialkiss),
+        }
+    }
+
+    /// Programmatically create a `TncAddress` pointing to a 6PACK TNC on a serial port.
+    pub fn new_sixpack(sixpack: SixpackConfig) -> Self {
+        TncAddress {
+            config: ConnectConfig::Sixpack(sixpack),
+        }
+    }
+
+    /// Programmatically create a `TncAddress` pointing to an AGWPE server.
+    pub fn new_agwpe(agwpe: AgwpeConfig) -> Self {
+        TncAddress {
+            config: ConnectConfig::Agwpe(agwpe),
+        }
+    }
+
+    /// Programmatically create a `TncAddress` that replays frames from a file.
+    pub fn new_file(file: FileConfig) -> Self {
+        TncAddress {
+            config: ConnectConfig::File(file),
+        }
+    }
 }
 
 impl FromStr for TncAddress {
@@ -157,35 +602,65 @@ impl FromStr for TncAddress {
         let len = components.len();
         Ok(match components[1] {
             "tcpkiss" => {
-                if len != 4 {
+                let (host, port) = parse_host_port(components[1], &components[2..])?;
+                TncAddress {
+                    config: ConnectConfig::TcpKiss(TcpKissConfig::new(host, port)),
+                }
+            }
+            "udpkiss" => {
+                let (host, port) = parse_host_port(components[1], &components[2..])?;
+                TncAddress {
+                    config: ConnectConfig::UdpKiss(UdpKissConfig::new(host, port)),
+                }
+            }
+            "axudp" => {
+                let (host, port) = parse_host_port(components[1], &components[2..])?;
+                TncAddress {
+                    config: ConnectConfig::Axudp(AxudpConfig::new(host, port)),
+                }
+            }
+            "serialkiss" => {
+                let (path, baud_rate) = parse_serial(components[1], &components[2..])?;
+                TncAddress {
+                    config: ConnectConfig::SerialKiss(SerialKissConfig::new(path, baud_rate)),
+                }
+            }
+            "sixpack" => {
+                let (path, baud_rate) = parse_serial(components[1], &components[2..])?;
+                TncAddress {
+                    config: ConnectConfig::Sixpack(SixpackConfig::new(path, baud_rate)),
+                }
+            }
+            "agwpe" => {
+                let (host, port) = parse_host_port(components[1], &components[2..])?;
+                TncAddress {
+                    config: ConnectConfig::Agwpe(AgwpeConfig::new(host, port)),
+                }
+            }
+            "linuxif" => {
+                if len != 3 {
                     return Err(ParseError::WrongParameterCount {
                         tnc_type: components[1].to_string(),
-                        expected: 2usize,
+                        expected: 1usize,
                         actual: len - 2,
                     });
                 }
                 TncAddress {
-                    config: ConnectConfig::TcpKiss(TcpKissConfig {
-                        host: components[2].to_string(),
-                        port: components[3].parse().map_err(|e| ParseError::InvalidPort {
-                            input: components[3].to_string(),
-                            source: e,
-                        })?,
-                    }),
+                    config: ConnectConfig::LinuxIf(LinuxIfConfig::new(components[2])),
                 }
             }
-            "linuxif" => {
-                if len != 3 {
+            "file" => {
+                // Paths may themselves contain colons
+                let path = components[2..].join(":");
+                if path.is_empty() {
                     return Err(ParseError::WrongParameterCount {
                         tnc_type: components[1].to_string(),
                         expected: 1usize,
-                        actual: len - 2,
+                        actual: 0,
                     });
                 }
                 TncAddress {
-                    config: ConnectConfig::LinuxIf(LinuxIfConfig {
-                        callsign: components[2].to_string(),
-                    }),
+                    config: ConnectConfig::File(FileConfig::new(path)),
                 }
             }
             unknown => {
@@ -197,11 +672,141 @@ impl FromStr for TncAddress {
     }
 }
 
+/// Parse the `path:baud` parameters of an address string for a serial port.
+fn parse_serial(tnc_type: &str, params: &[&str]) -> Result<(String, u32), ParseError> {
+    if params.len() < 2 {
+        return Err(ParseError::WrongParameterCount {
+            tnc_type: tnc_type.to_string(),
+            expected: 2usize,
+            actual: params.len(),
+        });
+    }
+    // Device paths may themselves contain colons
+    let (baud_rate, path) = params.split_last().unwrap();
+    let baud_rate = baud_rate.parse().map_err(|e| ParseError::InvalidBaudRate {
+        input: baud_rate.to_string(),
+        source: e,
+    })?;
+    Ok((path.join(":"), baud_rate))
+}
+
+/// Parse the `host:port` parameters of an address string, where the host may be an IPv6
+/// address in brackets.
+fn parse_host_port(tnc_type: &str, params: &[&str]) -> Result<(String, u16), ParseError> {
+    let joined = params.join(":");
+    let (host, port) = match joined.strip_prefix('[') {
+        Some(rest) => {
+            let invalid = || ParseError::InvalidHost {
+                input: joined.clone(),
+            };
+            let (host, after) = rest.split_once(']').ok_or_else(invalid)?;
+            host.parse::<Ipv6Addr>().map_err(|_| invalid())?;
+            match after.strip_prefix(':') {
+                Some(port) => (host, port),
+                None if after.is_empty() => {
+                    return Err(ParseError::WrongParameterCount {
+                        tnc_type: tnc_type.to_string(),
+                        expected: 2usize,
+                        actual: 1,
+                    })
+                }
+                None => return Err(invalid()),
+            }
+        }
+        None => {
+            if params.len() != 2 {
+                return Err(ParseError::WrongParameterCount {
+                    tnc_type: tnc_type.to_string(),
+                    expected: 2usize,
+                    actual: params.len(),
+                });
+            }
+            (params[0], params[1])
+        }
+    };
+    let port = port.parse().map_err(|e| ParseError::InvalidPort {
+        input: port.to_string(),
+        source: e,
+    })?;
+    Ok((host.to_string(), port))
+}
+
+/// Look up the addresses of a TNC's host, so that failing to find it can be reported
+/// separately from failing to connect.
+fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>, TncError> {
+    resolved(host, (host, port).to_socket_addrs())
+}
+
+/// Collect the result of looking up `host`, treating an empty result as a failure.
+pub(crate) fn resolved(
+    host: &str,
+    lookup: std::io::Result<impl Iterator<Item = SocketAddr>>,
+) -> Result<Vec<SocketAddr>, TncError> {
+    lookup
+        .and_then(|addrs| {
+            let addrs: Vec<SocketAddr> = addrs.collect();
+            if addrs.is_empty() {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "host has no addresses",
+                ))
+            } else {
+                Ok(addrs)
+            }
+        })
+        .map_err(|e| TncError::ResolveHost {
+            host: host.to_string(),
+            source: e,
+        })
+}
+
+/// Format a host and port for display, bracketing IPv6 addresses.
+fn host_port_string(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
 trait TncImpl: Send + Sync {
-    fn send_frame(&self, frame: &Ax25Frame) -> Result<(), TncError>;
-    fn receive_frame(&self) -> Result<Ax25Frame, TncError>;
+    fn send_frame(&self, frame: &[u8]) -> Result<(), TncError>;
+    fn receive_frame_timeout(&self, timeout: Duration) -> Result<Option<Vec<u8>>, TncError>;
     fn clone(&self) -> Box<dyn TncImpl>;
     fn shutdown(&self);
+
+    /// The type of TNC and where it is, for display to users.
+    fn description(&self) -> String;
+
+    /// How many times the connection to the TNC has been re-established.
+    fn reconnects(&self) -> u64 {
+        0
+    }
+
+    /// Whether the connection to the TNC is believed to be working.
+    fn is_connected(&self) -> bool {
+        true
+    }
+
+    /// The oldest non-data frame received from a KISS TNC, if there is one.
+    fn take_control_frame(&self) -> Option<kiss::KissControlFrame> {
+        None
+    }
+
+    /// Whether receiving should be held off until somebody is listening, because any
+    /// frames received before then could not be received again.
+    fn wait_for_receivers(&self) -> bool {
+        false
+    }
+
+    fn send_kiss_command(&self, _command: u8, _port: u8, _data: &[u8]) -> Result<(), TncError> {
+        Err(TncError::ConfigFailed {
+            source: std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "TNC does not accept KISS configuration commands",
+            ),
+        })
+    }
 }
 
 /// A local or remote TNC attached to a radio, which can send and receive frames.
@@ -214,189 +819,1350 @@ impl Tnc {
         let imp: Box<dyn TncImpl> = match &address.config {
             ConnectConfig::TcpKiss(config) => Box::new(TcpKissTnc::open(config)?),
             ConnectConfig::LinuxIf(config) => Box::new(LinuxIfTnc::open(config)?),
+            ConnectConfig::UdpKiss(config) => Box::new(UdpKissTnc::open(config)?),
+            ConnectConfig::Axudp(config) => Box::new(AxudpTnc::open(config)?),
+            ConnectConfig::SerialKiss(config) => Box::new(KissStreamTnc::open_serial(config)?),
+            ConnectConfig::Sixpack(config) => Box::new(SixpackTnc::open(config)?),
+            ConnectConfig::Agwpe(config) => Box::new(AgwpeTnc::open(config)?),
+            ConnectConfig::File(config) => Box::new(FileTnc::open(config)?),
         };
         Ok(Tnc(Arc::new(Mutex::new(TncInner::new(imp)))))
     }
 
+    /// Open a TNC like `open` for monitoring only. Sending a frame or a KISS command
+    /// fails with `TncError::ReceiveOnly` without anything being written to the TNC,
+    /// so the radio can never be keyed by this `Tnc` or its clones.
+    ///
+    /// A `linuxif` TNC still needs the same privileges as for `open`, since receiving
+    /// uses the same socket.
+    pub fn open_receive_only(address: &TncAddress) -> Result<Self, TncError> {
+        let tnc = Self::open(address)?;
+        {
+            let mut inner = tnc.0.lock().unwrap();
+            let imp = inner.imp.clone();
+            inner.imp = Box::new(ReceiveOnlyTnc { inner: imp });
+        }
+        Ok(tnc)
+    }
+
+    /// Use the KISS protocol over an existing connection to a TNC, such as a TLS session
+    /// or an SSH channel, which can carry traffic in both directions.
+    ///
+    /// Sending and receiving share the stream, so its reads must not block forever. Give
+    /// it a read timeout or make it non-blocking; reads failing with `WouldBlock` or
+    /// `TimedOut` are treated as no data having arrived yet.
+    pub fn from_kiss_stream<S: Read + Write + Send + 'static>(stream: S) -> Tnc {
+        let imp = KissStreamTnc {
+            iface: Arc::new(kiss::StreamKissInterface::new(stream)),
+            description: "kiss stream".to_string(),
+        };
+        Tnc(Arc::new(Mutex::new(TncInner::new(Box::new(imp)))))
+    }
+
+    /// Create two connected TNCs which exist only in memory, for testing.
+    ///
+    /// Every frame sent on one is received by the other. Frames are held until the
+    /// receiving side has at least one receiver from `incoming` or `incoming_raw`, so
+    /// none are lost while a test is being set up.
+    pub fn loopback_pair() -> (Tnc, Tnc) {
+        let (a_tx, a_rx) = channel();
+        let (b_tx, b_rx) = channel();
+        let a = LoopbackTnc::new(a_tx, b_rx);
+        let b = LoopbackTnc::new(b_tx, a_rx);
+        (
+            Tnc(Arc::new(Mutex::new(TncInner::new(Box::new(a))))),
+            Tnc(Arc::new(Mutex::new(TncInner::new(Box::new(b))))),
+        )
+    }
+
     /// Transmit a frame on the radio. Transmission is not guaranteed even if a
     /// `Ok` result is returned.
     pub fn send_frame(&self, frame: &Ax25Frame) -> Result<(), TncError> {
         self.0.lock().unwrap().send_frame(frame)
     }
 
+    /// Transmit a frame and wait up to `timeout` for a received frame for which
+    /// `is_reply` returns true, such as an answer from the station it was sent to.
+    ///
+    /// Receiving starts before the frame is sent, so a reply cannot be missed however
+    /// quickly it arrives. Other frames are ignored here but are still delivered to
+    /// other receivers as usual. Returns `Ok(None)` if no reply arrived in time.
+    ///
+    /// If the TNC stops receiving while waiting, the `ReceiveFrame` error has the same
+    /// I/O error kind as the error which stopped it, and wraps that error as an
+    /// `Arc<TncError>` which can be reached with `get_ref` and `downcast_ref`.
+    pub fn request<F>(
+        &self,
+        frame: &Ax25Frame,
+        is_reply: F,
+        timeout: Duration,
+    ) -> Result<Option<Ax25Frame>, TncError>
+    where
+        F: Fn(&Ax25Frame) -> bool,
+    {
+        let incoming = self.incoming();
+        self.send_frame(frame)?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let e = match incoming.recv_timeout(remaining) {
+                Ok(Ok(received)) if is_reply(&received) => return Ok(Some(received)),
+                Ok(Ok(_)) => continue,
+                // Some data was lost but the TNC is still receiving
+                Ok(Err(e)) if matches!(*e, TncError::ReceiveOverflow { .. }) => continue,
+                Ok(Err(e)) => {
+                    std::io::Error::new(e.io_error_kind().unwrap_or(std::io::ErrorKind::Other), e)
+                }
+                Err(RecvTimeoutError::Timeout) => return Ok(None),
+                Err(RecvTimeoutError::Disconnected) => std::io::Error::new(
+                    std::io::ErrorKind::NotConnected,
+                    "TNC has stopped receiving",
+                ),
+            };
+            return Err(TncError::ReceiveFrame { source: e });
+        }
+    }
+
+    /// Transmit bytes that are already an encoded AX.25 frame, such as one received
+    /// through `incoming_raw`, without parsing or re-encoding them.
+    pub fn send_raw(&self, bytes: &[u8]) -> Result<(), TncError> {
+        self.0.lock().unwrap().send_raw(bytes)
+    }
+
+    /// Queue a frame to be transmitted in the background once the channel is clear,
+    /// following the parameters given to `set_channel_access`.
+    ///
+    /// Frames are sent in the order they were queued. Sending errors are not reported,
+    /// so use `send_frame` when it matters whether a frame was handed to the TNC. Frames
+    /// still waiting when the last clone of this `Tnc` is dropped are discarded.
+    pub fn send_frame_queued(&self, frame: &Ax25Frame) {
+        self.0.lock().unwrap().send_queued(frame.to_bytes())
+    }
+
+    /// Change how `send_frame_queued` decides when to transmit, for all clones of this
+    /// TNC. Frames already queued are sent with the new parameters.
+    pub fn set_channel_access(&self, access: ChannelAccess) {
+        *self.0.lock().unwrap().channel_access.lock().unwrap() = access;
+    }
+
     /// Create a new `Receiver<Result<Ax25Frame, TncError>>`
     /// This will receive a copy of all incoming frames.
     pub fn incoming(&self) -> Receiver<Ax25FrameResult> {
         self.0.lock().unwrap().incoming()
     }
-}
 
-pub type Ax25FrameResult = Result<Ax25Frame, Arc<TncError>>;
+    /// Create a new `Receiver<Result<Ax25Frame, TncError>>` which holds at most
+    /// `capacity` results that have not yet been received.
+    ///
+    /// When it is full, newly arrived frames are dropped rather than waiting for the
+    /// receiver to catch up, and counted in `TncStats::frames_dropped`. This keeps a
+    /// stalled consumer from using ever more memory. With a capacity of 0, frames are
+    /// only delivered while a thread is waiting in `recv`.
+    pub fn incoming_bounded(&self, capacity: usize) -> Receiver<Ax25FrameResult> {
+        self.0.lock().unwrap().incoming_bounded(capacity)
+    }
 
-struct TncInner {
-    imp: Box<dyn TncImpl>,
-    senders: Arc<Mutex<Vec<Sender<Ax25FrameResult>>>>,
-}
+    /// Iterate over a copy of all incoming frames, blocking until each one arrives.
+    ///
+    /// This is a convenience over `incoming` for use in a `for` loop. Errors are
+    /// yielded like any other result. Iteration ends after an error which stops the
+    /// TNC from receiving, or once the TNC is closed.
+    pub fn frames(&self) -> impl Iterator<Item = Ax25FrameResult> {
+        self.incoming().into_iter()
+    }
 
-impl TncInner {
-    fn new(imp: Box<dyn TncImpl>) -> Self {
-        let senders: Arc<Mutex<Vec<Sender<Ax25FrameResult>>>> = Arc::new(Mutex::new(Vec::new()));
+    /// Create a new `Stream` which will receive a copy of all incoming frames, for
+    /// awaiting frames in async code without switching to `AsyncTnc`.
+    ///
+    /// This uses a thread to forward frames from `incoming`. The stream ends once the
+    /// TNC is closed.
+    #[cfg(feature = "futures")]
+    pub fn incoming_stream(&self) -> IncomingStream {
+        IncomingStream::bridge(self.incoming())
+    }
 
-        {
-            let imp = imp.clone();
-            let senders = senders.clone();
+    /// Create a new `Receiver<Result<Ax25Frame, TncError>>`
+    /// This will receive a copy of those incoming frames for which `filter` returns
+    /// true, and any errors.
+    ///
+    /// The filter runs on the receive thread so it should return quickly.
+    pub fn incoming_filtered<F>(&self, filter: F) -> Receiver<Ax25FrameResult>
+    where
+        F: Fn(&Ax25Frame) -> bool + Send + 'static,
+    {
+        self.0.lock().unwrap().subscribe(Some(Box::new(filter)))
+    }
 
-            thread::spawn(move || {
-                loop {
-                    let x = match imp.receive_frame() {
-                        Ok(a) => Ok(a),
-                        Err(e) => Err(Arc::new(e)),
-                    };
+    /// Set the delay between keying the transmitter and sending data, in units of 10 ms.
+    ///
+    /// This and the other configuration methods are only supported by KISS TNCs.
+    pub fn set_tx_delay(&self, units: u8) -> Result<(), TncError> {
+        self.send_kiss_command(kiss::CMD_TX_DELAY, 0, &[units])
+    }
 
-                    senders.lock().unwrap().retain(|s| {
-                        // If there's an error, remove sender from vec
-                        s.send(x.clone()).is_ok()
-                    });
-                    if x.is_err() {
-                        break;
-                    }
-                }
+    /// Set the CSMA persistence parameter `p`, where the chance of transmitting in a
+    /// given slot is `(p + 1) / 256`.
+    pub fn set_persistence(&self, p: u8) -> Result<(), TncError> {
+        self.send_kiss_command(kiss::CMD_PERSISTENCE, 0, &[p])
+    }
 
-                senders.lock().unwrap().clear();
-            });
-        }
+    /// Set the CSMA slot interval, in units of 10 ms.
+    pub fn set_slot_time(&self, units: u8) -> Result<(), TncError> {
+        self.send_kiss_command(kiss::CMD_SLOT_TIME, 0, &[units])
+    }
 
-        TncInner { imp, senders }
+    /// Set how long to keep the transmitter keyed after the last frame, in units of 10 ms.
+    pub fn set_tx_tail(&self, units: u8) -> Result<(), TncError> {
+        self.send_kiss_command(kiss::CMD_TX_TAIL, 0, &[units])
     }
 
-    /// Transmit a frame on the radio. Transmission is not guaranteed even if a
-    /// `Ok` result is returned.
-    pub fn send_frame(&self, frame: &Ax25Frame) -> Result<(), TncError> {
-        self.imp.send_frame(frame)
+    /// Enable or disable full duplex operation.
+    pub fn set_full_duplex(&self, full_duplex: bool) -> Result<(), TncError> {
+        self.send_kiss_command(kiss::CMD_FULL_DUPLEX, 0, &[full_duplex as u8])
     }
 
-    /// Create a new `Receiver<Result<Ax25Frame, TncError>>`
-    /// This will receive a copy of all incoming frames.
-    pub fn incoming(&self) -> Receiver<Ax25FrameResult> {
-        let (sender, receiver) = channel();
-        self.senders.lock().unwrap().push(sender);
-        receiver
+    /// Send a hardware-specific configuration command to the TNC.
+    pub fn set_hardware(&self, data: &[u8]) -> Result<(), TncError> {
+        self.send_kiss_command(kiss::CMD_SET_HARDWARE, 0, data)
     }
-}
 
-impl Drop for TncInner {
-    fn drop(&mut self) {
-        self.imp.shutdown();
+    /// Send a KISS command frame with arbitrary contents to `port` on the TNC, such as
+    /// `kiss::CMD_RETURN` to take it out of KISS mode.
+    ///
+    /// Like the configuration methods this is only supported by KISS TNCs.
+    pub fn send_kiss_command(&self, command: u8, port: u8, data: &[u8]) -> Result<(), TncError> {
+        self.0
+            .lock()
+            .unwrap()
+            .imp
+            .send_kiss_command(command, port, data)
     }
-}
 
-struct LinuxIfTnc {
-    socket: Arc<linux::Ax25RawSocket>,
-    ifindex: i32,
-}
+    /// Create a new `Receiver<Result<RawFrame, TncError>>`
+    /// This will receive a copy of all incoming frames including those which could not
+    /// be parsed, along with the bytes that were received from the TNC and the time they
+    /// arrived.
+    pub fn incoming_raw(&self) -> Receiver<RawFrameResult> {
+        self.0.lock().unwrap().incoming_raw()
+    }
 
-impl LinuxIfTnc {
-    fn open(config: &LinuxIfConfig) -> Result<Self, TncError> {
-        let socket = linux::Ax25RawSocket::new().map_err(|e| TncError::OpenTnc { source: e })?;
-        let ifindex = match socket
-            .list_ax25_interfaces()
-            .map_err(|e| TncError::OpenTnc { source: e })?
-            .iter()
-            .find(|nd| nd.name.to_uppercase() == config.callsign.to_uppercase())
-        {
-            Some(nd) => nd.ifindex,
-            None => {
-                return Err(TncError::InterfaceNotFound {
-                    callsign: config.callsign.clone(),
-                })
-            }
-        };
-        Ok(Self {
-            socket: Arc::new(socket),
-            ifindex,
-        })
+    /// Create a new `Receiver<KissControlFrame>`
+    /// This will receive a copy of all non-data frames sent by a KISS TNC, such as
+    /// responses to SetHardware commands. Other types of TNC never send these.
+    pub fn incoming_control(&self) -> Receiver<kiss::KissControlFrame> {
+        self.0.lock().unwrap().incoming_control()
     }
-}
 
-impl TncImpl for LinuxIfTnc {
-    fn send_frame(&self, frame: &Ax25Frame) -> Result<(), TncError> {
-        self.socket
-            .send_frame(&frame.to_bytes(), self.ifindex)
-            .map_err(|e| TncError::SendFrame { source: e })
+    /// A snapshot of the traffic that has passed through this TNC since it was opened,
+    /// shared between all clones.
+    pub fn stats(&self) -> TncStats {
+        self.0.lock().unwrap().stats()
     }
 
-    fn receive_frame(&self) -> Result<Ax25Frame, TncError> {
-        loop {
-            let bytes = self
-                .socket
-                .receive_frame(self.ifindex)
-                .map_err(|e| TncError::ReceiveFrame { source: e })?;
-            if let Ok(parsed) = Ax25Frame::from_bytes(&bytes) {
-                return Ok(parsed);
+    /// A short description of the type of TNC and where it is, such as
+    /// `"tcpkiss 192.168.0.1:8001"`, for identifying it in logs and error messages.
+    pub fn description(&self) -> String {
+        self.0.lock().unwrap().imp.description()
+    }
+
+    /// Whether the connection to the TNC is believed to be working.
+    ///
+    /// Only `tcpkiss` TNCs track this. It becomes `false` once the connection is found
+    /// to have failed, which happens promptly with `TcpKissConfig::keepalive` set, and
+    /// `true` again if it is re-established.
+    pub fn is_connected(&self) -> bool {
+        self.0.lock().unwrap().imp.is_connected()
+    }
+}
+
+pub type Ax25FrameResult = Result<Ax25Frame, Arc<TncError>>;
+pub type RawFrameResult = Result<RawFrame, Arc<TncError>>;
+
+/// A `Stream` of frames received by a `Tnc`, created with `Tnc::incoming_stream`.
+#[cfg(feature = "futures")]
+pub struct IncomingStream {
+    state: Arc<Mutex<StreamState>>,
+}
+
+#[cfg(feature = "futures")]
+#[derive(Default)]
+struct StreamState {
+    frames: std::collections::VecDeque<Ax25FrameResult>,
+    closed: bool,
+    waker: Option<std::task::Waker>,
+}
+
+#[cfg(feature = "futures")]
+impl IncomingStream {
+    /// Forward frames from a blocking `Receiver` on a thread of their own, until either
+    /// side is closed.
+    fn bridge(incoming: Receiver<Ax25FrameResult>) -> IncomingStream {
+        let state = Arc::new(Mutex::new(StreamState::default()));
+        let forward = state.clone();
+        thread::spawn(move || {
+            for frame in incoming.iter() {
+                // The stream has been dropped
+                if Arc::strong_count(&forward) == 1 {
+                    return;
+                }
+                let mut state = forward.lock().unwrap();
+                state.frames.push_back(frame);
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+            let mut state = forward.lock().unwrap();
+            state.closed = true;
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+        IncomingStream { state }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl futures_core::Stream for IncomingStream {
+    type Item = Ax25FrameResult;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(frame) = state.frames.pop_front() {
+            return std::task::Poll::Ready(Some(frame));
+        }
+        if state.closed {
+            return std::task::Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        std::task::Poll::Pending
+    }
+}
+
+/// Counts of traffic through a `Tnc`, as returned by `Tnc::stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TncStats {
+    /// Frames received from the TNC, including those which could not be parsed
+    pub frames_received: u64,
+    /// Frames successfully handed to the TNC for transmission
+    pub frames_sent: u64,
+    /// Total length of all received frames
+    pub bytes_received: u64,
+    /// Total length of all sent frames
+    pub bytes_sent: u64,
+    /// Received frames which could not be parsed, so were only delivered to
+    /// `incoming_raw` receivers
+    pub parse_errors: u64,
+    /// Times the connection to the TNC was lost and re-established
+    pub reconnects: u64,
+    /// Results not delivered to a receiver from `incoming_bounded` because it was full
+    pub frames_dropped: u64,
+}
+
+/// How `Tnc::send_frame_queued` shares the channel with other stations, using
+/// p-persistent CSMA like a KISS TNC.
+///
+/// A queued frame waits until the channel is considered clear, then in each slot it is
+/// sent with probability `(persistence + 1) / 256` or held back for another slot.
+/// Activity on the channel is judged from frames received by this `Tnc`, so it is only
+/// noticed while something is receiving from a TNC that only reads when asked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelAccess {
+    /// Chance of transmitting in each slot once the channel is clear. Defaults to 63,
+    /// or one in four.
+    pub persistence: u8,
+    /// How long to wait after deciding not to transmit. Defaults to 100 ms.
+    pub slot_time: Duration,
+    /// The channel is treated as busy for this long after a frame is received.
+    /// Defaults to 500 ms.
+    pub busy_after_receive: Duration,
+    /// Least time between one queued frame being sent and the next. Defaults to zero.
+    pub min_gap: Duration,
+}
+
+impl Default for ChannelAccess {
+    fn default() -> Self {
+        Self {
+            persistence: 63,
+            slot_time: Duration::from_millis(100),
+            busy_after_receive: Duration::from_millis(500),
+            min_gap: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Default)]
+struct StatsCounters {
+    frames_received: AtomicU64,
+    frames_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    parse_errors: AtomicU64,
+    frames_dropped: AtomicU64,
+}
+
+/// A frame exactly as it was received from the TNC, whether or not it could be parsed.
+#[derive(Debug, Clone)]
+pub struct RawFrame {
+    /// The bytes of the AX.25 frame, with any TNC framing removed.
+    pub bytes: Vec<u8>,
+    /// The parsed frame, or the reason it could not be parsed.
+    pub frame: Result<Ax25Frame, Arc<FrameParseError>>,
+    /// When the frame was received, taken as soon as the TNC delivered it and before it
+    /// was queued for any receivers.
+    pub timestamp: SystemTime,
+}
+
+type FrameFilter = Box<dyn Fn(&Ax25Frame) -> bool + Send>;
+
+/// The channel to a subscriber, which drops results if it has a limited capacity.
+enum FrameChannel {
+    Unbounded(Sender<Ax25FrameResult>),
+    Bounded(SyncSender<Ax25FrameResult>),
+}
+
+/// A subscriber to parsed frames, which may only be interested in some of them.
+struct FrameSender {
+    sender: FrameChannel,
+    filter: Option<FrameFilter>,
+}
+
+impl FrameSender {
+    /// Forward a result if it passes the filter. Errors are always forwarded.
+    /// Returns `false` if the receiver has gone away.
+    fn send(&self, x: &Ax25FrameResult, stats: &StatsCounters) -> bool {
+        if let (Some(filter), Ok(frame)) = (&self.filter, x) {
+            if !filter(frame) {
+                return true;
+            }
+        }
+        match &self.sender {
+            FrameChannel::Unbounded(sender) => sender.send(x.clone()).is_ok(),
+            FrameChannel::Bounded(sender) => match sender.try_send(x.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    stats.frames_dropped.fetch_add(1, Ordering::SeqCst);
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            },
+        }
+    }
+}
+
+/// How long the receive thread waits inside the interface before regaining control.
+const RECEIVE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+struct TncInner {
+    imp: Box<dyn TncImpl>,
+    senders: Arc<Mutex<Vec<FrameSender>>>,
+    raw_senders: Arc<Mutex<Vec<Sender<RawFrameResult>>>>,
+    control_senders: Arc<Mutex<Vec<Sender<kiss::KissControlFrame>>>>,
+    stats: Arc<StatsCounters>,
+    is_shutdown: Arc<AtomicBool>,
+    /// When a frame was last received, for judging whether the channel is busy
+    last_received: Arc<Mutex<Option<Instant>>>,
+    channel_access: Arc<Mutex<ChannelAccess>>,
+    /// Frames for the queued sender thread, which is started on first use
+    queue: Option<Sender<Vec<u8>>>,
+}
+
+impl TncInner {
+    fn new(imp: Box<dyn TncImpl>) -> Self {
+        let senders: Arc<Mutex<Vec<FrameSender>>> = Arc::new(Mutex::new(Vec::new()));
+        let raw_senders: Arc<Mutex<Vec<Sender<RawFrameResult>>>> = Arc::new(Mutex::new(Vec::new()));
+        let control_senders: Arc<Mutex<Vec<Sender<kiss::KissControlFrame>>>> =
+            Arc::new(Mutex::new(Vec::new()));
+        let stats = Arc::new(StatsCounters::default());
+        let is_shutdown = Arc::new(AtomicBool::new(false));
+        let last_received = Arc::new(Mutex::new(None));
+
+        {
+            let imp = imp.clone();
+            let senders = senders.clone();
+            let raw_senders = raw_senders.clone();
+            let control_senders = control_senders.clone();
+            let stats = stats.clone();
+            let is_shutdown = is_shutdown.clone();
+            let last_received = last_received.clone();
+
+            thread::spawn(move || {
+                loop {
+                    if imp.wait_for_receivers()
+                        && senders.lock().unwrap().is_empty()
+                        && raw_senders.lock().unwrap().is_empty()
+                    {
+                        if is_shutdown.load(Ordering::SeqCst) {
+                            break;
+                        }
+                        thread::sleep(RECEIVE_POLL_INTERVAL);
+                        continue;
+                    }
+                    let received = imp.receive_frame_timeout(RECEIVE_POLL_INTERVAL);
+                    let timestamp = SystemTime::now();
+                    while let Some(control) = imp.take_control_frame() {
+                        control_senders
+                            .lock()
+                            .unwrap()
+                            .retain(|s| s.send(control.clone()).is_ok());
+                    }
+                    // Once the last `Tnc` has gone away, any result (most likely an
+                    // error from the closed interface) is of no interest to anybody
+                    if is_shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let raw = match received {
+                        Ok(Some(bytes)) => Ok(RawFrame {
+                            frame: Ax25Frame::from_bytes(&bytes).map_err(Arc::new),
+                            bytes,
+                            timestamp,
+                        }),
+                        Ok(None) => continue,
+                        Err(e) => Err(Arc::new(e)),
+                    };
+                    if let Ok(raw) = &raw {
+                        *last_received.lock().unwrap() = Some(Instant::now());
+                        stats.frames_received.fetch_add(1, Ordering::SeqCst);
+                        stats
+                            .bytes_received
+                            .fetch_add(raw.bytes.len() as u64, Ordering::SeqCst);
+                        if raw.frame.is_err() {
+                            stats.parse_errors.fetch_add(1, Ordering::SeqCst);
+                        }
+                    }
+                    // Frames which failed to parse are only of interest to raw receivers
+                    let x = match &raw {
+                        Ok(RawFrame { frame: Ok(f), .. }) => Some(Ok(f.clone())),
+                        Ok(RawFrame { frame: Err(_), .. }) => None,
+                        Err(e) => Some(Err(e.clone())),
+                    };
+
+                    raw_senders
+                        .lock()
+                        .unwrap()
+                        .retain(|s| s.send(raw.clone()).is_ok());
+                    if let Some(x) = x {
+                        // If there's an error, remove sender from vec
+                        senders.lock().unwrap().retain(|s| s.send(&x, &stats));
+                    }
+                    // An overflow means some data was lost but the TNC is still usable
+                    if let Err(e) = &raw {
+                        if !matches!(**e, TncError::ReceiveOverflow { .. }) {
+                            break;
+                        }
+                    }
+                }
+
+                senders.lock().unwrap().clear();
+                raw_senders.lock().unwrap().clear();
+                control_senders.lock().unwrap().clear();
+            });
+        }
+
+        TncInner {
+            imp,
+            senders,
+            raw_senders,
+            control_senders,
+            stats,
+            is_shutdown,
+            last_received,
+            channel_access: Arc::new(Mutex::new(ChannelAccess::default())),
+            queue: None,
+        }
+    }
+
+    /// Transmit a frame on the radio. Transmission is not guaranteed even if a
+    /// `Ok` result is returned.
+    pub fn send_frame(&self, frame: &Ax25Frame) -> Result<(), TncError> {
+        self.send_raw(&frame.to_bytes())
+    }
+
+    fn send_raw(&self, bytes: &[u8]) -> Result<(), TncError> {
+        transmit(self.imp.as_ref(), &self.stats, bytes)
+    }
+
+    fn send_queued(&mut self, bytes: Vec<u8>) {
+        let queue = self.queue.get_or_insert_with(|| {
+            let (queue, frames) = channel::<Vec<u8>>();
+            let imp = self.imp.clone();
+            let stats = self.stats.clone();
+            let is_shutdown = self.is_shutdown.clone();
+            let last_received = self.last_received.clone();
+            let channel_access = self.channel_access.clone();
+            thread::spawn(move || {
+                let mut last_sent: Option<Instant> = None;
+                // Ends once the last `Tnc` has gone away and dropped the queue
+                while let Ok(bytes) = frames.recv() {
+                    loop {
+                        if is_shutdown.load(Ordering::SeqCst) {
+                            return;
+                        }
+                        let access = channel_access.lock().unwrap().clone();
+                        let now = Instant::now();
+                        let mut clear_at = now;
+                        if let Some(sent) = last_sent {
+                            clear_at = clear_at.max(sent + access.min_gap);
+                        }
+                        if let Some(received) = *last_received.lock().unwrap() {
+                            clear_at = clear_at.max(received + access.busy_after_receive);
+                        }
+                        if clear_at > now {
+                            thread::sleep(clear_at - now);
+                        } else if random_byte() <= access.persistence {
+                            break;
+                        } else {
+                            thread::sleep(access.slot_time);
+                        }
+                    }
+                    let _ = transmit(imp.as_ref(), &stats, &bytes);
+                    last_sent = Some(Instant::now());
+                }
+            });
+            queue
+        });
+        // The thread only stops once the queue is dropped
+        let _ = queue.send(bytes);
+    }
+
+    /// Create a new `Receiver<Result<Ax25Frame, TncError>>`
+    /// This will receive a copy of all incoming frames.
+    pub fn incoming(&self) -> Receiver<Ax25FrameResult> {
+        self.subscribe(None)
+    }
+
+    fn subscribe(&self, filter: Option<FrameFilter>) -> Receiver<Ax25FrameResult> {
+        let (sender, receiver) = channel();
+        self.senders.lock().unwrap().push(FrameSender {
+            sender: FrameChannel::Unbounded(sender),
+            filter,
+        });
+        receiver
+    }
+
+    fn incoming_bounded(&self, capacity: usize) -> Receiver<Ax25FrameResult> {
+        let (sender, receiver) = sync_channel(capacity);
+        self.senders.lock().unwrap().push(FrameSender {
+            sender: FrameChannel::Bounded(sender),
+            filter: None,
+        });
+        receiver
+    }
+
+    pub fn incoming_raw(&self) -> Receiver<RawFrameResult> {
+        let (sender, receiver) = channel();
+        self.raw_senders.lock().unwrap().push(sender);
+        receiver
+    }
+
+    fn incoming_control(&self) -> Receiver<kiss::KissControlFrame> {
+        let (sender, receiver) = channel();
+        self.control_senders.lock().unwrap().push(sender);
+        receiver
+    }
+
+    fn stats(&self) -> TncStats {
+        TncStats {
+            frames_received: self.stats.frames_received.load(Ordering::SeqCst),
+            frames_sent: self.stats.frames_sent.load(Ordering::SeqCst),
+            bytes_received: self.stats.bytes_received.load(Ordering::SeqCst),
+            bytes_sent: self.stats.bytes_sent.load(Ordering::SeqCst),
+            parse_errors: self.stats.parse_errors.load(Ordering::SeqCst),
+            reconnects: self.imp.reconnects(),
+            frames_dropped: self.stats.frames_dropped.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// Send a frame and count it in the statistics.
+fn transmit(imp: &dyn TncImpl, stats: &StatsCounters, bytes: &[u8]) -> Result<(), TncError> {
+    imp.send_frame(bytes)?;
+    stats.frames_sent.fetch_add(1, Ordering::SeqCst);
+    stats
+        .bytes_sent
+        .fetch_add(bytes.len() as u64, Ordering::SeqCst);
+    Ok(())
+}
+
+fn random_byte() -> u8 {
+    random_u64() as u8
+}
+
+/// A random number for channel access and scheduling.
+pub(crate) fn random_u64() -> u64 {
+    // Each RandomState is seeded differently, which is random enough for our purposes
+    let mut hasher = RandomState::new().build_hasher();
+    Instant::now().hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Drop for TncInner {
+    fn drop(&mut self) {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+        self.imp.shutdown();
+    }
+}
+
+struct LinuxIfTnc {
+    socket: Arc<linux::Ax25RawSocket>,
+    ifindex: i32,
+    /// Used for receiving instead of `socket` when receiving for a single callsign
+    datagram: Option<Arc<linux::Ax25DatagramSocket>>,
+    callsign: String,
+}
+
+impl LinuxIfTnc {
+    fn open(config: &LinuxIfConfig) -> Result<Self, TncError> {
+        let socket = linux::Ax25RawSocket::new()?;
+        socket.set_receive_buffer_size(config.receive_buffer);
+        let interface = linux::find_interface(&config.callsign)?;
+        // Binding spares us from receiving frames for other interfaces. If it fails
+        // they are still filtered out on receipt, so there is no need to give up.
+        let _ = socket.bind_interface(interface.ifindex);
+        let datagram = match &config.receive_mode {
+            LinuxIfReceiveMode::Promiscuous => None,
+            LinuxIfReceiveMode::Callsign(local) => {
+                let port = interface.callsign.parse::<Address>().map_err(|_| {
+                    TncError::InterfaceNotFound {
+                        callsign: interface.callsign.clone(),
+                    }
+                })?;
+                let datagram = linux::Ax25DatagramSocket::bind(&port, local)?;
+                datagram.set_receive_buffer_size(config.receive_buffer);
+                Some(Arc::new(datagram))
             }
+        };
+        Ok(Self {
+            socket: Arc::new(socket),
+            ifindex: interface.ifindex,
+            datagram,
+            callsign: config.callsign.clone(),
+        })
+    }
+}
+
+impl TncImpl for LinuxIfTnc {
+    fn send_frame(&self, frame: &[u8]) -> Result<(), TncError> {
+        self.socket
+            .send_frame(frame, self.ifindex)
+            .map_err(|e| TncError::SendFrame { source: e })
+    }
+
+    fn receive_frame_timeout(&self, timeout: Duration) -> Result<Option<Vec<u8>>, TncError> {
+        match &self.datagram {
+            Some(datagram) => datagram.receive_frame(Some(timeout)),
+            None => self.socket.receive_frame(self.ifindex, Some(timeout)),
         }
+        .map_err(|e| TncError::ReceiveFrame { source: e })
     }
 
     fn clone(&self) -> Box<dyn TncImpl> {
         Box::new(LinuxIfTnc {
             socket: self.socket.clone(),
             ifindex: self.ifindex,
+            datagram: self.datagram.clone(),
+            callsign: self.callsign.clone(),
         })
     }
 
+    fn description(&self) -> String {
+        format!("linuxif {}", self.callsign)
+    }
+
     fn shutdown(&self) {
         self.socket.shutdown();
+        if let Some(datagram) = &self.datagram {
+            datagram.shutdown();
+        }
     }
 }
 
 struct TcpKissTnc {
     iface: Arc<kiss::TcpKissInterface>,
+    reconnects: bool,
+    /// The configured host and port, for describing the TNC while disconnected
+    address: String,
 }
 
 impl TcpKissTnc {
     fn open(config: &TcpKissConfig) -> Result<Self, TncError> {
+        let address = host_port_string(&config.host, config.port);
+        let addrs = resolve(&config.host, config.port)?;
+        let mut iface =
+            kiss::TcpKissInterface::new_with_timeout(&addrs[..], config.connect_timeout)
+                .map_err(|e| TncError::OpenTnc { source: e })?
+                .with_max_buffer(config.max_buffer);
+        if let Some(policy) = &config.reconnect {
+            iface = iface.with_reconnect(policy.clone());
+        }
+        if config.smack {
+            iface = iface.with_smack();
+        }
+        if let Some(policy) = &config.keepalive {
+            iface = iface
+                .with_keepalive(policy.clone())
+                .map_err(|e| TncError::OpenTnc { source: e })?;
+        }
+        Ok(Self {
+            iface: Arc::new(iface),
+            reconnects: config.reconnect.is_some(),
+            address,
+        })
+    }
+}
+
+impl TncImpl for TcpKissTnc {
+    fn send_frame(&self, frame: &[u8]) -> Result<(), TncError> {
+        self.iface.send_frame(frame).map_err(|e| {
+            if self.reconnects && e.kind() == std::io::ErrorKind::NotConnected {
+                TncError::Reconnecting
+            } else {
+                TncError::SendFrame { source: e }
+            }
+        })
+    }
+
+    fn receive_frame_timeout(&self, timeout: Duration) -> Result<Option<Vec<u8>>, TncError> {
+        self.iface.receive_frame_timeout(timeout).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::InvalidData {
+                TncError::ReceiveOverflow { source: e }
+            } else {
+                TncError::ReceiveFrame { source: e }
+            }
+        })
+    }
+
+    fn clone(&self) -> Box<dyn TncImpl> {
+        Box::new(TcpKissTnc {
+            iface: self.iface.clone(),
+            reconnects: self.reconnects,
+            address: self.address.clone(),
+        })
+    }
+
+    fn description(&self) -> String {
+        match self.iface.peer_addr() {
+            Ok(addr) => format!("tcpkiss {}", addr),
+            Err(_) => format!("tcpkiss {}", self.address),
+        }
+    }
+
+    fn reconnects(&self) -> u64 {
+        self.iface.reconnect_count()
+    }
+
+    fn is_connected(&self) -> bool {
+        self.iface.is_connected()
+    }
+
+    fn shutdown(&self) {
+        self.iface.shutdown();
+    }
+
+    fn take_control_frame(&self) -> Option<kiss::KissControlFrame> {
+        self.iface.take_control_frame()
+    }
+
+    fn send_kiss_command(&self, command: u8, port: u8, data: &[u8]) -> Result<(), TncError> {
+        self.iface
+            .send_kiss_command(command, port, data)
+            .map_err(|e| TncError::ConfigFailed { source: e })
+    }
+}
+
+struct UdpKissTnc {
+    iface: Arc<kiss::UdpKissInterface>,
+}
+
+impl UdpKissTnc {
+    fn open(config: &UdpKissConfig) -> Result<Self, TncError> {
+        let remote = resolve(&config.host, config.port)?[0];
+        let local = match remote {
+            SocketAddr::V4(_) => ("0.0.0.0", config.local_port),
+            SocketAddr::V6(_) => ("::", config.local_port),
+        };
+        let mut iface = kiss::UdpKissInterface::new(local, remote)
+            .map_err(|e| TncError::OpenTnc { source: e })?;
+        if config.any_sender {
+            iface = iface.with_any_sender();
+        }
+        Ok(Self {
+            iface: Arc::new(iface),
+        })
+    }
+}
+
+impl TncImpl for UdpKissTnc {
+    fn send_frame(&self, frame: &[u8]) -> Result<(), TncError> {
+        self.iface
+            .send_frame(frame)
+            .map_err(|e| TncError::SendFrame { source: e })
+    }
+
+    fn receive_frame_timeout(&self, timeout: Duration) -> Result<Option<Vec<u8>>, TncError> {
+        self.iface
+            .receive_frame_timeout(timeout)
+            .map_err(|e| TncError::ReceiveFrame { source: e })
+    }
+
+    fn clone(&self) -> Box<dyn TncImpl> {
+        Box::new(UdpKissTnc {
+            iface: self.iface.clone(),
+        })
+    }
+
+    fn description(&self) -> String {
+        format!("udpkiss {}", self.iface.remote_addr())
+    }
+
+    fn shutdown(&self) {
+        self.iface.shutdown();
+    }
+
+    fn take_control_frame(&self) -> Option<kiss::KissControlFrame> {
+        self.iface.take_control_frame()
+    }
+
+    fn send_kiss_command(&self, command: u8, port: u8, data: &[u8]) -> Result<(), TncError> {
+        self.iface
+            .send_kiss_command(command, port, data)
+            .map_err(|e| TncError::ConfigFailed { source: e })
+    }
+}
+
+struct AxudpTnc {
+    iface: Arc<axudp::AxudpInterface>,
+}
+
+impl AxudpTnc {
+    fn open(config: &AxudpConfig) -> Result<Self, TncError> {
+        let remote = resolve(&config.host, config.port)?[0];
+        let local = match remote {
+            SocketAddr::V4(_) => ("0.0.0.0", config.local_port),
+            SocketAddr::V6(_) => ("::", config.local_port),
+        };
+        let mut iface = axudp::AxudpInterface::new(local, remote)
+            .map_err(|e| TncError::OpenTnc { source: e })?;
+        if config.any_sender {
+            iface = iface.with_any_sender();
+        }
+        Ok(Self {
+            iface: Arc::new(iface),
+        })
+    }
+}
+
+impl TncImpl for AxudpTnc {
+    fn send_frame(&self, frame: &[u8]) -> Result<(), TncError> {
+        self.iface
+            .send_frame(frame)
+            .map_err(|e| TncError::SendFrame { source: e })
+    }
+
+    fn receive_frame_timeout(&self, timeout: Duration) -> Result<Option<Vec<u8>>, TncError> {
+        self.iface
+            .receive_frame_timeout(timeout)
+            .map_err(|e| TncError::ReceiveFrame { source: e })
+    }
+
+    fn clone(&self) -> Box<dyn TncImpl> {
+        Box::new(AxudpTnc {
+            iface: self.iface.clone(),
+        })
+    }
+
+    fn description(&self) -> String {
+        format!("axudp {}", self.iface.remote_addr())
+    }
+
+    fn shutdown(&self) {
+        self.iface.shutdown();
+    }
+}
+
+struct KissStreamTnc {
+    iface: Arc<kiss::StreamKissInterface>,
+    description: String,
+}
+
+/// How long a read from a serial port may block before the receive loop regains control
+const SERIAL_READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Separate handles for reading from and writing to one serial port
+type SerialHandles = (
+    Box<dyn serialport::SerialPort>,
+    Box<dyn serialport::SerialPort>,
+);
+
+/// Open a serial port, returning separate handles for reading and writing.
+fn open_serial_port(
+    path: &str,
+    baud_rate: u32,
+    dtr: bool,
+    rts: bool,
+) -> Result<SerialHandles, TncError> {
+    let open = || -> Result<_, serialport::Error> {
+        let mut port = serialport::new(path, baud_rate)
+            .timeout(SERIAL_READ_TIMEOUT)
+            .open()?;
+        port.write_data_terminal_ready(dtr)?;
+        port.write_request_to_send(rts)?;
+        Ok((port.try_clone()?, port))
+    };
+    open().map_err(|e| TncError::OpenTnc { source: e.into() })
+}
+
+impl KissStreamTnc {
+    fn open_serial(config: &SerialKissConfig) -> Result<Self, TncError> {
+        let (reader, writer) =
+            open_serial_port(&config.path, config.baud_rate, config.dtr, config.rts)?;
+        Ok(Self {
+            iface: Arc::new(kiss::StreamKissInterface::new_split(reader, writer)),
+            description: format!("serialkiss {}", config.path),
+        })
+    }
+}
+
+impl TncImpl for KissStreamTnc {
+    fn send_frame(&self, frame: &[u8]) -> Result<(), TncError> {
+        self.iface
+            .send_frame(frame)
+            .map_err(|e| TncError::SendFrame { source: e })
+    }
+
+    fn receive_frame_timeout(&self, timeout: Duration) -> Result<Option<Vec<u8>>, TncError> {
+        self.iface.receive_frame_timeout(timeout).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::InvalidData {
+                TncError::ReceiveOverflow { source: e }
+            } else {
+                TncError::ReceiveFrame { source: e }
+            }
+        })
+    }
+
+    fn clone(&self) -> Box<dyn TncImpl> {
+        Box::new(KissStreamTnc {
+            iface: self.iface.clone(),
+            description: self.description.clone(),
+        })
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn shutdown(&self) {
+        self.iface.shutdown();
+    }
+
+    fn take_control_frame(&self) -> Option<kiss::KissControlFrame> {
+        self.iface.take_control_frame()
+    }
+
+    fn send_kiss_command(&self, command: u8, port: u8, data: &[u8]) -> Result<(), TncError> {
+        self.iface
+            .send_kiss_command(command, port, data)
+            .map_err(|e| TncError::ConfigFailed { source: e })
+    }
+}
+
+struct SixpackTnc {
+    iface: Arc<sixpack::SixpackInterface>,
+    path: String,
+}
+
+impl SixpackTnc {
+    fn open(config: &SixpackConfig) -> Result<Self, TncError> {
+        let (reader, writer) =
+            open_serial_port(&config.path, config.baud_rate, config.dtr, config.rts)?;
+        let iface = sixpack::SixpackInterface::new_split(reader, writer)
+            .map_err(|e| TncError::OpenTnc { source: e })?;
+        iface.set_tx_delay(config.tx_delay);
+        Ok(Self {
+            iface: Arc::new(iface),
+            path: config.path.clone(),
+        })
+    }
+}
+
+impl TncImpl for SixpackTnc {
+    fn send_frame(&self, frame: &[u8]) -> Result<(), TncError> {
+        self.iface
+            .send_frame(frame)
+            .map_err(|e| TncError::SendFrame { source: e })
+    }
+
+    fn receive_frame_timeout(&self, timeout: Duration) -> Result<Option<Vec<u8>>, TncError> {
+        self.iface
+            .receive_frame_timeout(timeout)
+            .map_err(|e| TncError::ReceiveFrame { source: e })
+    }
+
+    fn clone(&self) -> Box<dyn TncImpl> {
+        Box::new(SixpackTnc {
+            iface: self.iface.clone(),
+            path: self.path.clone(),
+        })
+    }
+
+    fn description(&self) -> String {
+        format!("sixpack {}", self.path)
+    }
+
+    fn shutdown(&self) {
+        self.iface.shutdown();
+    }
+
+    /// Only the TX delay is held by the TNC interface; the other parameters describe
+    /// channel access, which the host performs for 6PACK.
+    fn send_kiss_command(&self, command: u8, port: u8, data: &[u8]) -> Result<(), TncError> {
+        match (command, port, data) {
+            (kiss::CMD_TX_DELAY, 0, &[units]) => {
+                self.iface.set_tx_delay(units);
+                Ok(())
+            }
+            _ => Err(TncError::ConfigFailed {
+                source: std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "6PACK TNCs only accept the TX delay setting",
+                ),
+            }),
+        }
+    }
+}
+
+struct LoopbackTnc {
+    tx: Arc<Mutex<Sender<Vec<u8>>>>,
+    rx: Arc<Mutex<Receiver<Vec<u8>>>>,
+    is_shutdown: Arc<AtomicBool>,
+}
+
+impl LoopbackTnc {
+    fn new(tx: Sender<Vec<u8>>, rx: Receiver<Vec<u8>>) -> Self {
+        Self {
+            tx: Arc::new(Mutex::new(tx)),
+            rx: Arc::new(Mutex::new(rx)),
+            is_shutdown: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl TncImpl for LoopbackTnc {
+    fn send_frame(&self, frame: &[u8]) -> Result<(), TncError> {
+        self.tx
+            .lock()
+            .unwrap()
+            .send(frame.to_vec())
+            .map_err(|_| TncError::SendFrame {
+                source: std::io::Error::new(
+                    std::io::ErrorKind::NotConnected,
+                    "other end of loopback has been closed",
+                ),
+            })
+    }
+
+    fn receive_frame_timeout(&self, timeout: Duration) -> Result<Option<Vec<u8>>, TncError> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(TncError::ReceiveFrame {
+                source: std::io::Error::new(
+                    std::io::ErrorKind::NotConnected,
+                    "interface has been shut down",
+                ),
+            });
+        }
+        match self.rx.lock().unwrap().recv_timeout(timeout) {
+            Ok(frame) => Ok(Some(frame)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(TncError::ReceiveFrame {
+                source: std::io::Error::new(
+                    std::io::ErrorKind::NotConnected,
+                    "other end of loopback has been closed",
+                ),
+            }),
+        }
+    }
+
+    fn clone(&self) -> Box<dyn TncImpl> {
+        Box::new(LoopbackTnc {
+            tx: self.tx.clone(),
+            rx: self.rx.clone(),
+            is_shutdown: self.is_shutdown.clone(),
+        })
+    }
+
+    fn shutdown(&self) {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+    }
+
+    fn description(&self) -> String {
+        "loopback".to_string()
+    }
+
+    fn wait_for_receivers(&self) -> bool {
+        true
+    }
+}
+
+struct AgwpeTnc {
+    iface: Arc<agwpe::AgwpeInterface>,
+    description: String,
+}
+
+impl AgwpeTnc {
+    fn open(config: &AgwpeConfig) -> Result<Self, TncError> {
+        let addrs = resolve(&config.host, config.port)?;
         Ok(Self {
             iface: Arc::new(
-                kiss::TcpKissInterface::new(format!("{}:{}", config.host, config.port))
+                agwpe::AgwpeInterface::new(&addrs[..], config.radio_port)
                     .map_err(|e| TncError::OpenTnc { source: e })?,
             ),
+            description: format!(
+                "agwpe {} port {}",
+                host_port_string(&config.host, config.port),
+                config.radio_port
+            ),
         })
     }
 }
 
-impl TncImpl for TcpKissTnc {
-    fn send_frame(&self, frame: &Ax25Frame) -> Result<(), TncError> {
-        self.iface
-            .send_frame(&frame.to_bytes())
-            .map_err(|e| TncError::SendFrame { source: e })
+impl TncImpl for AgwpeTnc {
+    fn send_frame(&self, frame: &[u8]) -> Result<(), TncError> {
+        self.iface
+            .send_frame(frame)
+            .map_err(|e| TncError::SendFrame { source: e })
+    }
+
+    fn receive_frame_timeout(&self, timeout: Duration) -> Result<Option<Vec<u8>>, TncError> {
+        self.iface
+            .receive_frame_timeout(timeout)
+            .map_err(|e| TncError::ReceiveFrame { source: e })
+    }
+
+    fn clone(&self) -> Box<dyn TncImpl> {
+        Box::new(AgwpeTnc {
+            iface: self.iface.clone(),
+            description: self.description.clone(),
+        })
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn shutdown(&self) {
+        self.iface.shutdown();
+    }
+}
+
+struct FileTnc {
+    iface: Arc<file::FileInterface>,
+    path: String,
+}
+
+impl FileTnc {
+    fn open(config: &FileConfig) -> Result<Self, TncError> {
+        Ok(Self {
+            iface: Arc::new(
+                file::FileInterface::new(
+                    config.path.as_ref(),
+                    config.output.as_deref().map(AsRef::as_ref),
+                    config.pacing,
+                )
+                .map_err(|e| TncError::OpenTnc { source: e })?,
+            ),
+            path: config.path.clone(),
+        })
+    }
+}
+
+impl TncImpl for FileTnc {
+    fn send_frame(&self, frame: &[u8]) -> Result<(), TncError> {
+        self.iface
+            .send_frame(frame)
+            .map_err(|e| TncError::SendFrame { source: e })
+    }
+
+    fn receive_frame_timeout(&self, timeout: Duration) -> Result<Option<Vec<u8>>, TncError> {
+        self.iface
+            .receive_frame_timeout(timeout)
+            .map_err(|e| TncError::ReceiveFrame { source: e })
+    }
+
+    fn clone(&self) -> Box<dyn TncImpl> {
+        Box::new(FileTnc {
+            iface: self.iface.clone(),
+            path: self.path.clone(),
+        })
+    }
+
+    fn description(&self) -> String {
+        format!("file {}", self.path)
+    }
+
+    fn wait_for_receivers(&self) -> bool {
+        true
+    }
+
+    fn shutdown(&self) {
+        self.iface.shutdown();
+    }
+}
+
+/// Wraps another TNC, refusing to send anything to it.
+struct ReceiveOnlyTnc {
+    inner: Box<dyn TncImpl>,
+}
+
+impl TncImpl for ReceiveOnlyTnc {
+    fn send_frame(&self, _frame: &[u8]) -> Result<(), TncError> {
+        Err(TncError::ReceiveOnly)
     }
 
-    fn receive_frame(&self) -> Result<Ax25Frame, TncError> {
-        loop {
-            let bytes = self
-                .iface
-                .receive_frame()
-                .map_err(|e| TncError::ReceiveFrame { source: e })?;
-            if let Ok(parsed) = Ax25Frame::from_bytes(&bytes) {
-                return Ok(parsed);
-            }
-        }
+    fn receive_frame_timeout(&self, timeout: Duration) -> Result<Option<Vec<u8>>, TncError> {
+        self.inner.receive_frame_timeout(timeout)
     }
 
     fn clone(&self) -> Box<dyn TncImpl> {
-        Box::new(TcpKissTnc {
-            iface: self.iface.clone(),
+        Box::new(ReceiveOnlyTnc {
+            inner: self.inner.clone(),
         })
     }
 
+    fn description(&self) -> String {
+        format!("{} (receive only)", self.inner.description())
+    }
+
+    fn reconnects(&self) -> u64 {
+        self.inner.reconnects()
+    }
+
+    fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    fn take_control_frame(&self) -> Option<kiss::KissControlFrame> {
+        self.inner.take_control_frame()
+    }
+
+    fn wait_for_receivers(&self) -> bool {
+        self.inner.wait_for_receivers()
+    }
+
+    fn send_kiss_command(&self, _command: u8, _port: u8, _data: &[u8]) -> Result<(), TncError> {
+        Err(TncError::ReceiveOnly)
+    }
+
     fn shutdown(&self) {
-        self.iface.shutdown();
+        self.inner.shutdown();
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_util::{local_listener, tcp_kiss_address, tcp_kiss_pair, test_frame};
 
     #[test]
     fn parse_tnc_addresses() {
@@ -406,17 +2172,110 @@ mod test {
                 config: ConnectConfig::TcpKiss(TcpKissConfig {
                     host: "192.168.0.1".to_string(),
                     port: 8001_u16,
+                    reconnect: None,
+                    smack: false,
+                    max_buffer: kiss::DEFAULT_MAX_BUFFER,
+                    connect_timeout: kiss::DEFAULT_CONNECT_TIMEOUT,
+                    keepalive: None,
                 })
             })
         );
         assert_eq!(
             "tnc:linuxif:VK7NTK-2".parse::<TncAddress>(),
             Ok(TncAddress {
-                config: ConnectConfig::LinuxIf(LinuxIfConfig {
-                    callsign: "VK7NTK-2".to_string(),
+                config: ConnectConfig::LinuxIf(LinuxIfConfig::new("VK7NTK-2"))
+            })
+        );
+        assert_eq!(
+            "tnc:udpkiss:127.0.0.1:8093".parse::<TncAddress>(),
+            Ok(TncAddress {
+                config: ConnectConfig::UdpKiss(UdpKissConfig {
+                    host: "127.0.0.1".to_string(),
+                    port: 8093_u16,
+                    local_port: 0,
+                    any_sender: false,
+                })
+            })
+        );
+        assert_eq!(
+            "tnc:axudp:node.example.com:10093".parse::<TncAddress>(),
+            Ok(TncAddress {
+                config: ConnectConfig::Axudp(AxudpConfig {
+                    host: "node.example.com".to_string(),
+                    port: 10093_u16,
+                    local_port: 10093_u16,
+                    any_sender: false,
+                })
+            })
+        );
+        assert_eq!(
+            "tnc:serialkiss:COM3:9600".parse::<TncAddress>(),
+            Ok(TncAddress {
+                config: ConnectConfig::SerialKiss(SerialKissConfig {
+                    path: "COM3".to_string(),
+                    baud_rate: 9600,
+                    dtr: true,
+                    rts: true,
+                })
+            })
+        );
+        assert_eq!(
+            "tnc:serialkiss:/dev/serial/by-path/pci-0:1.0:1200".parse::<TncAddress>(),
+            Ok(TncAddress {
+                config: ConnectConfig::SerialKiss(SerialKissConfig::new(
+                    "/dev/serial/by-path/pci-0:1.0",
+                    1200
+                ))
+            })
+        );
+        assert!(matches!(
+            "tnc:serialkiss:/dev/ttyUSB0:fast".parse::<TncAddress>(),
+            Err(ParseError::InvalidBaudRate { input, .. }) if input == "fast"
+        ));
+        assert_eq!(
+            "tnc:sixpack:/dev/ttyS0:19200".parse::<TncAddress>(),
+            Ok(TncAddress {
+                config: ConnectConfig::Sixpack(SixpackConfig {
+                    path: "/dev/ttyS0".to_string(),
+                    baud_rate: 19200,
+                    tx_delay: sixpack::DEFAULT_TX_DELAY,
+                    dtr: true,
+                    rts: true,
+                })
+            })
+        );
+        assert!(matches!(
+            "tnc:sixpack:/dev/ttyS0".parse::<TncAddress>(),
+            Err(ParseError::WrongParameterCount {
+                expected: 2,
+                actual: 1,
+                ..
+            })
+        ));
+        assert_eq!(
+            "tnc:agwpe:localhost:8000".parse::<TncAddress>(),
+            Ok(TncAddress {
+                config: ConnectConfig::Agwpe(AgwpeConfig {
+                    host: "localhost".to_string(),
+                    port: 8000_u16,
+                    radio_port: 0,
+                })
+            })
+        );
+        assert_eq!(
+            "tnc:file:C:\\captures\\aprs.pcap".parse::<TncAddress>(),
+            Ok(TncAddress {
+                config: ConnectConfig::File(FileConfig {
+                    path: "C:\\captures\\aprs.pcap".to_string(),
+                    output: None,
+                    pacing: FilePacing::Burst,
                 })
             })
         );
+        assert!(matches!(
+            "tnc:file:".parse::<TncAddress>(),
+            Err(ParseError::WrongParameterCount { actual: 0, .. })
+        ));
         assert!(matches!(
             "fish".parse::<TncAddress>(),
             Err(ParseError::NoTncPrefix { .. })
@@ -471,5 +2330,651 @@ mod test {
                 _ => false,
             }
         );
+        assert!(matches!(
+            "tnc:tcpkiss:[::1]".parse::<TncAddress>(),
+            Err(ParseError::WrongParameterCount { actual: 1, .. })
+        ));
+        assert!(matches!("tnc:tcpkiss:[::1:8001".parse::<TncAddress>(),
+            Err(ParseError::InvalidHost { input }) if input == "[::1:8001"));
+        assert!(matches!(
+            "tnc:udpkiss:[myhost]:8001".parse::<TncAddress>(),
+            Err(ParseError::InvalidHost { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_bracketed_ipv6_hosts() {
+        assert_eq!(
+            "tnc:tcpkiss:[::1]:8001".parse::<TncAddress>(),
+            Ok(TncAddress::new_tcpkiss(TcpKissConfig::new("::1", 8001)))
+        );
+        assert_eq!(
+            "tnc:udpkiss:[fe80::1]:8093".parse::<TncAddress>(),
+            Ok(TncAddress::new_udpkiss(UdpKissConfig::new("fe80::1", 8093)))
+        );
+        assert_eq!(
+            "tnc:tcpkiss:myhost.local:8001".parse::<TncAddress>(),
+            Ok(TncAddress::new_tcpkiss(TcpKissConfig::new(
+                "myhost.local",
+                8001
+            )))
+        );
+    }
+
+    #[test]
+    fn tcpkiss_over_ipv6() {
+        use std::net::TcpListener;
+
+        // Skip where the host has no IPv6 loopback
+        let listener = match TcpListener::bind("[::1]:0") {
+            Ok(listener) => listener,
+            Err(_) => return,
+        };
+        let port = listener.local_addr().unwrap().port();
+        let addr = format!("tnc:tcpkiss:[::1]:{}", port)
+            .parse::<TncAddress>()
+            .unwrap();
+        let tnc = Tnc::open(&addr).unwrap();
+        let _server = listener.accept().unwrap();
+        assert_eq!(tnc.description(), format!("tcpkiss [::1]:{}", port));
+    }
+
+    #[test]
+    fn connect_timeout_bounds_open() {
+        use std::net::TcpStream;
+        use std::time::Instant;
+
+        assert_eq!(
+            TcpKissConfig::new("localhost", 8001).connect_timeout,
+            kiss::DEFAULT_CONNECT_TIMEOUT
+        );
+
+        // Fill the backlog of a listener that never accepts so further connections
+        // hang. Skip if the host never stops accepting them.
+        let listener = local_listener();
+        let local = listener.local_addr().unwrap();
+        let mut held = Vec::new();
+        loop {
+            match TcpStream::connect_timeout(&local, Duration::from_millis(50)) {
+                Ok(stream) if held.len() < 1000 => held.push(stream),
+                Ok(_) => return,
+                Err(_) => break,
+            }
+        }
+
+        let mut config = TcpKissConfig::new("127.0.0.1", local.port());
+        config.connect_timeout = Duration::from_millis(200);
+        let start = Instant::now();
+        let result = Tnc::open(&TncAddress::new_tcpkiss(config));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            result.err().and_then(|e| e.io_error_kind()),
+            Some(std::io::ErrorKind::TimedOut)
+        );
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn incoming_stream_without_runtime() {
+        use futures_core::Stream;
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct Unpark(thread::Thread);
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let (tnc, other) = Tnc::loopback_pair();
+        let mut stream = tnc.incoming_stream();
+        let frame = test_frame(b"hello");
+        other.send_frame(&frame).unwrap();
+
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            match std::pin::Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Ready(next) => {
+                    assert_eq!(next.unwrap().unwrap(), frame);
+                    break;
+                }
+                Poll::Pending => {
+                    assert!(Instant::now() < deadline);
+                    thread::park_timeout(Duration::from_millis(100));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unresolvable_host_is_distinct() {
+        let addr = TncAddress::new_tcpkiss(TcpKissConfig::new("no-such-host.invalid", 8001));
+        assert!(matches!(
+            Tnc::open(&addr),
+            Err(TncError::ResolveHost { host, .. }) if host == "no-such-host.invalid"
+        ));
+    }
+
+    #[test]
+    fn receive_thread_exits_on_drop() {
+        use std::sync::mpsc::RecvTimeoutError;
+
+        let (tnc, _server) = tcp_kiss_pair();
+        let receiver = tnc.incoming();
+        drop(tnc);
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap_err(),
+            RecvTimeoutError::Disconnected
+        );
+    }
+
+    #[test]
+    fn receive_thread_exits_on_remote_close() {
+        use std::sync::mpsc::RecvTimeoutError;
+
+        let (tnc, server) = tcp_kiss_pair();
+        let receiver = tnc.incoming();
+        drop(server);
+
+        let timeout = Duration::from_secs(5);
+        let err = receiver.recv_timeout(timeout).unwrap().unwrap_err();
+        assert!(matches!(&*err, TncError::ReceiveFrame { source }
+            if source.kind() == std::io::ErrorKind::UnexpectedEof));
+        assert_eq!(
+            receiver.recv_timeout(timeout).unwrap_err(),
+            RecvTimeoutError::Disconnected
+        );
+    }
+
+    #[test]
+    fn frames_ends_when_tnc_closes() {
+        use std::io::Write;
+
+        let (tnc, mut server) = tcp_kiss_pair();
+        let frames = tnc.frames();
+        let frame = test_frame(b"hi");
+        server
+            .write_all(&kiss::kiss_encode(&frame.to_bytes()))
+            .unwrap();
+        drop(server);
+
+        let results: Vec<_> = frames.collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap(), &frame);
+        assert!(matches!(
+            &*results[1].clone().unwrap_err(),
+            TncError::ReceiveFrame { .. }
+        ));
+    }
+
+    #[test]
+    fn address_kind_and_clone() {
+        for (address, kind) in [
+            ("tnc:tcpkiss:localhost:8001", "tcpkiss"),
+            ("tnc:udpkiss:localhost:8093", "udpkiss"),
+            ("tnc:axudp:localhost:10093", "axudp"),
+            ("tnc:serialkiss:/dev/ttyUSB0:9600", "serialkiss"),
+            ("tnc:sixpack:/dev/ttyS0:19200", "sixpack"),
+            ("tnc:linuxif:VK7NTK-2", "linuxif"),
+            ("tnc:agwpe:localhost:8000", "agwpe"),
+            ("tnc:file:capture.pcap", "file"),
+        ] {
+            let parsed = address.parse::<TncAddress>().unwrap();
+            assert_eq!(parsed.config_kind(), kind);
+            assert_eq!(parsed.clone(), parsed);
+        }
+
+        let config = TcpKissConfig::new("localhost", 8001);
+        let mut tweaked = config.clone();
+        tweaked.smack = true;
+        assert_ne!(
+            TncAddress::new_tcpkiss(tweaked),
+            TncAddress::new_tcpkiss(config)
+        );
+    }
+
+    #[test]
+    fn arbitrary_kiss_commands() {
+        use std::io::Read;
+
+        let (tnc, mut server) = tcp_kiss_pair();
+        tnc.send_kiss_command(kiss::CMD_SET_HARDWARE, 1, b"TNC")
+            .unwrap();
+        tnc.send_kiss_command(kiss::CMD_RETURN, 0, &[]).unwrap();
+        assert!(matches!(
+            tnc.send_kiss_command(kiss::CMD_TX_DELAY, 16, &[1]),
+            Err(TncError::ConfigFailed { .. })
+        ));
+        let mut received = [0u8; 9];
+        server.read_exact(&mut received).unwrap();
+        assert_eq!(
+            received,
+            [0xC0, 0x16, b'T', b'N', b'C', 0xC0, 0xC0, 0xFF, 0xC0]
+        );
+
+        let (a, _b) = Tnc::loopback_pair();
+        assert!(matches!(
+            a.send_kiss_command(kiss::CMD_RETURN, 0, &[]),
+            Err(TncError::ConfigFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn queued_frames_keep_their_distance() {
+        let (tnc, other) = Tnc::loopback_pair();
+        let incoming = other.incoming();
+        let gap = Duration::from_millis(100);
+        tnc.set_channel_access(ChannelAccess {
+            persistence: 255,
+            min_gap: gap,
+            ..Default::default()
+        });
+
+        let start = Instant::now();
+        for i in 0..3u8 {
+            tnc.send_frame_queued(&test_frame(&[i]));
+        }
+        let timeout = Duration::from_secs(5);
+        for i in 0..3u8 {
+            let frame = incoming.recv_timeout(timeout).unwrap().unwrap();
+            assert_eq!(frame.info_string_lossy().unwrap().as_bytes(), [i]);
+        }
+        assert!(start.elapsed() >= gap * 2);
+
+        // The frame can arrive before the queue thread updates the statistics
+        let deadline = Instant::now() + timeout;
+        while tnc.stats().frames_sent < 3 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(tnc.stats().frames_sent, 3);
+    }
+
+    #[test]
+    fn queued_frame_waits_for_quiet_channel() {
+        let (tnc, other) = Tnc::loopback_pair();
+        let heard = tnc.incoming();
+        let incoming = other.incoming();
+        let busy = Duration::from_millis(300);
+        tnc.set_channel_access(ChannelAccess {
+            persistence: 255,
+            busy_after_receive: busy,
+            ..Default::default()
+        });
+        let frame = test_frame(b"hi");
+
+        // Another station is heard just before we queue a frame
+        other.send_frame(&frame).unwrap();
+        let timeout = Duration::from_secs(5);
+        heard.recv_timeout(timeout).unwrap().unwrap();
+        let heard_at = Instant::now();
+        tnc.send_frame_queued(&frame);
+        incoming.recv_timeout(timeout).unwrap().unwrap();
+        assert!(heard_at.elapsed() >= busy - Duration::from_millis(50));
+    }
+
+    #[test]
+    fn io_error_kind_of_failed_open() {
+        // Find a port with nothing listening on it
+        let port = local_listener().local_addr().unwrap().port();
+        let err = Tnc::open(&TncAddress::new_tcpkiss(TcpKissConfig::new(
+            "127.0.0.1",
+            port,
+        )))
+        .err()
+        .unwrap();
+        assert!(matches!(err, TncError::OpenTnc { .. }));
+        assert_eq!(
+            err.io_error_kind(),
+            Some(std::io::ErrorKind::ConnectionRefused)
+        );
+        // The same error is reachable through the standard source chain
+        let source = err.source().unwrap().downcast_ref::<std::io::Error>();
+        assert!(std::ptr::eq(err.io_error().unwrap(), source.unwrap()));
+        assert_eq!(TncError::Reconnecting.io_error_kind(), None);
+    }
+
+    #[test]
+    fn description_names_tnc() {
+        let (tnc, server) = tcp_kiss_pair();
+        let port = server.local_addr().unwrap().port();
+        assert_eq!(tnc.description(), format!("tcpkiss 127.0.0.1:{}", port));
+        assert_eq!(tnc.clone().description(), tnc.description());
+
+        let (a, _b) = Tnc::loopback_pair();
+        assert_eq!(a.description(), "loopback");
+    }
+
+    #[test]
+    fn receive_only_never_writes() {
+        use std::io::{Read, Write};
+
+        let listener = local_listener();
+        let port = listener.local_addr().unwrap().port();
+        let tnc = Tnc::open_receive_only(&tcp_kiss_address(&listener)).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+        assert_eq!(
+            tnc.description(),
+            format!("tcpkiss 127.0.0.1:{} (receive only)", port)
+        );
+
+        let frame = test_frame(b"hi");
+        assert!(matches!(
+            tnc.clone().send_frame(&frame),
+            Err(TncError::ReceiveOnly)
+        ));
+        assert!(matches!(tnc.set_tx_delay(30), Err(TncError::ReceiveOnly)));
+        tnc.send_frame_queued(&frame);
+        server
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .unwrap();
+        let mut buf = [0u8; 64];
+        assert!(server.read(&mut buf).is_err());
+        assert_eq!(tnc.stats().frames_sent, 0);
+
+        let incoming = tnc.incoming();
+        server
+            .write_all(&kiss::kiss_encode(&frame.to_bytes()))
+            .unwrap();
+        let received = incoming.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(received.unwrap(), frame);
+    }
+
+    #[test]
+    fn raw_receiver_gets_unparseable_frames() {
+        use std::io::Write;
+
+        let (tnc, mut server) = tcp_kiss_pair();
+        let incoming = tnc.incoming();
+        let incoming_raw = tnc.incoming_raw();
+
+        let frame = test_frame(b"hello");
+        let garbage = vec![0x01, 0x02, 0x03];
+        let before = SystemTime::now();
+        server.write_all(&kiss::kiss_encode(&garbage)).unwrap();
+        server
+            .write_all(&kiss::kiss_encode(&frame.to_bytes()))
+            .unwrap();
+
+        let timeout = Duration::from_secs(5);
+        let first = incoming_raw.recv_timeout(timeout).unwrap().unwrap();
+        assert_eq!(first.bytes, garbage);
+        assert!(first.frame.is_err());
+        assert!(first.timestamp >= before && first.timestamp <= SystemTime::now());
+        let second = incoming_raw.recv_timeout(timeout).unwrap().unwrap();
+        assert_eq!(second.bytes, frame.to_bytes());
+        assert_eq!(second.frame.unwrap(), frame);
+        // The parsed channel only sees the valid frame
+        assert_eq!(incoming.recv_timeout(timeout).unwrap().unwrap(), frame);
+
+        tnc.send_frame(&frame).unwrap();
+        let len = frame.to_bytes().len() as u64;
+        assert_eq!(
+            tnc.stats(),
+            TncStats {
+                frames_received: 2,
+                frames_sent: 1,
+                bytes_received: garbage.len() as u64 + len,
+                bytes_sent: len,
+                parse_errors: 1,
+                reconnects: 0,
+                frames_dropped: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn file_tnc_replays_and_records() {
+        use ax25::pcap::{PcapReader, PcapWriter};
+
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("ax25_tnc_replay_{}.pcap", std::process::id()));
+        let output = dir.join(format!("ax25_tnc_record_{}.pcap", std::process::id()));
+        let _ = std::fs::remove_file(&output);
+        let frames: Vec<Ax25Frame> = ["one", "two"]
+            .iter()
+            .map(|info| test_frame(info.as_bytes()))
+            .collect();
+        let mut writer = PcapWriter::create(&input).unwrap();
+        for frame in &frames {
+            writer.write_frame(frame).unwrap();
+        }
+        drop(writer);
+
+        let mut config = FileConfig::new(input.to_str().unwrap());
+        config.output = Some(output.to_str().unwrap().to_string());
+        let tnc = Tnc::open(&TncAddress::new_file(config)).unwrap();
+        let incoming = tnc.incoming();
+        let timeout = Duration::from_secs(5);
+        for frame in &frames {
+            assert_eq!(&incoming.recv_timeout(timeout).unwrap().unwrap(), frame);
+        }
+        tnc.send_frame(&frames[0]).unwrap();
+        drop(tnc);
+
+        let recorded: Vec<Ax25Frame> = PcapReader::open(&output)
+            .unwrap()
+            .map(|f| f.unwrap())
+            .collect();
+        assert_eq!(recorded, vec![frames[0].clone()]);
+        let _ = std::fs::remove_file(&input);
+        let _ = std::fs::remove_file(&output);
+    }
+
+    #[test]
+    fn request_waits_for_matching_reply() {
+        let (tnc, other) = Tnc::loopback_pair();
+        let incoming = other.incoming();
+        let responder = thread::spawn(move || {
+            let question = incoming.recv().unwrap().unwrap();
+            // Something unrelated arrives first
+            other
+                .send_frame(
+                    &question.reply(b"noise".to_vec(), ax25::frame::ProtocolIdentifier::None),
+                )
+                .unwrap();
+            other
+                .send_frame(
+                    &question.reply(b"pong".to_vec(), ax25::frame::ProtocolIdentifier::None),
+                )
+                .unwrap();
+            other
+        });
+        let ping = Ax25Frame::new_simple_ui_frame(
+            "VK7NTK-1".parse().unwrap(),
+            "VK7NTK-2".parse().unwrap(),
+            b"ping".to_vec(),
+        );
+        let is_pong = |f: &Ax25Frame| f.info_string_lossy().as_deref() == Some("pong");
+        let timeout = Duration::from_secs(5);
+        let reply = tnc.request(&ping, is_pong, timeout).unwrap().unwrap();
+        assert_eq!(reply.destination, ping.source);
+        let _other = responder.join().unwrap();
+
+        let timeout = Duration::from_millis(100);
+        assert_eq!(tnc.request(&ping, is_pong, timeout).unwrap(), None);
+    }
+
+    #[test]
+    fn request_keeps_receive_error() {
+        use std::io::Read;
+
+        let (tnc, mut server) = tcp_kiss_pair();
+        // Hang up once the request has been sent
+        let server = thread::spawn(move || {
+            let mut buf = [0u8; 1];
+            server.read_exact(&mut buf).unwrap();
+        });
+        let ping = Ax25Frame::new_simple_ui_frame(
+            "VK7NTK-1".parse().unwrap(),
+            "VK7NTK-2".parse().unwrap(),
+            b"ping".to_vec(),
+        );
+        let err = tnc
+            .request(&ping, |_| false, Duration::from_secs(5))
+            .unwrap_err();
+        server.join().unwrap();
+        let TncError::ReceiveFrame { source } = &err else {
+            panic!("unexpected error {:?}", err);
+        };
+        let cause = source
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<Arc<TncError>>())
+            .unwrap();
+        assert_eq!(Some(source.kind()), cause.io_error_kind());
+    }
+
+    #[test]
+    fn bounded_incoming_drops_when_full() {
+        let (tnc, other) = Tnc::loopback_pair();
+        let bounded = other.incoming_bounded(2);
+        let incoming = other.incoming();
+        let frames: Vec<Ax25Frame> = (0..5u8).map(|i| test_frame(&[i])).collect();
+        for frame in &frames {
+            tnc.send_frame(frame).unwrap();
+        }
+
+        // Unbounded receivers still get everything
+        let timeout = Duration::from_secs(5);
+        for frame in &frames {
+            assert_eq!(&incoming.recv_timeout(timeout).unwrap().unwrap(), frame);
+        }
+        assert_eq!(bounded.try_recv().unwrap().unwrap(), frames[0]);
+        assert_eq!(bounded.try_recv().unwrap().unwrap(), frames[1]);
+        assert!(bounded.try_recv().is_err());
+        assert_eq!(other.stats().frames_dropped, 3);
+
+        // There is room again once the receiver catches up
+        tnc.send_frame(&frames[0]).unwrap();
+        assert_eq!(bounded.recv_timeout(timeout).unwrap().unwrap(), frames[0]);
+        assert_eq!(other.stats().frames_dropped, 3);
+    }
+
+    #[test]
+    fn kiss_over_custom_stream() {
+        use std::collections::VecDeque;
+        use std::io;
+
+        /// Reads from a shared queue, returning `WouldBlock` when it is empty, and
+        /// writes to a shared buffer.
+        struct MemoryStream {
+            input: Arc<Mutex<VecDeque<u8>>>,
+            output: Arc<Mutex<Vec<u8>>>,
+        }
+
+        impl Read for MemoryStream {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let mut input = self.input.lock().unwrap();
+                if input.is_empty() {
+                    return Err(io::ErrorKind::WouldBlock.into());
+                }
+                let n = buf.len().min(input.len());
+                for (b, c) in buf.iter_mut().zip(input.drain(..n)) {
+                    *b = c;
+                }
+                Ok(n)
+            }
+        }
+
+        impl Write for MemoryStream {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.output.lock().unwrap().extend(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let input = Arc::new(Mutex::new(VecDeque::new()));
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let tnc = Tnc::from_kiss_stream(MemoryStream {
+            input: input.clone(),
+            output: output.clone(),
+        });
+        let incoming = tnc.incoming();
+        let frame = test_frame(b"hello");
+
+        tnc.send_frame(&frame).unwrap();
+        let encoded = kiss::kiss_encode(&frame.to_bytes());
+        assert_eq!(*output.lock().unwrap(), encoded);
+
+        input.lock().unwrap().extend(&encoded);
+        let received = incoming.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(received.unwrap(), frame);
+    }
+
+    #[test]
+    fn loopback_pair_exchanges_frames() {
+        let (a, b) = Tnc::loopback_pair();
+        let frame = test_frame(b"hello");
+        // Sent before anybody is listening on the other side
+        a.send_frame(&frame).unwrap();
+        let incoming_b = b.incoming();
+        let incoming_a = a.incoming();
+        let timeout = Duration::from_secs(5);
+        assert_eq!(incoming_b.recv_timeout(timeout).unwrap().unwrap(), frame);
+
+        b.send_frame(&frame).unwrap();
+        assert_eq!(incoming_a.recv_timeout(timeout).unwrap().unwrap(), frame);
+        // A TNC does not hear its own transmissions
+        assert!(incoming_b.try_recv().is_err());
+
+        // Raw bytes are passed through untouched even if they can't be parsed
+        let raw_a = a.incoming_raw();
+        b.send_raw(&[0x01, 0x02, 0x03]).unwrap();
+        let raw = raw_a.recv_timeout(timeout).unwrap().unwrap();
+        assert_eq!(raw.bytes, [0x01, 0x02, 0x03]);
+    }
+
+    #[test]
+    fn filtered_receivers_only_get_matching_frames() {
+        let (a, b) = Tnc::loopback_pair();
+        let wanted: ax25::frame::Address = "VK7NTK-1".parse().unwrap();
+        let filtered = {
+            let wanted = wanted.clone();
+            b.incoming_filtered(move |f| f.source == wanted)
+        };
+        let everything = b.incoming();
+        let other = Ax25Frame::new_simple_ui_frame(
+            "VK7NTK-2".parse().unwrap(),
+            "IDENT".parse().unwrap(),
+            b"skip".to_vec(),
+        );
+        let frame =
+            Ax25Frame::new_simple_ui_frame(wanted, "IDENT".parse().unwrap(), b"hi".to_vec());
+        a.send_frame(&other).unwrap();
+        a.send_frame(&frame).unwrap();
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(everything.recv_timeout(timeout).unwrap().unwrap(), other);
+        assert_eq!(everything.recv_timeout(timeout).unwrap().unwrap(), frame);
+        assert_eq!(filtered.recv_timeout(timeout).unwrap().unwrap(), frame);
+        assert!(filtered.try_recv().is_err());
+    }
+
+    #[test]
+    fn control_frames_are_routed_separately() {
+        use std::io::Write;
+
+        let (tnc, mut server) = tcp_kiss_pair();
+        let incoming = tnc.incoming_raw();
+        let control = tnc.incoming_control();
+
+        let frame = test_frame(b"hello");
+        server.write_all(&[0xC0, 0x06, b'v', b'1', 0xC0]).unwrap();
+        server
+            .write_all(&kiss::kiss_encode(&frame.to_bytes()))
+            .unwrap();
+
+        let timeout = Duration::from_secs(5);
+        let received = control.recv_timeout(timeout).unwrap();
+        assert_eq!(received.command, kiss::CMD_SET_HARDWARE);
+        assert_eq!(received.data, b"v1");
+        // Only the data frame reaches the frame receivers
+        let raw = incoming.recv_timeout(timeout).unwrap().unwrap();
+        assert_eq!(raw.frame.unwrap(), frame);
     }
 }